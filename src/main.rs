@@ -1,28 +1,253 @@
-mod config;
-mod modules;
-
 use clap::{Parser, Subcommand};
-use config::Config;
-use modules::task::task_batch::TaskBatch;
-use modules::task::task_manager::TaskBatchHandler;
-use modules::{authorization::Authorization, task::task_api_client::TaskApiClient};
+use yandex_tracker_api_rust::config::Config;
+use yandex_tracker_api_rust::modules::import::csv_import::{CsvImporter, CsvMapping};
+use yandex_tracker_api_rust::modules::queue::metadata_cache::MetadataCache;
+use yandex_tracker_api_rust::modules::task::task_batch::TaskBatch;
+use yandex_tracker_api_rust::modules::task::task_batch_format::BatchFormat;
+use yandex_tracker_api_rust::modules::task::task_batch_validator::TaskBatchValidator;
+use yandex_tracker_api_rust::modules::task::task_diff::colour_enabled;
+use yandex_tracker_api_rust::modules::task::task_export::{ExportMode, ExportSelection, TaskExporter};
+use yandex_tracker_api_rust::modules::task::task_journal::{TaskJournal, RUNS_DIR};
+use yandex_tracker_api_rust::modules::task::task_manager::{ConflictStrategy, TaskBatchHandler};
+use yandex_tracker_api_rust::modules::task::task_plan::TaskBatchPlanner;
+use yandex_tracker_api_rust::modules::task::task_rollback::TaskRollback;
+use yandex_tracker_api_rust::modules::task::task_run_report::{unfinished_run, RunReport};
+use yandex_tracker_api_rust::modules::task::task_sync::{SyncState, TaskSync};
+use yandex_tracker_api_rust::modules::tracker_time::TrackerDuration;
+use yandex_tracker_api_rust::modules::worklog::worklog_timer::WorklogTimer;
+use yandex_tracker_api_rust::modules::worklog::CreatedWorklogBody;
+use yandex_tracker_api_rust::modules::{
+    authorization::Authorization, task::task_api_client::TaskApiClient,
+};
+
+use chrono::Local;
 
 use env_logger;
 use log::{error, info};
 
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Subcommand)]
 enum Commands {
     /// Run tasks
     #[command(name = "run_tasks")]
-    RunTasks,
+    RunTasks {
+        /// The batch file to run
+        #[arg(long, default_value = "tasks.json")]
+        file: PathBuf,
+
+        /// The format of the batch file, detected from its extension by default
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<BatchFormat>,
+
+        /// Set a variable of the batch file, overriding its `vars`, e.g. `--var sprint="Sprint 12"`
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+
+        /// Print the HTTP operations the run would perform without performing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Print the plan of a dry run as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+
+        /// Resume a previous run, the latest one if no run ID is given
+        #[arg(long, value_name = "RUN_ID", num_args = 0..=1, default_missing_value = "", conflicts_with = "dry_run")]
+        resume: Option<String>,
+
+        /// Start a new run even if the last run of the batch file did not finish
+        #[arg(long, conflicts_with_all = ["dry_run", "resume"])]
+        new_run: bool,
+
+        /// Keep processing independent entries after a failure instead of stopping
+        #[arg(long, conflicts_with = "dry_run")]
+        keep_going: bool,
+
+        /// Undo everything the run did if it fails
+        #[arg(long, conflicts_with_all = ["dry_run", "keep_going"])]
+        atomic: bool,
+
+        /// Move the issues created by a rolled back run to this queue instead of closing them
+        #[arg(long, value_name = "QUEUE", requires = "atomic")]
+        trash_queue: Option<String>,
+
+        /// Fail the updates of issues changed by someone else during the run, or rebase them when
+        /// the changes do not overlap
+        #[arg(long, value_name = "STRATEGY", default_value = "fail", conflicts_with = "dry_run")]
+        on_conflict: ConflictStrategy,
+    },
+
+    /// Bring existing issues to the state declared in a state file, creating the missing ones
+    #[command(name = "apply")]
+    Apply {
+        /// The state file to apply
+        #[arg(long, default_value = "state.yaml")]
+        file: PathBuf,
+
+        /// The format of the state file, detected from its extension by default
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<BatchFormat>,
+
+        /// Print the changes without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Undo a run: close the issues it created and restore the fields of the issues it updated
+    #[command(name = "rollback")]
+    Rollback {
+        /// The ID of the run, the name of its directory in **runs**
+        run_id: String,
+
+        /// Move the issues created by the run to this queue instead of closing them
+        #[arg(long, value_name = "QUEUE")]
+        trash_queue: Option<String>,
+    },
+
+    /// Print the HTTP operations `run_tasks` would perform, same as `run_tasks --dry-run`
+    #[command(name = "plan")]
+    Plan {
+        /// The batch file to plan
+        #[arg(long, default_value = "tasks.json")]
+        file: PathBuf,
+
+        /// The format of the batch file, detected from its extension by default
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<BatchFormat>,
+
+        /// Set a variable of the batch file, overriding its `vars`, e.g. `--var sprint="Sprint 12"`
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+
+        /// Print the plan as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Validate tasks against the queue metadata without running them
+    #[command(name = "validate")]
+    Validate {
+        /// The batch file to validate
+        #[arg(long, default_value = "tasks.json")]
+        file: PathBuf,
+
+        /// The format of the batch file, detected from its extension by default
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<BatchFormat>,
+
+        /// Set a variable of the batch file, overriding its `vars`, e.g. `--var sprint="Sprint 12"`
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+    },
+
+    /// Import created tasks from a CSV file, e.g. a spreadsheet export, into a batch file
+    #[command(name = "import_csv")]
+    ImportCsv {
+        /// The CSV file to import
+        file: PathBuf,
+
+        /// The file mapping the columns to task fields, e.g. **mapping.toml**
+        #[arg(long)]
+        mapping: PathBuf,
+
+        /// The batch file to write, in the format of its extension
+        #[arg(long, default_value = "tasks.json")]
+        output: PathBuf,
+
+        /// Overwrite the batch file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Export existing issues into a batch file, to edit them in bulk or to clone them
+    #[command(name = "export")]
+    Export {
+        /// The keys of the issues to export
+        keys: Vec<String>,
+
+        /// Export the issues found by a query, e.g. `Queue: TEST Status: open`
+        #[arg(long)]
+        query: Option<String>,
+
+        /// Write the issues as `updated` entries, or as `created` entries without keys
+        #[arg(long, value_name = "MODE", default_value = "updated")]
+        mode: ExportMode,
+
+        /// The queue of the created entries, the queue of each issue by default
+        #[arg(long, value_name = "QUEUE")]
+        queue: Option<String>,
+
+        /// Also export the subtasks of the issues, at every depth
+        #[arg(long)]
+        subtasks: bool,
+
+        /// The batch file to write, in the format of its extension
+        #[arg(long, default_value = "tasks.json")]
+        output: PathBuf,
+
+        /// Overwrite the batch file if it exists
+        #[arg(long)]
+        force: bool,
+    },
 
     #[command(name = "template_tasks")]
-    TemplateTasks,
+    TemplateTasks {
+        /// The format of the template: json, jsonc, yaml or toml
+        #[arg(long, value_name = "FORMAT", default_value = "json")]
+        format: BatchFormat,
+    },
 
     #[command(name = "template_config")]
     TemplateConfig,
+
+    /// Log work on issues
+    #[command(name = "worklog", subcommand)]
+    Worklog(WorklogCommands),
+
+    /// Track time on an issue with a local timer
+    #[command(name = "timer", subcommand)]
+    Timer(TimerCommands),
+}
+
+#[derive(Debug, Subcommand)]
+enum WorklogCommands {
+    /// Log time spent on an issue, e.g. `worklog add TASK-1 1h30m`
+    #[command(name = "add")]
+    Add {
+        /// The key of the issue
+        issue: String,
+
+        /// The time spent, e.g. `1h30m`, `2d` or `PT45M`
+        duration: String,
+
+        /// A comment on the work
+        #[arg(long)]
+        comment: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum TimerCommands {
+    /// Start a timer for an issue
+    #[command(name = "start")]
+    Start {
+        /// The key of the issue
+        issue: String,
+    },
+
+    /// Stop the running timer and log the tracked time
+    #[command(name = "stop")]
+    Stop {
+        /// A comment on the work
+        #[arg(long)]
+        comment: Option<String>,
+    },
+
+    /// Show the running timer
+    #[command(name = "status")]
+    Status,
 }
 
 /// A simple CLI
@@ -53,46 +278,504 @@ async fn main() {
                 Err(err) => println!("Err: {:#?}", err),
             }
         },
-        Commands::TemplateTasks => {
-            match TaskBatch::default().save_to_file() {
-                Ok(_) => println!("Config file created!"),
+        Commands::TemplateTasks { format } => {
+            match TaskBatch::default().save_to_file(format) {
+                Ok(path) => println!("Template {} created!", path.display()),
                 Err(err) => println!("Err: {:#?}", err),
             }
         },
-        Commands::RunTasks => {
+        Commands::RunTasks { file, format, vars, dry_run: true, json, .. }
+        | Commands::Plan { file, format, vars, json } => {
+            let format = format.unwrap_or_else(|| BatchFormat::from_path(&file));
+            plan_tasks(&file, format, &vars.into_iter().collect(), json).await
+        }
+        Commands::RunTasks {
+            file,
+            format,
+            vars,
+            dry_run: false,
+            resume,
+            new_run,
+            keep_going,
+            atomic,
+            trash_queue,
+            on_conflict,
+            ..
+        } => {
             println!("Running tasks...");
+            let format = format.unwrap_or_else(|| BatchFormat::from_path(&file));
             let task_batch: TaskBatch;
-            match TaskBatch::create_from_path(Path::new("tasks.json")) {
+            match TaskBatch::create_from_path_with_vars(&file, format, &vars.into_iter().collect()) {
                 Ok(batch) => task_batch = batch,
                 Err(err) => {
-                    error!("{}", err);
+                    println!("{}", err);
                     return;
                 }
             }
 
-            let mut authenticator = Authorization::new();
-            let token_response = match authenticator.authorize().await {
-                Ok(token_response) => {
-                    info!("Access Token: {}", token_response.access_token);
-                    token_response
-                }
-                Err(err) => {
-                    error!("Authorization error: {}", err);
-                    return;
+            let api_client = match authorized_client().await {
+                Some(api_client) => api_client,
+                None => return,
+            };
+
+            if !validate_batch(&MetadataCache::new(api_client.clone()), &task_batch).await {
+                return;
+            }
+
+            if resume.is_none() && !new_run {
+                match unfinished_run(Path::new(RUNS_DIR), &file) {
+                    Ok(Some(run_id)) => {
+                        return println!(
+                            "Run {} of {} did not finish. Resume it with `run_tasks --resume {}`, or start over \
+                             with `--new-run`: tasks without a `unique` would be created again",
+                            run_id,
+                            file.display(),
+                            run_id
+                        )
+                    }
+                    Ok(None) => {}
+                    Err(err) => return println!("Err: {}", err),
                 }
+            }
+
+            let mut journal = match open_journal(resume) {
+                Ok(journal) => journal,
+                Err(err) => return println!("Err: {}", err),
+            };
+            if let Err(err) = journal.record_batch(&file) {
+                return println!("Err: {}", err);
+            }
+
+            let batch_handler = TaskBatchHandler::new(api_client.clone())
+                .with_keep_going(keep_going)
+                .with_conflict_strategy(on_conflict);
+            let result = batch_handler.process_tasks(&task_batch, &mut journal).await;
+
+            let report = RunReport::new(journal.run_id(), &task_batch, journal.entries());
+            if let Err(err) = report.save(journal.dir()) {
+                println!("Err: {}", err);
+            }
+            println!("{}", report);
+            println!("Journal and report saved in {}", journal.dir().display());
+
+            if let Err(err) = &result {
+                println!("Error: {}", err);
+            }
+
+            if atomic && result.is_err() {
+                return rollback_run(api_client, &mut journal, trash_queue).await;
+            }
+
+            match task_batch.save_failed_entries(journal.dir(), format, journal.entries()) {
+                Ok(Some(path)) => println!(
+                    "Failed entries saved in {}, fix them and run `run_tasks --file {}`",
+                    path.display(),
+                    path.display()
+                ),
+                Ok(None) => {}
+                Err(err) => println!("Err: {}", err),
+            }
+        }
+        Commands::Apply { file, format, dry_run } => {
+            let format = format.unwrap_or_else(|| BatchFormat::from_path(&file));
+            apply_state(&file, format, dry_run).await
+        }
+        Commands::Rollback { run_id, trash_queue } => {
+            let mut journal = match TaskJournal::open(Path::new(RUNS_DIR), &run_id) {
+                Ok(journal) => journal,
+                Err(err) => return println!("Err: {}", err),
+            };
+
+            let api_client = match authorized_client().await {
+                Some(api_client) => api_client,
+                None => return,
+            };
+
+            rollback_run(api_client, &mut journal, trash_queue).await
+        }
+        Commands::Validate { file, format, vars } => {
+            let format = format.unwrap_or_else(|| BatchFormat::from_path(&file));
+            let task_batch = match TaskBatch::create_from_path_with_vars(&file, format, &vars.into_iter().collect()) {
+                Ok(batch) => batch,
+                Err(err) => return println!("{}", err),
             };
 
-            let batch_handler = TaskBatchHandler::new(TaskApiClient::new(
+            let api_client = match authorized_client().await {
+                Some(api_client) => api_client,
+                None => return,
+            };
+
+            if validate_batch(&MetadataCache::new(api_client), &task_batch).await {
+                println!("Tasks are valid!");
+            }
+        }
+        Commands::ImportCsv { file, mapping, output, force } => {
+            let task_batch = match CsvMapping::load(&mapping) {
+                Ok(mapping) => CsvImporter::new(mapping).import(&file),
+                Err(err) => return println!("{}", err),
+            };
+            match task_batch {
+                Ok(task_batch) => save_imported_batch(&task_batch, &output, force),
+                Err(err) => println!("{}", err),
+            }
+        }
+        Commands::Export { keys, query, mode, queue, subtasks, output, force } => {
+            let selection = ExportSelection { keys, query, subtasks };
+            export_tasks(selection, mode, queue, &output, force).await
+        }
+        Commands::Worklog(WorklogCommands::Add {
+            issue,
+            duration,
+            comment,
+        }) => add_worklog(issue, duration, comment).await,
+        Commands::Timer(TimerCommands::Start { issue }) => match WorklogTimer::start(issue) {
+            Ok(timer) => println!("Timer started for {} at {}", timer.issue, timer.started_at),
+            Err(err) => println!("Err: {}", err),
+        },
+        Commands::Timer(TimerCommands::Stop { comment }) => stop_timer(comment).await,
+        Commands::Timer(TimerCommands::Status) => match WorklogTimer::load_from_file() {
+            Ok(timer) => println!(
+                "Timer running for {}: {}",
+                timer.issue,
+                timer.elapsed(Local::now().fixed_offset())
+            ),
+            Err(err) => println!("{}", err),
+        },
+    }
+}
+
+/// Parses a `--var` argument of the form `name=value`.
+fn parse_var(var: &str) -> Result<(String, String), String> {
+    match var.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.trim().to_string(), value.to_string())),
+        _ => Err(format!("expected `NAME=VALUE`, got `{}`", var)),
+    }
+}
+
+/// Authorizes the user and creates the API client.
+///
+/// # Returns
+///
+/// * `Some(TaskApiClient)` - if the authorization was successful.
+/// * `None` - if the authorization failed; the error is logged.
+async fn authorized_client() -> Option<TaskApiClient> {
+    let mut authenticator = Authorization::new();
+    match authenticator.authorize().await {
+        Ok(token_response) => {
+            info!("Access Token: {}", token_response.access_token);
+            Some(TaskApiClient::new(
                 token_response.access_token,
                 Config::global().organization_id.clone(),
-            ));
+            ))
+        }
+        Err(err) => {
+            error!("Authorization error: {}", err);
+            None
+        }
+    }
+}
+
+/// Validates a batch against the live queue metadata and prints every problem found.
+///
+/// # Arguments
+///
+/// * `cache` - The metadata the batch is validated against.
+/// * `task_batch` - The batch to validate.
+///
+/// # Returns
+///
+/// * `true` - if the batch is valid.
+/// * `false` - if problems were found or the metadata could not be loaded.
+async fn validate_batch(cache: &MetadataCache, task_batch: &TaskBatch) -> bool {
+    match TaskBatchValidator::new(cache).validate(task_batch).await {
+        Ok(report) if report.is_empty() => true,
+        Ok(report) => {
+            println!("Tasks are invalid:\n{}", report);
+            false
+        }
+        Err(err) => {
+            error!("Validation error: {}", err);
+            false
+        }
+    }
+}
 
-            let str = format!("Success: {:#?}", task_batch);
+/// Starts the journal of a new run, or reopens the journal of a run to resume.
+///
+/// # Arguments
+///
+/// * `resume` - The ID of the run to resume, empty for the latest run, or `None` for a new run.
+fn open_journal(resume: Option<String>) -> Result<TaskJournal, std::io::Error> {
+    let runs_dir = Path::new(RUNS_DIR);
+    let run_id = match resume {
+        None => return TaskJournal::create(runs_dir),
+        Some(run_id) if run_id.is_empty() => TaskJournal::latest_run_id(runs_dir)?
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No run to resume"))?,
+        Some(run_id) => run_id,
+    };
 
-            match batch_handler.process_tasks(task_batch).await {
-                Ok(_) => println!("{:#?}", str),
-                Err(err) => println!("Error: {:#?}", err),
-            };
+    println!("Resuming run {}", run_id);
+    TaskJournal::open(runs_dir, &run_id)
+}
+
+/// Undoes a run and prints every step.
+///
+/// # Arguments
+///
+/// * `api_client` - The API client used for task operations.
+/// * `journal` - The journal of the run.
+/// * `trash_queue` - The queue the created issues are moved to, or `None` to close them.
+async fn rollback_run(api_client: TaskApiClient, journal: &mut TaskJournal, trash_queue: Option<String>) {
+    let steps = TaskRollback::steps(journal.entries());
+    if steps.is_empty() {
+        return println!("Nothing to roll back in run {}", journal.run_id());
+    }
+
+    println!("Rolling back run {}...", journal.run_id());
+    for step in &steps {
+        println!("  {}", step);
+    }
+
+    match TaskRollback::new(api_client, trash_queue).rollback(journal).await {
+        Ok(()) => println!("Run {} rolled back", journal.run_id()),
+        Err(err) => println!(
+            "Error: {}\nThe rollback is incomplete, run `rollback {}` again to retry the failed steps",
+            err,
+            journal.run_id()
+        ),
+    }
+}
+
+/// Prints the changes that bring the issues to a state file, then applies them.
+///
+/// # Arguments
+///
+/// * `file` - The state file.
+/// * `format` - The format of the state file.
+/// * `dry_run` - Whether to only print the changes.
+async fn apply_state(file: &Path, format: BatchFormat, dry_run: bool) {
+    let state = match SyncState::load(file, format) {
+        Ok(state) => state,
+        Err(err) => return println!("{}", err),
+    };
+
+    let api_client = match authorized_client().await {
+        Some(api_client) => api_client,
+        None => return,
+    };
+
+    let sync = TaskSync::new(api_client);
+    let plan = match sync.plan(&state, &Config::global().default_queue).await {
+        Ok(plan) => plan,
+        Err(err) => return println!("{}", err),
+    };
+    if colour_enabled() {
+        print!("{:#}", plan);
+    } else {
+        print!("{}", plan);
+    }
+    if dry_run || plan.is_empty() {
+        return;
+    }
+
+    let mut journal = match TaskJournal::create(Path::new(RUNS_DIR)) {
+        Ok(journal) => journal,
+        Err(err) => return println!("Err: {}", err),
+    };
+    match sync.apply(&plan, &mut journal).await {
+        Ok(()) => println!("\nApplied, journal saved in {}", journal.dir().display()),
+        Err(err) => println!(
+            "\nError: {}\nApply again to retry the remaining changes, or run `rollback {}` to undo the applied ones",
+            err,
+            journal.run_id()
+        ),
+    }
+}
+
+/// Validates a batch file and prints the HTTP operations running it would perform.
+///
+/// Nothing is written to the tracker and the batch file is left untouched.
+///
+/// # Arguments
+///
+/// * `file` - The batch file, **tasks.json** by default.
+/// * `format` - The format of the batch file.
+/// * `vars` - The variables set on the command line.
+/// * `json` - Whether to print the plan as JSON instead of text.
+async fn plan_tasks(file: &Path, format: BatchFormat, vars: &HashMap<String, String>, json: bool) {
+    let task_batch = match TaskBatch::create_from_path_with_vars(file, format, vars) {
+        Ok(batch) => batch,
+        Err(err) => return println!("{}", err),
+    };
+
+    let api_client = match authorized_client().await {
+        Some(api_client) => api_client,
+        None => return,
+    };
+
+    let cache = MetadataCache::new(api_client);
+    if !validate_batch(&cache, &task_batch).await {
+        return;
+    }
+
+    match TaskBatchPlanner::new(&cache).plan(&task_batch).await {
+        Ok(plan) if json => match serde_json::to_string_pretty(&plan) {
+            Ok(plan) => println!("{}", plan),
+            Err(err) => println!("Error: {}", err),
+        },
+        Ok(plan) if colour_enabled() => print!("{:#}", plan),
+        Ok(plan) => print!("{}", plan),
+        Err(err) => println!("Error: {}", err),
+    }
+}
+
+/// Writes a batch to a batch file, printing the error if it cannot be written.
+///
+/// # Arguments
+///
+/// * `task_batch` - The batch to write.
+/// * `output` - The batch file, written in the format of its extension.
+/// * `force` - Whether to overwrite the batch file if it exists.
+///
+/// # Returns
+///
+/// * `true` - if the batch file was written.
+/// * `false` - otherwise.
+fn save_batch(task_batch: &TaskBatch, output: &Path, force: bool) -> bool {
+    if output.exists() && !force {
+        println!("Err: {} already exists, pass --force to overwrite it", output.display());
+        return false;
+    }
+
+    let format = BatchFormat::from_path(output);
+    let result = format
+        .to_string(task_batch)
+        .and_then(|text| std::fs::write(output, text).map_err(|err| err.into()));
+    match result {
+        Ok(()) => true,
+        Err(err) => {
+            println!("Err: {}", err);
+            false
+        }
+    }
+}
+
+/// Writes an imported batch to a batch file and prints how to check it.
+///
+/// # Arguments
+///
+/// * `task_batch` - The imported batch.
+/// * `output` - The batch file, written in the format of its extension.
+/// * `force` - Whether to overwrite the batch file if it exists.
+fn save_imported_batch(task_batch: &TaskBatch, output: &Path, force: bool) {
+    if save_batch(task_batch, output, force) {
+        println!(
+            "Imported {} tasks into {}, check them with `validate --file {}`",
+            task_batch.created_entries().len(),
+            output.display(),
+            output.display()
+        );
+    }
+}
+
+/// Exports existing issues to a batch file.
+///
+/// # Arguments
+///
+/// * `selection` - The issues to export.
+/// * `mode` - Whether the issues are written as `updated` or `created` entries.
+/// * `queue` - The queue of the created entries, the queue of each issue if `None`.
+/// * `output` - The batch file, written in the format of its extension.
+/// * `force` - Whether to overwrite the batch file if it exists.
+async fn export_tasks(selection: ExportSelection, mode: ExportMode, queue: Option<String>, output: &Path, force: bool) {
+    if selection.keys.is_empty() && selection.query.is_none() {
+        return println!("Err: pass the keys of the issues to export or a --query");
+    }
+
+    let api_client = match authorized_client().await {
+        Some(api_client) => api_client,
+        None => return,
+    };
+
+    let task_batch = match TaskExporter::new(api_client).export(&selection, mode, queue.as_deref()).await {
+        Ok(task_batch) => task_batch,
+        Err(err) => return println!("Error: {}", err),
+    };
+
+    let count = match mode {
+        ExportMode::Updated => task_batch.updated.len(),
+        ExportMode::Created => task_batch.created_entries().len(),
+    };
+    if save_batch(&task_batch, output, force) {
+        println!(
+            "Exported {} issues into {} as {} entries, check them with `plan --file {}`",
+            count,
+            output.display(),
+            mode,
+            output.display()
+        );
+    }
+}
+
+/// Logs time spent on an issue, ending now.
+///
+/// # Arguments
+///
+/// * `issue` - The key of the issue.
+/// * `duration` - The time spent, e.g. `1h30m`.
+/// * `comment` - A comment on the work (optional).
+async fn add_worklog(issue: String, duration: String, comment: Option<String>) {
+    let duration = match TrackerDuration::parse(&duration) {
+        Ok(duration) if !duration.is_zero() => duration,
+        Ok(_) => return println!("Err: the duration must not be zero"),
+        Err(err) => return println!("Err: {}", err),
+    };
+
+    let api_client = match authorized_client().await {
+        Some(api_client) => api_client,
+        None => return,
+    };
+
+    let start = Local::now().fixed_offset() - chrono::Duration::seconds(duration.as_seconds() as i64);
+    let worklog = CreatedWorklogBody {
+        start,
+        duration,
+        comment,
+    };
+
+    match api_client.add_worklog(&issue, worklog).await {
+        Ok(worklog) => println!("Logged {} on {}", worklog.duration, worklog.issue.key),
+        Err(err) => println!("Error: {}", err),
+    }
+}
+
+/// Stops the running timer and logs the tracked time on its issue.
+///
+/// The timer file is removed only after the worklog record is created,
+/// so a failed request can be retried with `timer stop`.
+///
+/// # Arguments
+///
+/// * `comment` - A comment on the work (optional).
+async fn stop_timer(comment: Option<String>) {
+    let timer = match WorklogTimer::load_from_file() {
+        Ok(timer) => timer,
+        Err(err) => return println!("Err: {}", err),
+    };
+
+    let api_client = match authorized_client().await {
+        Some(api_client) => api_client,
+        None => return,
+    };
+
+    let worklog = timer.to_worklog(Local::now().fixed_offset(), comment);
+    match api_client.add_worklog(&timer.issue, worklog).await {
+        Ok(worklog) => {
+            if let Err(err) = WorklogTimer::remove_file() {
+                println!("Err: {}", err);
+            }
+            println!("Logged {} on {}", worklog.duration, worklog.issue.key);
         }
+        Err(err) => println!("Error: {}", err),
     }
 }
@@ -1,2 +1,7 @@
 pub mod authorization;
-pub mod task;
\ No newline at end of file
+pub mod import;
+pub mod queue;
+pub mod task;
+pub mod tracker_time;
+pub mod user;
+pub mod worklog;
\ No newline at end of file
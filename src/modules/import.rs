@@ -0,0 +1,3 @@
+pub mod csv_import;
+pub mod markdown_import;
+pub mod import_error;
@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use csv::{ReaderBuilder, StringRecord, Trim};
+use serde::{Deserialize, Serialize};
+
+use super::import_error::{ImportError, RowProblem, RowProblems};
+use crate::config::Config;
+use crate::modules::task::task_batch::TaskBatch;
+use crate::modules::task::task_batch_format::BatchFormat;
+use crate::modules::task::task_fields::IssueFields;
+use crate::modules::task::CreatedTaskInfo;
+
+/// The fields of a created task that can be read from a column.
+///
+/// In `columns` every field holds the header of its column; in `defaults` it holds the value
+/// used when the column is missing or the cell is empty.
+///
+/// # Fields
+///
+/// * `id` - The identifier of the row other rows refer to in their `parent` column (optional).
+/// * `queue` - The queue of the task (optional).
+/// * `summary` - The summary of the task, required in `columns`.
+/// * `description` - The description of the task (optional).
+/// * `task_type` - The type of the task (optional).
+/// * `priority` - The priority of the task (optional).
+/// * `assignee` - The user assigned to the task (optional).
+/// * `sprint` - The sprints of the task, separated by commas (optional).
+/// * `parent` - The `id` of the row holding the parent task, or the key of an existing issue (optional).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct CsvFields {
+    pub id: Option<String>,
+    pub queue: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub task_type: Option<String>,
+    pub priority: Option<String>,
+    pub assignee: Option<String>,
+    pub sprint: Option<String>,
+    pub parent: Option<String>,
+}
+
+impl CsvFields {
+    /// Lists the fields by name, e.g. `("type", Some(...))`.
+    fn entries(&self) -> [(&'static str, &Option<String>); 9] {
+        [
+            ("id", &self.id),
+            ("queue", &self.queue),
+            ("summary", &self.summary),
+            ("description", &self.description),
+            ("type", &self.task_type),
+            ("priority", &self.priority),
+            ("assignee", &self.assignee),
+            ("sprint", &self.sprint),
+            ("parent", &self.parent),
+        ]
+    }
+}
+
+/// Represents how the columns of a CSV file map to the fields of created tasks.
+///
+/// # Fields
+///
+/// * `delimiter` - The character separating the cells, `,` by default.
+/// * `columns` - The header of the column of every imported field.
+/// * `defaults` - The values of fields whose column is missing or whose cell is empty.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CsvMapping {
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    pub columns: CsvFields,
+    #[serde(default)]
+    pub defaults: CsvFields,
+}
+
+/// Provides the default value for the `delimiter` field in `CsvMapping`.
+fn default_delimiter() -> char {
+    ','
+}
+
+impl CsvMapping {
+    /// Loads a mapping file, written in any batch format, e.g. **mapping.toml**.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the mapping file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the mapping, or `ImportError` if the file cannot be read or is invalid.
+    pub fn load(path: &Path) -> Result<CsvMapping, ImportError> {
+        let source = fs::read_to_string(path)?;
+        let document = BatchFormat::from_path(path)
+            .parse(&source)
+            .map_err(|err| ImportError::MappingError(err.message))?;
+        let mapping: CsvMapping = serde_path_to_error::deserialize(document)
+            .map_err(|err| ImportError::MappingError(format!("{} at `{}`", err.inner(), err.path())))?;
+
+        if !mapping.delimiter.is_ascii() {
+            return Err(ImportError::MappingError("`delimiter` must be an ASCII character".to_string()));
+        }
+        if mapping.columns.summary.is_none() {
+            return Err(ImportError::MappingError("`columns` must name the `summary` column".to_string()));
+        }
+        Ok(mapping)
+    }
+}
+
+/// Imports created tasks from CSV files, such as spreadsheet exports.
+pub struct CsvImporter {
+    mapping: CsvMapping,
+}
+
+/// A task read from a row, before the rows are nested under their parents.
+struct ImportedRow {
+    row: usize,
+    id: Option<String>,
+    parent: Option<String>,
+    task: CreatedTaskInfo,
+}
+
+impl CsvImporter {
+    /// Creates a new `CsvImporter` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `mapping` - The mapping of the columns to the fields of the tasks.
+    pub fn new(mapping: CsvMapping) -> Self {
+        CsvImporter { mapping }
+    }
+
+    /// Imports the rows of a CSV file as a batch of created tasks.
+    ///
+    /// Every row becomes a task, in file order, and rows whose `parent` names the `id` of another
+    /// row become subtasks of that row's task. Empty rows are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the CSV file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the batch, or `ImportError::InvalidRows` with every bad row.
+    pub fn import(&self, path: &Path) -> Result<TaskBatch, ImportError> {
+        self.import_from(fs::File::open(path)?, path)
+    }
+
+    /// Imports the rows of CSV data as a batch of created tasks, see `import`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The CSV data.
+    /// * `file` - The path reported with the bad rows.
+    pub fn import_from<R: Read>(&self, reader: R, file: &Path) -> Result<TaskBatch, ImportError> {
+        let mut reader = ReaderBuilder::new()
+            .delimiter(self.mapping.delimiter as u8)
+            .trim(Trim::All)
+            .from_reader(reader);
+        let mut problems = Vec::new();
+
+        let header = reader.headers().cloned().unwrap_or_else(|err| {
+            problems.push(RowProblem {
+                row: 1,
+                message: err.to_string(),
+            });
+            StringRecord::new()
+        });
+        let mut columns: HashMap<&str, usize> = HashMap::new();
+        for (field, column) in self.mapping.columns.entries() {
+            let Some(column) = column else {
+                continue;
+            };
+            match header.iter().position(|name| name == column) {
+                Some(index) => {
+                    columns.insert(field, index);
+                }
+                None => problems.push(RowProblem {
+                    row: 1,
+                    message: format!("No column `{}` in the header, mapped to `{}`", column, field),
+                }),
+            }
+        }
+        if !problems.is_empty() {
+            return Err(ImportError::InvalidRows(RowProblems {
+                file: file.to_path_buf(),
+                problems,
+            }));
+        }
+
+        let mut rows = Vec::new();
+        for (index, record) in reader.records().enumerate() {
+            let row = index + 2;
+            match record {
+                Ok(record) if record.iter().all(str::is_empty) => {}
+                Ok(record) => match self.read_row(row, &record, &columns) {
+                    Ok(imported) => rows.push(imported),
+                    Err(message) => problems.push(RowProblem { row, message }),
+                },
+                Err(err) => problems.push(RowProblem {
+                    row,
+                    message: match err.kind() {
+                        csv::ErrorKind::UnequalLengths { expected_len, len, .. } => {
+                            format!("Row has {} cells, the header has {}", len, expected_len)
+                        }
+                        _ => err.to_string(),
+                    },
+                }),
+            }
+        }
+
+        let parents = resolve_parents(&mut rows, &mut problems);
+        if !problems.is_empty() {
+            problems.sort_by_key(|problem| problem.row);
+            return Err(ImportError::InvalidRows(RowProblems {
+                file: file.to_path_buf(),
+                problems,
+            }));
+        }
+
+        Ok(TaskBatch {
+            created: nest_rows(rows, &parents),
+            updated: Vec::new(),
+            links: Vec::new(),
+            resolved: HashMap::new(),
+            ids: HashMap::new(),
+        })
+    }
+
+    /// Reads a task from a row.
+    ///
+    /// # Returns
+    ///
+    /// The row, or the description of its problem.
+    fn read_row(&self, row: usize, record: &StringRecord, columns: &HashMap<&str, usize>) -> Result<ImportedRow, String> {
+        let defaults: HashMap<&str, &Option<String>> = self.mapping.defaults.entries().into_iter().collect();
+        let cell = |field: &str| -> Option<String> {
+            columns
+                .get(field)
+                .and_then(|index| record.get(*index))
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .or_else(|| defaults[field].clone())
+        };
+
+        let summary = cell("summary").ok_or_else(|| "Summary cannot be empty".to_string())?;
+        let queue = cell("queue").unwrap_or_else(|| Config::global().default_queue.clone());
+        let sprint = cell("sprint")
+            .map(|sprints| {
+                sprints
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|sprint| !sprint.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(ImportedRow {
+            row,
+            id: cell("id"),
+            parent: cell("parent"),
+            task: CreatedTaskInfo {
+                queue,
+                summary,
+                parent: None,
+                epic: None,
+                description: cell("description"),
+                sprint,
+                task_type: cell("type"),
+                priority: cell("priority"),
+                followers: Vec::new(),
+                tags: Vec::new(),
+                components: Vec::new(),
+                affected_versions: Vec::new(),
+                fix_versions: Vec::new(),
+                assignee: cell("assignee"),
+                author: None,
+                unique: None,
+                id: None,
+                attachment_ids: Vec::new(),
+                description_attachment_ids: Vec::new(),
+                attachments: Vec::new(),
+                description_attachments: Vec::new(),
+                links: Vec::new(),
+                checklist: Vec::new(),
+                comments: Vec::new(),
+                subtasks: Vec::new(),
+                fields: IssueFields::default(),
+                error: None,
+            },
+        })
+    }
+}
+
+/// Finds the row holding the parent of every row.
+///
+/// A `parent` naming the `id` of a row refers to that row; any other `parent` is the key of an
+/// existing issue and is kept as the `parent` of the task. Duplicate ids and parents forming a
+/// cycle are recorded as problems.
+///
+/// # Returns
+///
+/// The position of the parent row of every row, if any.
+fn resolve_parents(rows: &mut [ImportedRow], problems: &mut Vec<RowProblem>) -> Vec<Option<usize>> {
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    for (index, imported) in rows.iter().enumerate() {
+        let Some(id) = &imported.id else {
+            continue;
+        };
+        match ids.get(id) {
+            Some(first) => problems.push(RowProblem {
+                row: imported.row,
+                message: format!("Id `{}` is already used by row {}", id, rows[*first].row),
+            }),
+            None => {
+                ids.insert(id.clone(), index);
+            }
+        }
+    }
+
+    let mut parents = Vec::with_capacity(rows.len());
+    for imported in rows.iter_mut() {
+        let parent = imported.parent.as_ref().and_then(|parent| ids.get(parent).copied());
+        if parent.is_none() {
+            imported.task.parent = imported.parent.clone();
+        }
+        parents.push(parent);
+    }
+
+    for start in 0..rows.len() {
+        let mut cycle = vec![start];
+        let mut current = parents[start];
+        while let Some(index) = current {
+            if index == start {
+                if cycle.iter().all(|member| *member >= start) {
+                    let members: Vec<String> = cycle
+                        .iter()
+                        .chain([&start])
+                        .map(|member| format!("row {}", rows[*member].row))
+                        .collect();
+                    problems.push(RowProblem {
+                        row: rows[start].row,
+                        message: format!("Parents form a cycle: {}", members.join(" -> ")),
+                    });
+                }
+                break;
+            }
+            if cycle.contains(&index) {
+                break;
+            }
+            cycle.push(index);
+            current = parents[index];
+        }
+    }
+    parents
+}
+
+/// Nests every row under the row of its parent, keeping the file order.
+fn nest_rows(rows: Vec<ImportedRow>, parents: &[Option<usize>]) -> Vec<CreatedTaskInfo> {
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); rows.len()];
+    let mut roots = Vec::new();
+    for (index, parent) in parents.iter().enumerate() {
+        match parent {
+            Some(parent) => children[*parent].push(index),
+            None => roots.push(index),
+        }
+    }
+
+    let mut tasks: Vec<Option<CreatedTaskInfo>> = rows.into_iter().map(|imported| Some(imported.task)).collect();
+    roots.into_iter().map(|index| take_nested(index, &mut tasks, &children)).collect()
+}
+
+/// Takes the task of a row out of the list, with its subtasks.
+fn take_nested(index: usize, tasks: &mut [Option<CreatedTaskInfo>], children: &[Vec<usize>]) -> CreatedTaskInfo {
+    let mut task = tasks[index].take().unwrap_or_default();
+    task.subtasks = children[index].iter().map(|child| take_nested(*child, tasks, children)).collect();
+    task
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping() -> CsvMapping {
+        CsvMapping {
+            delimiter: ';',
+            columns: CsvFields {
+                id: Some("ID".to_string()),
+                summary: Some("Title".to_string()),
+                description: Some("Details".to_string()),
+                task_type: Some("Type".to_string()),
+                sprint: Some("Sprint".to_string()),
+                parent: Some("Parent".to_string()),
+                ..CsvFields::default()
+            },
+            defaults: CsvFields {
+                queue: Some("TEST".to_string()),
+                task_type: Some("task".to_string()),
+                ..CsvFields::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_import_csv() {
+        let csv = "ID;Title;Details;Type;Sprint;Parent\n\
+                   2;Write tests;;;Sprint 1, Sprint 2;1\n\
+                   1;Feature;\"Line one\nLine two\";epic;;\n\
+                   ;;;;;\n\
+                   3;Hotfix;;bug;;TEST-42\n";
+
+        let task_batch = CsvImporter::new(mapping())
+            .import_from(csv.as_bytes(), Path::new("tasks.csv"))
+            .unwrap();
+
+        assert_eq!(task_batch.created.len(), 2);
+        let feature = &task_batch.created[0];
+        assert_eq!(feature.summary, "Feature");
+        assert_eq!(feature.queue, "TEST");
+        assert_eq!(feature.description.as_deref(), Some("Line one\nLine two"));
+        assert_eq!(feature.task_type.as_deref(), Some("epic"));
+        assert_eq!(feature.subtasks[0].summary, "Write tests");
+        assert_eq!(feature.subtasks[0].task_type.as_deref(), Some("task"));
+        assert_eq!(feature.subtasks[0].sprint, vec!["Sprint 1", "Sprint 2"]);
+        assert_eq!(task_batch.created[1].parent.as_deref(), Some("TEST-42"));
+        assert!(task_batch.is_valid());
+    }
+
+    #[test]
+    fn test_import_csv_bad_rows() {
+        let csv = "ID;Title;Details;Type;Sprint;Parent\n\
+                   1;Feature;;;;3\n\
+                   2;;;;;\n\
+                   3;Story;;;;1\n\
+                   3;Bug;;;\n";
+
+        let Err(ImportError::InvalidRows(rows)) =
+            CsvImporter::new(mapping()).import_from(csv.as_bytes(), Path::new("tasks.csv"))
+        else {
+            panic!("the rows should be invalid");
+        };
+
+        assert_eq!(
+            rows.problems,
+            vec![
+                RowProblem {
+                    row: 2,
+                    message: "Parents form a cycle: row 2 -> row 4 -> row 2".to_string()
+                },
+                RowProblem {
+                    row: 3,
+                    message: "Summary cannot be empty".to_string()
+                },
+                RowProblem {
+                    row: 5,
+                    message: "Row has 5 cells, the header has 6".to_string()
+                },
+            ]
+        );
+    }
+}
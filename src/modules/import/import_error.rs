@@ -0,0 +1,71 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Represents a bad row of an imported file.
+///
+/// # Fields
+///
+/// * `row` - The number of the row as shown by a spreadsheet, the header being row 1.
+/// * `message` - The description of the problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowProblem {
+    pub row: usize,
+    pub message: String,
+}
+
+/// Represents all bad rows of an imported file.
+///
+/// # Fields
+///
+/// * `file` - The path of the imported file.
+/// * `problems` - The problems, in row order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowProblems {
+    pub file: PathBuf,
+    pub problems: Vec<RowProblem>,
+}
+
+impl fmt::Display for RowProblems {
+    /// Formats every problem like the diagnostics of a batch file, followed by a summary line:
+    ///
+    /// ```text
+    /// error: Summary cannot be empty
+    ///   --> tasks.csv, row 4
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.problems {
+            writeln!(f, "error: {}\n  --> {}, row {}\n", problem.message, self.file.display(), problem.row)?;
+        }
+        write!(
+            f,
+            "error: could not import `{}` due to {} bad row{}",
+            self.file.display(),
+            self.problems.len(),
+            if self.problems.len() == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Represents errors that can occur while importing tasks from another format.
+///
+/// # Variants
+///
+/// * `ReadError` - Indicates an error reading the imported file or the mapping file.
+/// * `MappingError` - Indicates an invalid mapping file.
+/// * `InvalidRows` - Indicates that rows of the imported file are bad, with their row numbers.
+#[derive(Error, Debug)]
+pub enum ImportError {
+    /// Error reading a file.
+    #[error("Error reading the file: {0}")]
+    ReadError(#[from] std::io::Error),
+
+    /// The mapping file is invalid.
+    #[error("Invalid mapping file: {0}")]
+    MappingError(String),
+
+    /// Rows of the imported file are bad.
+    #[error("{0}")]
+    InvalidRows(RowProblems),
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+/// The number of columns a tab advances the indentation by.
+const TAB_WIDTH: usize = 4;
+
+/// Represents a batch read from a Markdown outline.
+///
+/// Headings and list items become created tasks. Deeper headings, and list items under a heading
+/// or indented under another item, become subtasks. The text under an item becomes its description,
+/// and annotations in the item set fields: `@jdoe` the assignee, `!high` the priority and `#bug`
+/// the type. A backslash keeps an annotation as text, e.g. `\#1`.
+///
+/// # Fields
+///
+/// * `document` - The batch document, with `created` tasks and no `updated` ones.
+/// * `positions` - The line and column of every task and of its fields, by JSON pointer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownOutline {
+    pub document: Value,
+    pub positions: HashMap<String, (usize, usize)>,
+}
+
+/// The kind of line an outline item is declared on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    /// A heading of the given level, 1 for `#`.
+    Heading(usize),
+    /// A list item with the marker at the given indentation.
+    ListItem(usize),
+}
+
+/// An item of the outline, with the items nested under it.
+#[derive(Debug)]
+struct OutlineItem {
+    kind: ItemKind,
+    line: usize,
+    column: usize,
+    text: String,
+    /// The indentation removed from the lines of the description.
+    content_indent: usize,
+    /// The lines of the description, with their line numbers.
+    description: Vec<(usize, String)>,
+    children: Vec<OutlineItem>,
+}
+
+impl MarkdownOutline {
+    /// Parses a Markdown outline.
+    ///
+    /// Text before the first heading or list item is ignored, and so are headings and list items
+    /// inside fenced code blocks, which belong to the description.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The Markdown text.
+    pub fn parse(source: &str) -> MarkdownOutline {
+        let mut roots: Vec<OutlineItem> = Vec::new();
+        // The path of the innermost open item, as positions in `roots` and in the children below.
+        let mut open: Vec<usize> = Vec::new();
+        let mut fence: Option<&str> = None;
+
+        for (index, line) in source.lines().enumerate() {
+            let indent = indentation(line);
+            let trimmed = line.trim_start();
+
+            if fence.is_none() {
+                let item = heading(trimmed)
+                    .map(|(level, column, text)| (ItemKind::Heading(level), indent + column, text, 0))
+                    .or_else(|| {
+                        list_item(trimmed)
+                            .map(|(column, text)| (ItemKind::ListItem(indent), indent + column, text, indent + column))
+                    });
+                if let Some((kind, column, text, content_indent)) = item {
+                    close_items(&mut roots, &mut open, |open_kind| match (kind, open_kind) {
+                        (ItemKind::Heading(level), ItemKind::Heading(open_level)) => open_level >= level,
+                        (ItemKind::Heading(_), ItemKind::ListItem(_)) => true,
+                        (ItemKind::ListItem(_), ItemKind::Heading(_)) => false,
+                        (ItemKind::ListItem(indent), ItemKind::ListItem(open_indent)) => open_indent >= indent,
+                    });
+                    let item = OutlineItem {
+                        kind,
+                        line: index + 1,
+                        column: column + 1,
+                        text: text.to_string(),
+                        content_indent,
+                        description: Vec::new(),
+                        children: Vec::new(),
+                    };
+                    let siblings = match item_at(&mut roots, &open) {
+                        Some(parent) => &mut parent.children,
+                        None => &mut roots,
+                    };
+                    siblings.push(item);
+                    open.push(siblings.len() - 1);
+                    continue;
+                }
+
+                // Text that is not indented under the innermost list item ends the list.
+                if !trimmed.is_empty() {
+                    close_items(&mut roots, &mut open, |open_kind| {
+                        matches!(open_kind, ItemKind::ListItem(open_indent) if indent <= open_indent)
+                    });
+                }
+            }
+
+            if let Some(marker) = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)) {
+                fence = match fence {
+                    Some(open_marker) if open_marker == marker => None,
+                    Some(open_marker) => Some(open_marker),
+                    None => Some(marker),
+                };
+            }
+            if let Some(item) = item_at(&mut roots, &open) {
+                let dedented = strip_indentation(line, item.content_indent);
+                item.description.push((index + 1, dedented));
+            }
+        }
+
+        let mut positions = HashMap::new();
+        let created: Vec<Value> = roots
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| item.into_task(&format!("/created/{}", index), &mut positions))
+            .collect();
+        MarkdownOutline {
+            document: json!({ "created": created, "updated": [] }),
+            positions,
+        }
+    }
+}
+
+impl OutlineItem {
+    /// Converts the item into a created task, recording the positions of its fields.
+    fn into_task(self, pointer: &str, positions: &mut HashMap<String, (usize, usize)>) -> Value {
+        let mut task = Map::new();
+        let mut words = Vec::new();
+        for word in self.text.split_whitespace() {
+            let field = match word.chars().next() {
+                Some('@') => "assignee",
+                Some('!') => "priority",
+                Some('#') => "task_type",
+                _ => {
+                    words.push(word.strip_prefix('\\').filter(|rest| rest.starts_with(['@', '!', '#'])).unwrap_or(word));
+                    continue;
+                }
+            };
+            match &word[1..] {
+                "" => words.push(word),
+                value => {
+                    task.insert(field.to_string(), Value::String(value.to_string()));
+                    positions.insert(format!("{}/{}", pointer, field), (self.line, self.column));
+                }
+            }
+        }
+        let summary = words.join(" ");
+        let summary = summary
+            .strip_prefix("[ ] ")
+            .or_else(|| summary.strip_prefix("[x] "))
+            .or_else(|| summary.strip_prefix("[X] "))
+            .unwrap_or(&summary);
+        task.insert("summary".to_string(), Value::String(summary.to_string()));
+        positions.insert(pointer.to_string(), (self.line, self.column));
+        positions.insert(format!("{}/summary", pointer), (self.line, self.column));
+
+        let first = self.description.iter().position(|(_, line)| !line.trim().is_empty());
+        let last = self.description.iter().rposition(|(_, line)| !line.trim().is_empty());
+        if let (Some(first), Some(last)) = (first, last) {
+            let lines: Vec<&str> = self.description[first..=last].iter().map(|(_, line)| line.as_str()).collect();
+            task.insert("description".to_string(), Value::String(lines.join("\n")));
+            positions.insert(format!("{}/description", pointer), (self.description[first].0, 1));
+        }
+
+        let subtasks: Vec<Value> = self
+            .children
+            .into_iter()
+            .enumerate()
+            .map(|(index, child)| child.into_task(&format!("{}/subtasks/{}", pointer, index), positions))
+            .collect();
+        task.insert("subtasks".to_string(), Value::Array(subtasks));
+        Value::Object(task)
+    }
+}
+
+/// Returns the item at a path of positions, the innermost open item.
+fn item_at<'a>(roots: &'a mut [OutlineItem], path: &[usize]) -> Option<&'a mut OutlineItem> {
+    let (first, rest) = path.split_first()?;
+    let mut item = &mut roots[*first];
+    for index in rest {
+        item = &mut item.children[*index];
+    }
+    Some(item)
+}
+
+/// Closes the innermost open items as long as `closes` holds for their kind.
+fn close_items(roots: &mut [OutlineItem], open: &mut Vec<usize>, closes: impl Fn(ItemKind) -> bool) {
+    while let Some(item) = item_at(roots, open) {
+        if !closes(item.kind) {
+            break;
+        }
+        open.pop();
+    }
+}
+
+/// Reads an ATX heading such as `## Title`.
+///
+/// # Returns
+///
+/// The level, the column its text starts at and the text, or `None` if the line is not a heading.
+fn heading(line: &str) -> Option<(usize, usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim_end();
+    Some((level, line.len() - rest.trim_start().len(), text))
+}
+
+/// Reads a list item such as `- Title` or `1. Title`.
+///
+/// # Returns
+///
+/// The column its text starts at and the text, or `None` if the line is not a list item.
+fn list_item(line: &str) -> Option<(usize, &str)> {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let marker = match line[digits..].chars().next() {
+        Some('-' | '*' | '+') if digits == 0 => 1,
+        Some('.' | ')') if digits > 0 => digits + 1,
+        _ => return None,
+    };
+    let rest = &line[marker..];
+    if !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let text = rest.trim();
+    Some((line.len() - rest.trim_start().len(), text))
+}
+
+/// Measures the indentation of a line, a tab advancing to the next multiple of `TAB_WIDTH`.
+fn indentation(line: &str) -> usize {
+    let mut width = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += TAB_WIDTH - width % TAB_WIDTH,
+            _ => break,
+        }
+    }
+    width
+}
+
+/// Removes up to `indent` columns of indentation from a line.
+fn strip_indentation(line: &str, indent: usize) -> String {
+    let mut width = 0;
+    for (position, c) in line.char_indices() {
+        if width >= indent || !matches!(c, ' ' | '\t') {
+            return line[position..].to_string();
+        }
+        width = if c == '\t' { width + TAB_WIDTH - width % TAB_WIDTH } else { width + 1 };
+    }
+    String::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTLINE: &str = "Planning notes, not a task.
+
+# Checkout redesign #epic @jdoe
+
+Rework the checkout flow.
+
+## Payment form !critical
+
+- Validate cards @asmith #task
+  Use the **Luhn** check:
+
+  ```
+  # not a heading
+  - not a task
+  ```
+  - [ ] Add tests
+- Save cards \\#2
+1. Release
+## Analytics
+";
+
+    #[test]
+    fn test_parse_outline() {
+        let outline = MarkdownOutline::parse(OUTLINE);
+        let epic = &outline.document["created"][0];
+
+        assert_eq!(outline.document["created"].as_array().map(Vec::len), Some(1));
+        assert_eq!(epic["summary"], "Checkout redesign");
+        assert_eq!(epic["task_type"], "epic");
+        assert_eq!(epic["assignee"], "jdoe");
+        assert_eq!(epic["description"], "Rework the checkout flow.");
+
+        let form = &epic["subtasks"][0];
+        assert_eq!(form["priority"], "critical");
+        assert!(form.get("description").is_none());
+
+        let validate = &form["subtasks"][0];
+        assert_eq!(validate["summary"], "Validate cards");
+        assert_eq!(validate["assignee"], "asmith");
+        assert_eq!(
+            validate["description"],
+            "Use the **Luhn** check:\n\n```\n# not a heading\n- not a task\n```"
+        );
+        assert_eq!(validate["subtasks"][0]["summary"], "Add tests");
+        assert_eq!(form["subtasks"][1]["summary"], "Save cards #2");
+        assert_eq!(form["subtasks"][2]["summary"], "Release");
+        assert_eq!(epic["subtasks"][1]["summary"], "Analytics");
+
+        assert_eq!(outline.positions["/created/0/subtasks/0/subtasks/0/subtasks/0"], (16, 5));
+        assert_eq!(outline.positions["/created/0/description"], (5, 1));
+    }
+}
@@ -0,0 +1,584 @@
+pub mod metadata_cache;
+
+use reqwest::Method;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::modules::task::task_api_client::{HandleResponseError, TaskApiClient};
+
+/// Deserializes an identifier that the API returns either as a string or as a number.
+pub(crate) fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(id) => Ok(id),
+        serde_json::Value::Number(id) => Ok(id.to_string()),
+        other => Err(serde::de::Error::custom(format!("invalid id: {}", other))),
+    }
+}
+
+/// Represents a short reference to an entity returned by the Yandex Tracker API.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// The URL of the entity.
+    ///
+    /// This field is serialized/deserialized as `self`.
+    #[serde(rename = "self")]
+    pub url: String,
+
+    /// The identifier of the entity.
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+
+    /// The key of the entity, if it has one.
+    #[serde(default)]
+    pub key: Option<String>,
+
+    /// The display name of the entity.
+    #[serde(default)]
+    pub display: String,
+}
+
+impl Reference {
+    /// Checks if the entity is referred to by `value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - A key, an ID or a display name, compared case-insensitively.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - If `value` matches the key, the ID or the display name of the entity.
+    /// * `false` - Otherwise.
+    pub fn matches(&self, value: &str) -> bool {
+        self.key.as_deref().is_some_and(|key| key.eq_ignore_ascii_case(value))
+            || self.id == value
+            || self.display.to_lowercase() == value.to_lowercase()
+    }
+}
+
+/// Represents a queue of Yandex Tracker.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Queue {
+    /// The identifier of the queue.
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+
+    /// The key of the queue.
+    pub key: String,
+
+    /// The name of the queue.
+    pub name: String,
+
+    /// The description of the queue.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// The owner of the queue.
+    #[serde(default)]
+    pub lead: Option<Reference>,
+
+    /// The issue type used when none is specified.
+    ///
+    /// This field is serialized/deserialized as `defaultType`.
+    #[serde(rename = "defaultType", default)]
+    pub default_type: Option<Reference>,
+
+    /// The priority used when none is specified.
+    ///
+    /// This field is serialized/deserialized as `defaultPriority`.
+    #[serde(rename = "defaultPriority", default)]
+    pub default_priority: Option<Reference>,
+
+    /// The issue types available in the queue.
+    ///
+    /// This field is serialized/deserialized as `issueTypes`.
+    #[serde(rename = "issueTypes", default)]
+    pub issue_types: Vec<Reference>,
+
+    /// The workflow and resolutions of every issue type of the queue.
+    ///
+    /// This field is serialized/deserialized as `issueTypesConfig`.
+    #[serde(rename = "issueTypesConfig", default)]
+    pub issue_types_config: Vec<IssueTypeConfig>,
+}
+
+/// Represents the configuration of an issue type in a queue.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IssueTypeConfig {
+    /// The issue type.
+    ///
+    /// This field is serialized/deserialized as `issueType`.
+    #[serde(rename = "issueType")]
+    pub issue_type: Reference,
+
+    /// The workflow of issues of this type.
+    pub workflow: Option<Reference>,
+
+    /// The resolutions available for issues of this type.
+    #[serde(default)]
+    pub resolutions: Vec<Reference>,
+}
+
+/// Represents an issue type, a priority, a status or a resolution.
+///
+/// All of these share the same shape in the Yandex Tracker API.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Constant {
+    /// The identifier of the entity.
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+
+    /// The key of the entity, used in requests.
+    pub key: String,
+
+    /// The display name of the entity.
+    #[serde(alias = "display")]
+    pub name: String,
+
+    /// The description of the entity.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// The sort order of the entity.
+    #[serde(default)]
+    pub order: Option<i64>,
+}
+
+impl Constant {
+    /// Checks if the entity is referred to by `value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - A key or a display name, compared case-insensitively.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - If `value` matches the key or the name of the entity.
+    /// * `false` - Otherwise.
+    pub fn matches(&self, value: &str) -> bool {
+        self.key.eq_ignore_ascii_case(value) || self.name.to_lowercase() == value.to_lowercase()
+    }
+}
+
+/// An issue type of Yandex Tracker.
+pub type IssueType = Constant;
+
+/// A priority of Yandex Tracker.
+pub type Priority = Constant;
+
+/// A status of Yandex Tracker.
+pub type Status = Constant;
+
+/// A resolution of Yandex Tracker.
+pub type Resolution = Constant;
+
+/// Represents a component of a queue.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Component {
+    /// The identifier of the component.
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+
+    /// The name of the component.
+    pub name: String,
+
+    /// The queue of the component.
+    pub queue: Option<Reference>,
+
+    /// The description of the component.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// The owner of the component.
+    #[serde(default)]
+    pub lead: Option<Reference>,
+}
+
+/// Represents a version of a queue.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Version {
+    /// The identifier of the version.
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+
+    /// The name of the version.
+    pub name: String,
+
+    /// The queue of the version.
+    pub queue: Option<Reference>,
+
+    /// The description of the version.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// The planned start date of the version.
+    ///
+    /// This field is serialized/deserialized as `startDate`.
+    #[serde(rename = "startDate", default)]
+    pub start_date: Option<String>,
+
+    /// The planned release date of the version.
+    ///
+    /// This field is serialized/deserialized as `dueDate`.
+    #[serde(rename = "dueDate", default)]
+    pub due_date: Option<String>,
+
+    /// Whether the version is released.
+    #[serde(default)]
+    pub released: bool,
+
+    /// Whether the version is archived.
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// Represents an agile board of Yandex Tracker.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Board {
+    /// The identifier of the board.
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+
+    /// The name of the board.
+    pub name: String,
+}
+
+/// Represents a sprint of an agile board.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Sprint {
+    /// The identifier of the sprint.
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+
+    /// The name of the sprint.
+    pub name: String,
+
+    /// The board of the sprint.
+    pub board: Option<Reference>,
+
+    /// The status of the sprint: `draft`, `in_progress` or `released`.
+    #[serde(default)]
+    pub status: Option<String>,
+
+    /// Whether the sprint is archived.
+    #[serde(default)]
+    pub archived: bool,
+
+    /// The start date of the sprint.
+    ///
+    /// This field is serialized/deserialized as `startDate`.
+    #[serde(rename = "startDate", default)]
+    pub start_date: Option<String>,
+
+    /// The end date of the sprint.
+    ///
+    /// This field is serialized/deserialized as `endDate`.
+    #[serde(rename = "endDate", default)]
+    pub end_date: Option<String>,
+}
+
+/// Represents an issue field, global to the organization or local to a queue.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Field {
+    /// The identifier of the field, used in requests for global fields.
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+
+    /// The key of a local field, used in requests for local fields.
+    #[serde(default)]
+    pub key: Option<String>,
+
+    /// The display name of the field.
+    pub name: String,
+
+    /// The type of the values of the field.
+    #[serde(default)]
+    pub schema: Option<FieldSchema>,
+
+    /// Whether the field cannot be changed.
+    #[serde(default)]
+    pub readonly: bool,
+}
+
+impl Field {
+    /// Checks if the field is referred to by `value`, its ID or its key.
+    pub fn matches(&self, value: &str) -> bool {
+        self.id == value || self.key.as_deref() == Some(value)
+    }
+}
+
+/// Represents the type of the values of an issue field.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FieldSchema {
+    /// The type of the value, e.g. `string`, `float`, `date` or `array`.
+    ///
+    /// This field is serialized/deserialized as `type`.
+    #[serde(rename = "type")]
+    pub value_type: String,
+
+    /// The type of the items of an `array` value.
+    #[serde(default)]
+    pub items: Option<String>,
+
+    /// Whether the field must have a value.
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl FieldSchema {
+    /// Checks if a value from a batch file can be sent for a field of this type.
+    ///
+    /// Numbers are checked for `float` and `integer` fields, and dates such as `2024-05-10` for
+    /// `date` fields; values of other types are left to the API.
+    pub fn accepts(&self, value: &serde_json::Value) -> bool {
+        match self.value_type.as_str() {
+            "float" => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "date" => value
+                .as_str()
+                .is_some_and(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok()),
+            _ => true,
+        }
+    }
+}
+
+/// The maximum number of entities requested per page from list endpoints.
+const PER_PAGE: &str = "1000";
+
+impl TaskApiClient {
+    /// Lists the queues of the organization.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the queues, or `HandleResponseError` if an error occurred.
+    pub async fn list_queues(&self) -> Result<Vec<Queue>, HandleResponseError> {
+        let response = self
+            .request(Method::GET, "/queues")
+            .query(&[("perPage", PER_PAGE)])
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Reads a queue with its issue types and workflows.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key or ID of the queue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the queue, or `HandleResponseError` if an error occurred.
+    pub async fn get_queue(&self, queue: &str) -> Result<Queue, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/queues/{}", queue))
+            .query(&[("expand", "all")])
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Lists the issue types of the organization.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the issue types, or `HandleResponseError` if an error occurred.
+    pub async fn list_issue_types(&self) -> Result<Vec<IssueType>, HandleResponseError> {
+        self.list_constants("/issuetypes").await
+    }
+
+    /// Lists the priorities of the organization.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the priorities, or `HandleResponseError` if an error occurred.
+    pub async fn list_priorities(&self) -> Result<Vec<Priority>, HandleResponseError> {
+        self.list_constants("/priorities").await
+    }
+
+    /// Lists the statuses of the organization.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the statuses, or `HandleResponseError` if an error occurred.
+    pub async fn list_statuses(&self) -> Result<Vec<Status>, HandleResponseError> {
+        self.list_constants("/statuses").await
+    }
+
+    /// Lists the resolutions of the organization.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the resolutions, or `HandleResponseError` if an error occurred.
+    pub async fn list_resolutions(&self) -> Result<Vec<Resolution>, HandleResponseError> {
+        self.list_constants("/resolutions").await
+    }
+
+    /// Lists the components of a queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key or ID of the queue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the components, or `HandleResponseError` if an error occurred.
+    pub async fn list_components(&self, queue: &str) -> Result<Vec<Component>, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/queues/{}/components", queue))
+            .query(&[("perPage", PER_PAGE)])
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Lists the versions of a queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key or ID of the queue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the versions, or `HandleResponseError` if an error occurred.
+    pub async fn list_versions(&self, queue: &str) -> Result<Vec<Version>, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/queues/{}/versions", queue))
+            .query(&[("perPage", PER_PAGE)])
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Lists the agile boards of the organization.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the boards, or `HandleResponseError` if an error occurred.
+    pub async fn list_boards(&self) -> Result<Vec<Board>, HandleResponseError> {
+        let response = self.request(Method::GET, "/boards").send().await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Lists the sprints of an agile board.
+    ///
+    /// # Arguments
+    ///
+    /// * `board_id` - The identifier of the board.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the sprints, or `HandleResponseError` if an error occurred.
+    pub async fn list_sprints(&self, board_id: &str) -> Result<Vec<Sprint>, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/boards/{}/sprints", board_id))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Lists the global issue fields of the organization.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the fields, or `HandleResponseError` if an error occurred.
+    pub async fn list_fields(&self) -> Result<Vec<Field>, HandleResponseError> {
+        let response = self.request(Method::GET, "/fields").send().await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Lists the local issue fields of a queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key or ID of the queue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the fields, or `HandleResponseError` if an error occurred.
+    pub async fn list_local_fields(&self, queue: &str) -> Result<Vec<Field>, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/queues/{}/localFields", queue))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Lists the entities of a global dictionary endpoint.
+    async fn list_constants(&self, path: &str) -> Result<Vec<Constant>, HandleResponseError> {
+        let response = self.request(Method::GET, path).send().await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_deserialize_queue() {
+        let queue: Queue = serde_json::from_value(json!({
+            "self": "https://api.tracker.yandex.net/v2/queues/TEST",
+            "id": 3,
+            "key": "TEST",
+            "version": 5,
+            "name": "Test",
+            "defaultType": { "self": "", "id": "1", "key": "bug", "display": "Bug" },
+            "defaultPriority": { "self": "", "id": "3", "key": "normal", "display": "Normal" },
+            "issueTypes": [{ "self": "", "id": "1", "key": "bug", "display": "Bug" }],
+            "issueTypesConfig": [{
+                "issueType": { "self": "", "id": "1", "key": "bug", "display": "Bug" },
+                "workflow": { "self": "", "id": "dev", "display": "Development" },
+                "resolutions": [{ "self": "", "id": "1", "key": "fixed", "display": "Fixed" }]
+            }]
+        }))
+        .unwrap();
+
+        assert_eq!(queue.id, "3");
+        assert_eq!(queue.issue_types_config[0].resolutions[0].key.as_deref(), Some("fixed"));
+    }
+
+    #[test]
+    fn test_constant_matches() {
+        let priority: Priority = serde_json::from_value(json!({
+            "self": "https://api.tracker.yandex.net/v2/priorities/3",
+            "id": 3,
+            "key": "normal",
+            "version": 1,
+            "name": "Normal",
+            "order": 3
+        }))
+        .unwrap();
+
+        assert!(priority.matches("NORMAL"));
+        assert!(priority.matches("normal"));
+        assert!(!priority.matches("critical"));
+    }
+
+    #[test]
+    fn test_local_field() {
+        let field: Field = serde_json::from_value(json!({
+            "self": "https://api.tracker.yandex.net/v2/queues/TEST/localFields/team",
+            "id": "6061a3c9e1a2b3--team",
+            "key": "team",
+            "name": "Team",
+            "schema": { "type": "float", "required": false },
+            "readonly": false,
+            "type": "local"
+        }))
+        .unwrap();
+
+        assert!(field.matches("team"));
+        let schema = field.schema.unwrap();
+        assert!(schema.accepts(&json!(2.5)));
+        assert!(!schema.accepts(&json!("2.5")));
+    }
+}
@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+
+use tokio::sync::{Mutex, OnceCell};
+
+use super::{Component, Constant, Field, IssueType, Priority, Queue, Resolution, Sprint, Status, Version};
+use crate::modules::task::task_api_client::{HandleResponseError, TaskApiClient};
+use crate::modules::user::TrackerUser;
+
+/// Caches the queue metadata of the organization for the duration of a session.
+///
+/// Every entity is requested at most once; later lookups are served from memory.
+/// The cache resolves the free-form names used in batch files to the keys and IDs
+/// expected by the API.
+pub struct MetadataCache {
+    /// The API client used to load the metadata.
+    api_client: TaskApiClient,
+
+    /// Queues by key; `None` marks a queue that does not exist.
+    queues: Mutex<HashMap<String, Option<Queue>>>,
+
+    /// The global issue types.
+    issue_types: OnceCell<Vec<IssueType>>,
+
+    /// The global priorities.
+    priorities: OnceCell<Vec<Priority>>,
+
+    /// The global statuses.
+    statuses: OnceCell<Vec<Status>>,
+
+    /// The global resolutions.
+    resolutions: OnceCell<Vec<Resolution>>,
+
+    /// Components by queue key.
+    components: Mutex<HashMap<String, Vec<Component>>>,
+
+    /// Versions by queue key.
+    versions: Mutex<HashMap<String, Vec<Version>>>,
+
+    /// The sprints of all boards.
+    sprints: OnceCell<Vec<Sprint>>,
+
+    /// The global issue fields.
+    fields: OnceCell<Vec<Field>>,
+
+    /// Local issue fields by queue key.
+    local_fields: Mutex<HashMap<String, Vec<Field>>>,
+
+    /// Users by login or ID; `None` marks a user that does not exist.
+    users: Mutex<HashMap<String, Option<TrackerUser>>>,
+}
+
+impl MetadataCache {
+    /// Creates a new, empty `MetadataCache` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_client` - The API client used to load the metadata.
+    pub fn new(api_client: TaskApiClient) -> Self {
+        MetadataCache {
+            api_client,
+            queues: Mutex::new(HashMap::new()),
+            issue_types: OnceCell::new(),
+            priorities: OnceCell::new(),
+            statuses: OnceCell::new(),
+            resolutions: OnceCell::new(),
+            components: Mutex::new(HashMap::new()),
+            versions: Mutex::new(HashMap::new()),
+            sprints: OnceCell::new(),
+            fields: OnceCell::new(),
+            local_fields: Mutex::new(HashMap::new()),
+            users: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the API client used by the cache.
+    pub fn api_client(&self) -> &TaskApiClient {
+        &self.api_client
+    }
+
+    /// Returns a queue with its issue types and workflows.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the queue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the queue, `None` if it does not exist, or `HandleResponseError`.
+    pub async fn queue(&self, key: &str) -> Result<Option<Queue>, HandleResponseError> {
+        let mut queues = self.queues.lock().await;
+        if let Some(queue) = queues.get(key) {
+            return Ok(queue.clone());
+        }
+
+        let queue = match self.api_client.get_queue(key).await {
+            Ok(queue) => Some(queue),
+            Err(err) if err.is_not_found() => None,
+            Err(err) => return Err(err),
+        };
+        queues.insert(key.to_string(), queue.clone());
+        Ok(queue)
+    }
+
+    /// Returns the global issue types.
+    pub async fn issue_types(&self) -> Result<&Vec<IssueType>, HandleResponseError> {
+        self.issue_types
+            .get_or_try_init(|| self.api_client.list_issue_types())
+            .await
+    }
+
+    /// Returns the global priorities.
+    pub async fn priorities(&self) -> Result<&Vec<Priority>, HandleResponseError> {
+        self.priorities
+            .get_or_try_init(|| self.api_client.list_priorities())
+            .await
+    }
+
+    /// Returns the global statuses.
+    pub async fn statuses(&self) -> Result<&Vec<Status>, HandleResponseError> {
+        self.statuses
+            .get_or_try_init(|| self.api_client.list_statuses())
+            .await
+    }
+
+    /// Returns the global resolutions.
+    pub async fn resolutions(&self) -> Result<&Vec<Resolution>, HandleResponseError> {
+        self.resolutions
+            .get_or_try_init(|| self.api_client.list_resolutions())
+            .await
+    }
+
+    /// Returns the components of a queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key of the queue.
+    pub async fn components(&self, queue: &str) -> Result<Vec<Component>, HandleResponseError> {
+        let mut components = self.components.lock().await;
+        if let Some(cached) = components.get(queue) {
+            return Ok(cached.clone());
+        }
+
+        let loaded = self.api_client.list_components(queue).await?;
+        components.insert(queue.to_string(), loaded.clone());
+        Ok(loaded)
+    }
+
+    /// Returns the versions of a queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key of the queue.
+    pub async fn versions(&self, queue: &str) -> Result<Vec<Version>, HandleResponseError> {
+        let mut versions = self.versions.lock().await;
+        if let Some(cached) = versions.get(queue) {
+            return Ok(cached.clone());
+        }
+
+        let loaded = self.api_client.list_versions(queue).await?;
+        versions.insert(queue.to_string(), loaded.clone());
+        Ok(loaded)
+    }
+
+    /// Returns the sprints of all agile boards.
+    pub async fn sprints(&self) -> Result<&Vec<Sprint>, HandleResponseError> {
+        self.sprints
+            .get_or_try_init(|| async {
+                let mut sprints = Vec::new();
+                for board in self.api_client.list_boards().await? {
+                    sprints.extend(self.api_client.list_sprints(&board.id).await?);
+                }
+                Ok(sprints)
+            })
+            .await
+    }
+
+    /// Returns the global issue fields.
+    pub async fn fields(&self) -> Result<&Vec<Field>, HandleResponseError> {
+        self.fields.get_or_try_init(|| self.api_client.list_fields()).await
+    }
+
+    /// Returns the local issue fields of a queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key of the queue.
+    pub async fn local_fields(&self, queue: &str) -> Result<Vec<Field>, HandleResponseError> {
+        let mut local_fields = self.local_fields.lock().await;
+        if let Some(cached) = local_fields.get(queue) {
+            return Ok(cached.clone());
+        }
+
+        let loaded = self.api_client.list_local_fields(queue).await?;
+        local_fields.insert(queue.to_string(), loaded.clone());
+        Ok(loaded)
+    }
+
+    /// Returns a user of the organization.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The login or the unique identifier of the user.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the user, `None` if it does not exist, or `HandleResponseError`.
+    pub async fn user(&self, user: &str) -> Result<Option<TrackerUser>, HandleResponseError> {
+        let mut users = self.users.lock().await;
+        if let Some(cached) = users.get(user) {
+            return Ok(cached.clone());
+        }
+
+        let loaded = match self.api_client.get_user(user).await {
+            Ok(loaded) => Some(loaded),
+            Err(err) if err.is_not_found() => None,
+            Err(err) => return Err(err),
+        };
+        users.insert(user.to_string(), loaded.clone());
+        Ok(loaded)
+    }
+
+    /// Resolves an issue type of a queue by its key or name.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key of the queue.
+    /// * `value` - The key or the name of the issue type.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the key of the issue type, or `None` if the queue does not exist
+    /// or the type is not available in it.
+    pub async fn resolve_issue_type(
+        &self,
+        queue: &str,
+        value: &str,
+    ) -> Result<Option<String>, HandleResponseError> {
+        let queue = match self.queue(queue).await? {
+            Some(queue) => queue,
+            None => return Ok(None),
+        };
+
+        Ok(queue
+            .issue_types
+            .iter()
+            .chain(queue.issue_types_config.iter().map(|config| &config.issue_type))
+            .find(|issue_type| issue_type.matches(value))
+            .and_then(|issue_type| issue_type.key.clone()))
+    }
+
+    /// Resolves a priority by its key or name.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The key or the name of the priority.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the key of the priority, or `None` if it does not exist.
+    pub async fn resolve_priority(&self, value: &str) -> Result<Option<String>, HandleResponseError> {
+        Ok(Self::resolve_constant(self.priorities().await?, value))
+    }
+
+    /// Resolves a status by its key or name.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The key or the name of the status.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the key of the status, or `None` if it does not exist.
+    pub async fn resolve_status(&self, value: &str) -> Result<Option<String>, HandleResponseError> {
+        Ok(Self::resolve_constant(self.statuses().await?, value))
+    }
+
+    /// Resolves a resolution by its key or name.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The key or the name of the resolution.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the key of the resolution, or `None` if it does not exist.
+    pub async fn resolve_resolution(&self, value: &str) -> Result<Option<String>, HandleResponseError> {
+        Ok(Self::resolve_constant(self.resolutions().await?, value))
+    }
+
+    /// Resolves a component of a queue by its name or ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key of the queue.
+    /// * `value` - The name or the ID of the component.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the ID of the component, or `None` if it does not exist.
+    pub async fn resolve_component(
+        &self,
+        queue: &str,
+        value: &str,
+    ) -> Result<Option<String>, HandleResponseError> {
+        Ok(self
+            .components(queue)
+            .await?
+            .into_iter()
+            .find(|component| component.id == value || component.name.eq_ignore_ascii_case(value))
+            .map(|component| component.id))
+    }
+
+    /// Resolves a version of a queue by its name or ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key of the queue.
+    /// * `value` - The name or the ID of the version.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the ID of the version, or `None` if it does not exist.
+    pub async fn resolve_version(
+        &self,
+        queue: &str,
+        value: &str,
+    ) -> Result<Option<String>, HandleResponseError> {
+        Ok(self
+            .versions(queue)
+            .await?
+            .into_iter()
+            .find(|version| version.id == value || version.name.eq_ignore_ascii_case(value))
+            .map(|version| version.id))
+    }
+
+    /// Resolves an issue field that can be set on issues of a queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The key of the queue.
+    /// * `value` - The ID of a global field, or the ID or key of a local field of the queue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the field, or `None` if it does not exist.
+    pub async fn resolve_field(&self, queue: &str, value: &str) -> Result<Option<Field>, HandleResponseError> {
+        if let Some(field) = self.fields().await?.iter().find(|field| field.matches(value)) {
+            return Ok(Some(field.clone()));
+        }
+        Ok(self
+            .local_fields(queue)
+            .await?
+            .into_iter()
+            .find(|field| field.matches(value)))
+    }
+
+    /// Resolves a sprint by its name or ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The name or the ID of the sprint.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the ID of the sprint, or `None` if it does not exist.
+    pub async fn resolve_sprint(&self, value: &str) -> Result<Option<String>, HandleResponseError> {
+        Ok(self
+            .sprints()
+            .await?
+            .iter()
+            .find(|sprint| sprint.id == value || sprint.name.eq_ignore_ascii_case(value))
+            .map(|sprint| sprint.id.clone()))
+    }
+
+    /// Finds the key of the constant that matches `value`.
+    fn resolve_constant(constants: &[Constant], value: &str) -> Option<String> {
+        constants
+            .iter()
+            .find(|constant| constant.matches(value))
+            .map(|constant| constant.key.clone())
+    }
+}
+
+#[cfg(test)]
+impl MetadataCache {
+    /// Records the metadata of a queue as if it had been loaded from the API.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the queue.
+    /// * `queue` - The queue, or `None` if it does not exist.
+    /// * `components` - The components of the queue.
+    /// * `versions` - The versions of the queue.
+    /// * `local_fields` - The local fields of the queue.
+    pub(crate) fn with_queue(
+        mut self,
+        key: &str,
+        queue: Option<Queue>,
+        components: Vec<Component>,
+        versions: Vec<Version>,
+        local_fields: Vec<Field>,
+    ) -> Self {
+        self.queues.get_mut().insert(key.to_string(), queue);
+        self.components.get_mut().insert(key.to_string(), components);
+        self.versions.get_mut().insert(key.to_string(), versions);
+        self.local_fields.get_mut().insert(key.to_string(), local_fields);
+        self
+    }
+
+    /// Records the global priorities, sprints and fields as if they had been loaded from the API.
+    pub(crate) fn with_globals(self, priorities: Vec<Priority>, sprints: Vec<Sprint>, fields: Vec<Field>) -> Self {
+        let _ = self.priorities.set(priorities);
+        let _ = self.sprints.set(sprints);
+        let _ = self.fields.set(fields);
+        self
+    }
+
+    /// Records a user as if it had been loaded from the API; `None` marks a user that does not exist.
+    pub(crate) fn with_user(mut self, user: &str, found: Option<TrackerUser>) -> Self {
+        self.users.get_mut().insert(user.to_string(), found);
+        self
+    }
+}
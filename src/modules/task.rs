@@ -1,15 +1,38 @@
 pub mod error_response;
 pub mod success_response;
 pub mod task_api_client;
+pub mod task_attachment;
 pub mod task_batch;
+pub mod task_batch_diagnostics;
 pub mod task_batch_error;
+pub mod task_batch_failures;
+pub mod task_batch_format;
+pub mod task_batch_graph;
+pub mod task_batch_template;
+pub mod task_batch_validator;
+pub mod task_checklist;
+pub mod task_comment;
+pub mod task_diff;
+pub mod task_export;
+pub mod task_fields;
+pub mod task_journal;
+pub mod task_link;
+pub mod task_list_update;
 pub mod task_manager;
+pub mod task_plan;
+pub mod task_reference;
+pub mod task_rollback;
+pub mod task_run_report;
+pub mod task_sync;
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
 
 use crate::config::Config;
+use task_checklist::ChecklistItemInfo;
+use task_fields::IssueFields;
+use task_link::TaskLinkInfo;
+use task_list_update::ListUpdate;
 
 /// Represents a task to be created in Yandex Tracker.
 ///
@@ -19,14 +42,21 @@ use crate::config::Config;
 /// * `summary` - A brief summary of the task.
 /// * `description` - A detailed description of the task (optional).
 /// * `parent` - The parent task ID (optional).
+/// * `epic` - The key of the epic the task belongs to (optional).
 /// * `sprint` - The sprint associated with the task (optional).
 /// * `task_type` - The type of the task (optional).
 /// * `priority` - The priority level of the task (optional).
 /// * `followers` - A list of followers for the task (optional).
+/// * `tags` - A list of tags of the task (optional).
+/// * `components` - A list of components of the task, by name or ID (optional).
+/// * `affected_versions` - A list of versions affected by the task, by name or ID (optional).
+/// * `fix_versions` - A list of versions the task is fixed in, by name or ID (optional).
 /// * `assignee` - The user assigned to the task (optional).
 /// * `author` - The author of the task (optional).
 /// * `unique` - A unique identifier for the task (optional).
 /// * `attachment_ids` - A list of attachment IDs associated with the task (optional).
+/// * `description_attachment_ids` - A list of attachment IDs for the task description (optional).
+/// * `fields` - The standard and queue-local fields of the task, sent as fields of the body.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreatedTaskBody {
     pub queue: String,
@@ -34,6 +64,8 @@ pub struct CreatedTaskBody {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub parent: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epic: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(default)]
     pub sprint: Vec<String>,
@@ -43,6 +75,14 @@ pub struct CreatedTaskBody {
     pub priority: Option<String>,
     #[serde(default)]
     pub followers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<String>,
+    #[serde(rename = "affectedVersions", default, skip_serializing_if = "Vec::is_empty")]
+    pub affected_versions: Vec<String>,
+    #[serde(rename = "fixVersions", default, skip_serializing_if = "Vec::is_empty")]
+    pub fix_versions: Vec<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub assignee: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -51,6 +91,10 @@ pub struct CreatedTaskBody {
     pub unique: Option<String>,
     #[serde(rename = "attachmentIds", default)]
     pub attachment_ids: Vec<String>,
+    #[serde(rename = "descriptionAttachmentIds", default)]
+    pub description_attachment_ids: Vec<String>,
+    #[serde(flatten)]
+    pub fields: IssueFields,
 }
 
 /// Represents the information of a created task in Yandex Tracker.
@@ -60,35 +104,83 @@ pub struct CreatedTaskBody {
 /// * `queue` - The queue to which the task belongs.
 /// * `summary` - A brief summary of the task.
 /// * `parent` - The parent task ID (optional).
+/// * `epic` - The key of an existing epic the task belongs to (optional).
 /// * `description` - A detailed description of the task (optional).
 /// * `sprint` - The sprint associated with the task (optional).
-/// * `task_type` - The type of the task (optional).
+/// * `task_type` - The type of the task, written `type` or `task_type` (optional).
 /// * `priority` - The priority level of the task (optional).
 /// * `followers` - A list of followers for the task (optional).
+/// * `tags` - A list of tags of the task (optional).
+/// * `components` - A list of components of the task, by name or ID (optional).
+/// * `affected_versions` - A list of versions affected by the task, by name or ID (optional).
+/// * `fix_versions` - A list of versions the task is fixed in, by name or ID (optional).
 /// * `assignee` - The user assigned to the task (optional).
 /// * `author` - The author of the task (optional).
 /// * `unique` - A unique identifier for the task (optional).
+/// * `id` - A local identifier other entries of the batch refer to the task by, as `${id}` (optional).
 /// * `attachment_ids` - A list of attachment IDs associated with the task (optional).
-/// * `subtasks` - A set of subtasks associated with this task.
-#[derive(Serialize, Debug, Deserialize, Clone)]
+/// * `description_attachment_ids` - A list of attachment IDs for the task description (optional).
+/// * `attachments` - A list of local files uploaded and attached to the task (optional).
+/// * `description_attachments` - A list of local files uploaded for the task description (optional).
+/// * `links` - A list of links to existing issues or to other tasks of the batch (optional).
+/// * `checklist` - A list of checklist items added to the task once it is created (optional).
+/// * `comments` - A list of comments posted once every task of the batch exists (optional).
+/// * `subtasks` - A list of subtasks, created in order right after this task.
+/// * `fields` - The standard fields such as `deadline`, and any other field of the entry, e.g. a
+///   local field of the queue, see `IssueFields`.
+/// * `error` - The error of a previous run, written to failed entries and ignored (optional).
+///
+/// Tasks of a batch are identified by their `unique`; tasks without one are identified by their
+/// position in the batch file, so two tasks may share the same queue and summary.
+/// `parent`, the `issue` of links and comments may refer to another created task as `${id}`.
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq)]
 pub struct CreatedTaskInfo {
     #[serde(default = "default_queue")]
     pub queue: String,
     pub summary: String,
     pub parent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epic: Option<String>,
     pub description: Option<String>,
     #[serde(default)]
     pub sprint: Vec<String>,
+    #[serde(rename = "type", alias = "task_type")]
     pub task_type: Option<String>,
     pub priority: Option<String>,
     #[serde(default)]
     pub followers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<String>,
+    #[serde(rename = "affectedVersions", default, skip_serializing_if = "Vec::is_empty")]
+    pub affected_versions: Vec<String>,
+    #[serde(rename = "fixVersions", default, skip_serializing_if = "Vec::is_empty")]
+    pub fix_versions: Vec<String>,
     pub assignee: Option<String>,
     pub author: Option<String>,
     pub unique: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     #[serde(rename = "attachmentIds", default)]
     pub attachment_ids: Vec<String>,
-    pub subtasks: HashSet<CreatedTaskInfo>,
+    #[serde(rename = "descriptionAttachmentIds", default)]
+    pub description_attachment_ids: Vec<String>,
+    #[serde(default)]
+    pub attachments: Vec<PathBuf>,
+    #[serde(rename = "descriptionAttachments", default)]
+    pub description_attachments: Vec<PathBuf>,
+    #[serde(default)]
+    pub links: Vec<TaskLinkInfo>,
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItemInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+    pub subtasks: Vec<CreatedTaskInfo>,
+    #[serde(flatten)]
+    pub fields: IssueFields,
+    #[serde(default, skip_serializing)]
+    pub error: Option<String>,
 }
 
 /// Provides a default value for the `queue` field in `CreatedTaskInfo`.
@@ -123,15 +215,22 @@ impl From<CreatedTaskInfo> for CreatedTaskBody {
             queue: value.queue,
             summary: value.summary,
             parent: value.parent,
+            epic: value.epic,
             description: value.description,
             sprint: value.sprint,
             task_type: value.task_type,
             priority: value.priority,
             followers: value.followers,
+            tags: value.tags,
+            components: value.components,
+            affected_versions: value.affected_versions,
+            fix_versions: value.fix_versions,
             assignee: value.assignee,
             author: value.author,
             unique: value.unique,
             attachment_ids: value.attachment_ids,
+            description_attachment_ids: value.description_attachment_ids,
+            fields: value.fields,
         }
     }
 }
@@ -152,16 +251,30 @@ impl Default for CreatedTaskInfo {
             queue: "The queue to which the task belongs".to_string(),
             summary: "A brief summary of the task".to_string(),
             parent: Some("The parent task ID (optional)".to_string()),
+            epic: Some("The key of an existing epic the task belongs to (optional)".to_string()),
             description: Some("A detailed description of the task (optional)".to_string()),
             sprint: Vec::new(),
             task_type: Some("The type of the task (optional)".to_string()),
             priority: Some("The priority level of the task (optional)".to_string()),
             followers: Vec::new(),
+            tags: vec!["A tag of the task (optional)".to_string()],
+            components: vec!["A component of the queue (optional)".to_string()],
+            affected_versions: vec!["A version affected by the task (optional)".to_string()],
+            fix_versions: vec!["A version the task is fixed in (optional)".to_string()],
             assignee: Some("The user assigned to the task (optional)".to_string()),
             author: Some("The author of the task (optional)".to_string()),
             unique: Some("A unique identifier for the task (optional)".to_string()),
+            id: None,
             attachment_ids: Vec::new(),
-            subtasks: HashSet::new(),
+            description_attachment_ids: Vec::new(),
+            attachments: Vec::new(),
+            description_attachments: Vec::new(),
+            links: Vec::new(),
+            checklist: Vec::new(),
+            comments: Vec::new(),
+            subtasks: Vec::new(),
+            fields: IssueFields::example(),
+            error: None,
         }
     }
 }
@@ -184,16 +297,42 @@ impl CreatedTaskInfo {
             queue: queue,
             summary: self.summary.clone(),
             parent: Some(parent),
+            epic: self.epic.clone(),
             description: self.description.clone(),
             sprint: self.sprint.clone(),
             task_type: self.task_type.clone(),
             priority: self.priority.clone(),
             followers: self.followers.clone(),
+            tags: self.tags.clone(),
+            components: self.components.clone(),
+            affected_versions: self.affected_versions.clone(),
+            fix_versions: self.fix_versions.clone(),
             assignee: self.assignee.clone(),
             author: self.author.clone(),
             unique: self.unique.clone(),
+            id: self.id.clone(),
             attachment_ids: self.attachment_ids.clone(),
+            description_attachment_ids: self.description_attachment_ids.clone(),
+            attachments: self.attachments.clone(),
+            description_attachments: self.description_attachments.clone(),
+            links: self.links.clone(),
+            checklist: self.checklist.clone(),
+            comments: self.comments.clone(),
             subtasks: self.subtasks.clone(),
+            fields: self.fields.clone(),
+            error: self.error.clone(),
+        }
+    }
+
+    /// Returns a copy of the task sent with `unique`, unless the task declares its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `unique` - The `unique` generated for the entry, see `TaskJournal::unique`.
+    pub fn with_unique(&self, unique: &str) -> CreatedTaskInfo {
+        CreatedTaskInfo {
+            unique: Some(self.unique.clone().unwrap_or_else(|| unique.to_string())),
+            ..self.clone()
         }
     }
 
@@ -210,27 +349,6 @@ impl CreatedTaskInfo {
     }
 }
 
-impl PartialEq for CreatedTaskInfo {
-    /// Compares two `CreatedTask` instances for equality.
-    ///
-    /// This implementation considers two tasks equal if their `queue` and `summary` fields are equal.
-    fn eq(&self, other: &Self) -> bool {
-        self.queue == other.queue && self.summary == other.summary
-    }
-}
-
-impl Eq for CreatedTaskInfo {}
-
-impl Hash for CreatedTaskInfo {
-    /// Hashes the `CreatedTask` instance.
-    ///
-    /// This implementation hashes the `queue` and `summary` fields.
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.queue.hash(state);
-        self.summary.hash(state);
-    }
-}
-
 /// Represents a task to be updated in Yandex Tracker.
 ///
 /// # Fields
@@ -238,14 +356,23 @@ impl Hash for CreatedTaskInfo {
 /// * `issue_id` - The ID of the issue to be updated.
 /// * `summary` - A brief summary of the task (optional).
 /// * `parent` - The parent task ID (optional).
+/// * `epic` - The key of the epic of the task (optional).
 /// * `description` - A detailed description of the task (optional).
-/// * `sprint` - The sprint associated with the task (optional).
+/// * `sprint` - The change of the sprints of the task (optional).
 /// * `task_type` - The type of the task (optional).
 /// * `priority` - The priority level of the task (optional).
-/// * `followers` - A list of followers for the task (optional).
-/// * `attachment_ids` - A list of attachment IDs associated with the task (optional).
+/// * `followers` - The change of the followers of the task (optional).
+/// * `tags` - The change of the tags of the task (optional).
+/// * `components` - The change of the components of the task (optional).
+/// * `affected_versions` - The change of the versions affected by the task (optional).
+/// * `fix_versions` - The change of the versions the task is fixed in (optional).
+/// * `attachment_ids` - A list of attachment IDs added to the task (optional).
 /// * `description_attachment_ids` - A list of attachment IDs for the task description (optional).
-#[derive(Serialize, Deserialize, Debug, Hash, PartialEq, Eq, Clone)]
+/// * `version` - The version of the issue the update is written against, sent as a query parameter (optional).
+/// * `fields` - The standard and queue-local fields of the task, sent as fields of the body.
+///
+/// List fields that are not changed are left out of the request body.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct UpdatedTask {
     #[serde(skip)]
     pub issue_id: String,
@@ -254,19 +381,33 @@ pub struct UpdatedTask {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub parent: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub description: Option<String>,
+    pub epic: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub sprint: Option<String>,
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub sprint: ListUpdate,
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub task_type: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub priority: Option<String>,
-    #[serde(default)]
-    pub followers: Vec<String>,
-    #[serde(rename = "attachmentIds", default)]
+    #[serde(default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub followers: ListUpdate,
+    #[serde(default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub tags: ListUpdate,
+    #[serde(default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub components: ListUpdate,
+    #[serde(rename = "affectedVersions", default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub affected_versions: ListUpdate,
+    #[serde(rename = "fixVersions", default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub fix_versions: ListUpdate,
+    #[serde(rename = "attachmentIds", default, skip_serializing_if = "Vec::is_empty")]
     pub attachment_ids: Vec<String>,
-    #[serde(rename = "descriptionAttachmentIds", default)]
+    #[serde(rename = "descriptionAttachmentIds", default, skip_serializing_if = "Vec::is_empty")]
     pub description_attachment_ids: Vec<String>,
+    #[serde(skip)]
+    pub version: Option<u32>,
+    #[serde(flatten)]
+    pub fields: IssueFields,
 }
 
 /// Represents the information of a task to be updated in Yandex Tracker.
@@ -276,28 +417,72 @@ pub struct UpdatedTask {
 /// * `issue_id` - The ID of the issue to be updated.
 /// * `summary` - A brief summary of the task (optional).
 /// * `parent` - The parent task ID (optional).
+/// * `epic` - The key of the epic of the task (optional).
 /// * `description` - A detailed description of the task (optional).
-/// * `sprint` - The sprint associated with the task (optional).
-/// * `task_type` - The type of the task (optional).
+/// * `sprint` - The sprints of the task, or `add`/`remove`/`set` operations on them (optional).
+/// * `task_type` - The type of the task, written `type` or `task_type` (optional).
 /// * `priority` - The priority level of the task (optional).
-/// * `followers` - A list of followers for the task (optional).
-/// * `attachment_ids` - A list of attachment IDs associated with the task (optional).
+/// * `followers` - The followers of the task, or operations on them, see `ListUpdate` (optional).
+/// * `tags` - The tags of the task, or operations on them (optional).
+/// * `components` - The components of the task, by name or ID, or operations on them (optional).
+/// * `affected_versions` - The versions of the queue affected by the task, or operations on them (optional).
+/// * `fix_versions` - The versions of the queue the task is fixed in, or operations on them (optional).
+/// * `attachment_ids` - A list of attachment IDs added to the task (optional).
 /// * `description_attachment_ids` - A list of attachment IDs for the task description (optional).
-#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
+/// * `attachments` - A list of local files uploaded and attached to the task (optional).
+/// * `description_attachments` - A list of local files uploaded for the task description (optional).
+/// * `links` - A list of links to existing issues or to tasks created in the batch (optional).
+/// * `checklist` - A list of checklist items added to the task (optional).
+/// * `comments` - A list of comments posted once every task of the batch exists (optional).
+/// * `version` - The version of the issue the update is written against; the update fails if the issue
+///   has changed since (optional).
+/// * `fields` - The standard fields such as `deadline`, and any other field of the entry, see `IssueFields`.
+/// * `error` - The error of a previous run, written to failed entries and ignored (optional).
+///
+/// `issue_id`, `parent` and `epic` may refer to a task created by the batch as `${id}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct UpdatedTaskInfo {
     pub issue_id: String,
     pub summary: Option<String>,
     pub parent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epic: Option<String>,
     pub description: Option<String>,
-    pub sprint: Option<String>,
-    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub sprint: ListUpdate,
+    #[serde(rename = "type", alias = "task_type", skip_serializing_if = "Option::is_none")]
     pub task_type: Option<String>,
     pub priority: Option<String>,
-    pub followers: Vec<String>,
+    #[serde(default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub followers: ListUpdate,
+    #[serde(default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub tags: ListUpdate,
+    #[serde(default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub components: ListUpdate,
+    #[serde(rename = "affectedVersions", default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub affected_versions: ListUpdate,
+    #[serde(rename = "fixVersions", default, skip_serializing_if = "ListUpdate::is_empty")]
+    pub fix_versions: ListUpdate,
     #[serde(rename = "attachmentIds", default)]
     pub attachment_ids: Vec<String>,
     #[serde(rename = "descriptionAttachmentIds", default)]
     pub description_attachment_ids: Vec<String>,
+    #[serde(default)]
+    pub attachments: Vec<PathBuf>,
+    #[serde(rename = "descriptionAttachments", default)]
+    pub description_attachments: Vec<PathBuf>,
+    #[serde(default)]
+    pub links: Vec<TaskLinkInfo>,
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItemInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    #[serde(flatten)]
+    pub fields: IssueFields,
+    #[serde(default, skip_serializing)]
+    pub error: Option<String>,
 }
 
 impl From<UpdatedTaskInfo> for UpdatedTask {
@@ -318,13 +503,20 @@ impl From<UpdatedTaskInfo> for UpdatedTask {
             issue_id: value.issue_id,
             summary: value.summary,
             parent: value.parent,
+            epic: value.epic,
             description: value.description,
             sprint: value.sprint,
             task_type: value.task_type,
             priority: value.priority,
             followers: value.followers,
+            tags: value.tags,
+            components: value.components,
+            affected_versions: value.affected_versions,
+            fix_versions: value.fix_versions,
             attachment_ids: value.attachment_ids,
             description_attachment_ids: value.description_attachment_ids,
+            version: value.version,
+            fields: value.fields,
         }
     }
 }
@@ -345,13 +537,33 @@ impl Default for UpdatedTaskInfo {
             issue_id: "The ID of the issue to be updated".to_string(),
             summary: Some("A brief summary of the task (optional)".to_string()),
             parent: Some("The parent task ID (optional)".to_string()),
+            epic: Some("The key of the epic of the task (optional)".to_string()),
             description: Some("A detailed description of the task (optional)".to_string()),
-            sprint: Some("The sprint associated with the task (optional)".to_string()),
+            sprint: ListUpdate::set(vec!["The sprint associated with the task (optional)".to_string()]),
             task_type: Some("The type of the task (optional)".to_string()),
             priority: Some("The priority level of the task (optional)".to_string()),
-            followers: Vec::new(),
+            followers: ListUpdate {
+                add: vec!["A follower added to the task (optional)".to_string()],
+                remove: vec!["A follower removed from the task (optional)".to_string()],
+                ..ListUpdate::default()
+            },
+            tags: ListUpdate {
+                add: vec!["A tag added to the task (optional)".to_string()],
+                ..ListUpdate::default()
+            },
+            components: ListUpdate::default(),
+            affected_versions: ListUpdate::default(),
+            fix_versions: ListUpdate::set(vec!["The version the task is fixed in (optional)".to_string()]),
             attachment_ids: Vec::new(),
             description_attachment_ids: Vec::new(),
+            attachments: Vec::new(),
+            description_attachments: Vec::new(),
+            links: Vec::new(),
+            checklist: Vec::new(),
+            comments: Vec::new(),
+            version: None,
+            fields: IssueFields::example(),
+            error: None,
         }
     }
 }
@@ -366,15 +578,35 @@ impl UpdatedTaskInfo {
     /// * `true` - if all fields are `None` or empty.
     /// * `false` - if at least one field is not `None` or empty.
     pub fn is_empty(&self) -> bool {
-        self.summary.is_none()
+        !self.has_field_changes() && self.links.is_empty() && self.checklist.is_empty() && self.comments.is_empty()
+    }
+
+    /// Checks if the `UpdatedTaskInfo` object changes any field of the issue.
+    ///
+    /// Links, checklist items and comments are created with separate requests and are not taken into account.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - if at least one issue field is not `None` or empty.
+    /// * `false` - if all issue fields are `None` or empty.
+    pub fn has_field_changes(&self) -> bool {
+        !(self.summary.is_none()
             && self.parent.is_none()
+            && self.epic.is_none()
             && self.description.is_none()
-            && self.sprint.is_none()
+            && self.sprint.is_empty()
             && self.task_type.is_none()
             && self.priority.is_none()
             && self.followers.is_empty()
+            && self.tags.is_empty()
+            && self.components.is_empty()
+            && self.affected_versions.is_empty()
+            && self.fix_versions.is_empty()
             && self.attachment_ids.is_empty()
             && self.description_attachment_ids.is_empty()
+            && self.attachments.is_empty()
+            && self.description_attachments.is_empty()
+            && self.fields.is_empty())
     }
 }
 
@@ -497,6 +729,27 @@ mod tests {
         assert!(task.has_required_fields());
     }
 
+    #[test]
+    fn test_created_task_type() {
+        let json_data = r#"{ "queue": "main_queue", "summary": "Bug", "type": "bug", "team": "Backend", "subtasks": [] }"#;
+
+        let task: CreatedTaskInfo = serde_json::from_str(json_data).unwrap();
+        assert_eq!(task.task_type.as_deref(), Some("bug"));
+        assert!(!task.fields.extra.contains_key("type"));
+
+        let written = serde_json::to_value(&task).unwrap();
+        assert_eq!(written["type"], "bug");
+        assert!(written.get("task_type").is_none());
+
+        let body = serde_json::to_string(&CreatedTaskBody::from(task)).unwrap();
+        assert_eq!(body.matches(r#""type""#).count(), 1);
+        assert!(body.contains(r#""team":"Backend""#));
+
+        let updated: UpdatedTaskInfo = serde_json::from_str(r#"{ "issue_id": "TEST-1", "task_type": "bug" }"#).unwrap();
+        assert_eq!(updated.task_type.as_deref(), Some("bug"));
+        assert_eq!(serde_json::to_value(&updated).unwrap()["type"], "bug");
+    }
+
     #[test]
     fn test_created_task_empty_fields() {
         let json_data = r#"
@@ -513,4 +766,33 @@ mod tests {
         assert!(!task.has_required_fields());
         assert!(task.queue == Config::global().default_queue);
     }
+
+    #[test]
+    fn test_created_task_with_unique() {
+        let json_data = r#"{ "queue": "main_queue", "summary": "Write tests", "subtasks": [] }"#;
+
+        let task: CreatedTaskInfo = serde_json::from_str(json_data).unwrap();
+        assert_eq!(task.with_unique("batch-run-1").unique.as_deref(), Some("batch-run-1"));
+
+        let declared = CreatedTaskInfo {
+            unique: Some("tests".to_string()),
+            ..task
+        };
+        assert_eq!(declared.with_unique("batch-run-1").unique.as_deref(), Some("tests"));
+    }
+
+    #[test]
+    fn test_task_templates() {
+        let created = serde_json::to_value(CreatedTaskInfo::default()).unwrap();
+        assert_eq!(created["deadline"], "2024-06-28");
+        assert_eq!(created["originalEstimation"], "PT16H");
+        assert!(created["fixVersions"].is_array());
+        let created: CreatedTaskInfo = serde_json::from_value(created).unwrap();
+        assert_eq!(created.fields, IssueFields::example());
+
+        let updated = serde_json::to_value(UpdatedTaskInfo::default()).unwrap();
+        assert_eq!(updated["tags"], json!({ "add": ["A tag added to the task (optional)"] }));
+        let updated: UpdatedTaskInfo = serde_json::from_value(updated).unwrap();
+        assert_eq!(updated, UpdatedTaskInfo::default());
+    }
 }
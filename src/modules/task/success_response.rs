@@ -1,5 +1,7 @@
 use serde::{Serialize, Deserialize};
 
+use crate::modules::queue::Reference;
+
 /// Represents a successful response from the Yandex Tracker API.
 ///
 /// This struct captures details about the created or retrieved entity, 
@@ -30,6 +32,10 @@ pub struct SuccessResponse {
     
     /// The status of the entity.
     pub status: Status,
+
+    /// The queue of the entity.
+    #[serde(default)]
+    pub queue: Option<Reference>,
     
     /// Information about the user who created the entity.
     ///
@@ -53,7 +59,7 @@ pub struct SuccessResponse {
 /// Represents the status of an entity in the Yandex Tracker API.
 ///
 /// This struct captures the ID, key, and display name of the status.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Status {
     /// The unique identifier of the status.
     pub id: String,
@@ -68,7 +74,7 @@ pub struct Status {
 /// Represents a user in the Yandex Tracker API.
 ///
 /// This struct captures the URL, ID, and display name of the user.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct User {
     /// The URL of the user.
     ///
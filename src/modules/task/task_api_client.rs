@@ -1,5 +1,7 @@
-use reqwest::Client;
+use reqwest::{Client, Method, RequestBuilder};
+use serde::de::DeserializeOwned;
 use thiserror::Error;
+use super::task_attachment::AttachmentError;
 use super::CreatedTaskBody;
 use super::success_response::SuccessResponse;
 use super::error_response::ErrorResponse;
@@ -23,8 +25,74 @@ pub enum HandleResponseError {
     /// Represents an input/output error.
     #[error("IO error: {0}")]
     IO(#[from] std::io::Error),
+
+    /// Represents local attachments that cannot be uploaded.
+    #[error("Attachment error: {0}")]
+    Attachment(#[from] AttachmentError),
+
+    /// Represents a link that points to a task of the batch which has not been created.
+    #[error("Unresolved link target: {0}")]
+    UnresolvedLink(String),
+
+    /// Represents a `${id}` reference to a task of the batch which has not been created.
+    #[error("Unresolved reference: ${{{0}}}")]
+    UnresolvedReference(String),
+
+    /// Represents an issue that no transition of its workflow can close.
+    #[error("No transition closes issue {0}")]
+    NoCloseTransition(String),
+
+    /// Represents an update rejected because the issue was changed by someone else since the version
+    /// the update was written against; `fields` lists the fields both changed, if known.
+    #[error("Issue {issue} was changed since version {expected}{}", conflicting_fields(.fields))]
+    VersionConflict {
+        issue: String,
+        expected: u32,
+        fields: Vec<String>,
+    },
+
+    /// Represents an issue that no transition of its workflow moves to the requested status.
+    #[error("No transition moves issue {0} to status {1}")]
+    NoTransition(String, String),
 }
 
+/// Formats the fields of a version conflict for its message.
+fn conflicting_fields(fields: &[String]) -> String {
+    if fields.is_empty() {
+        String::new()
+    } else {
+        format!(", conflicting fields: {}", fields.join(", "))
+    }
+}
+
+impl HandleResponseError {
+    /// Returns the HTTP status code of an API error response.
+    ///
+    /// # Returns
+    ///
+    /// The status code, or `None` if the error is not an API error response.
+    pub fn status_code(&self) -> Option<u32> {
+        match self {
+            HandleResponseError::Response(response) => Some(response.status_code),
+            _ => None,
+        }
+    }
+
+    /// Checks if the error is a `404 Not Found` API error response.
+    pub fn is_not_found(&self) -> bool {
+        self.status_code() == Some(404)
+    }
+
+    /// Checks if the error is a `409 Conflict` API error response,
+    /// e.g. an issue with the same `unique` already exists.
+    pub fn is_conflict(&self) -> bool {
+        self.status_code() == Some(409)
+    }
+}
+
+/// The base URL of the Yandex Tracker API.
+pub const API_BASE_URL: &str = "https://api.tracker.yandex.net/v2";
+
 /// A client for interacting with the Yandex Tracker API for task management.
 #[derive(Debug, Clone)]
 pub struct TaskApiClient {
@@ -36,6 +104,9 @@ pub struct TaskApiClient {
 
     /// The organization ID for the Yandex Tracker API.
     org_id: String,
+
+    /// The base URL requests are sent to, `API_BASE_URL` by default.
+    base_url: String,
 }
 
 impl TaskApiClient {
@@ -50,9 +121,20 @@ impl TaskApiClient {
             client: Client::new(),
             token,
             org_id,
+            base_url: API_BASE_URL.to_string(),
         }
     }
 
+    /// Sends the requests of the client to another server, e.g. a local one in tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The URL the endpoint paths are appended to, without a trailing `/`.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     /// Creates a new task in the Yandex Tracker.
     ///
     /// # Arguments
@@ -68,10 +150,7 @@ impl TaskApiClient {
         task_data: CreatedTaskBody,
     ) -> Result<SuccessResponse, HandleResponseError> {
         let response = self
-            .client
-            .post("https://api.tracker.yandex.net/v2/issues")
-            .header("Authorization", format!("OAuth {}", self.token))
-            .header("X-Org-ID", &self.org_id)
+            .request(Method::POST, "/issues")
             .json(&task_data)
             .send()
             .await?;
@@ -79,6 +158,66 @@ impl TaskApiClient {
         TaskApiClient::handle_response(response).await
     }
 
+    /// Reads a task from the Yandex Tracker.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the task.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `SuccessResponse` with the task,
+    /// or `HandleResponseError` if an error occurred.
+    pub async fn get_task(&self, issue_id: &str) -> Result<SuccessResponse, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/issues/{}", issue_id))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Finds the task created with a `unique` value.
+    ///
+    /// # Arguments
+    ///
+    /// * `unique` - The `unique` the task was created with.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the task, `None` if no task has this `unique`,
+    /// or `HandleResponseError` if an error occurred.
+    pub async fn find_task_by_unique(
+        &self,
+        unique: &str,
+    ) -> Result<Option<SuccessResponse>, HandleResponseError> {
+        let response = self
+            .request(Method::POST, "/issues/_search")
+            .json(&serde_json::json!({ "filter": { "unique": unique } }))
+            .send()
+            .await?;
+
+        let tasks: Vec<SuccessResponse> = TaskApiClient::handle_response(response).await?;
+        Ok(tasks.into_iter().next())
+    }
+
+    /// Builds an authorized request to the Yandex Tracker API.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The HTTP method of the request.
+    /// * `path` - The endpoint path relative to the base URL, starting with `/`.
+    ///
+    /// # Returns
+    ///
+    /// A `RequestBuilder` with the `Authorization` and `X-Org-ID` headers set.
+    pub(crate) fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.base_url, path))
+            .header("Authorization", format!("OAuth {}", self.token))
+            .header("X-Org-ID", &self.org_id)
+    }
+
     /// Handles the response from the Yandex Tracker API.
     ///
     /// # Arguments
@@ -87,21 +226,44 @@ impl TaskApiClient {
     ///
     /// # Returns
     ///
-    /// A `Result` containing `SuccessResponse` if the response indicates success,
+    /// A `Result` containing the deserialized body if the response indicates success,
     /// or `HandleResponseError` if an error occurred.
-    async fn handle_response(
+    pub(crate) async fn handle_response<T: DeserializeOwned>(
         response: reqwest::Response,
-    ) -> Result<SuccessResponse, HandleResponseError> {
+    ) -> Result<T, HandleResponseError> {
         let status = response.status();
         let text = response.text().await?;
 
-        if status == 200 || status == 201 {
+        if status.is_success() {
             Ok(serde_json::from_str(&text)?)
         } else {
             Err(HandleResponseError::Response(serde_json::from_str(&text)?))
         }
     }
 
+    /// Handles a response from the Yandex Tracker API that carries no body on success.
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - The HTTP response received from the API.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result` if the response indicates success,
+    /// or `HandleResponseError` if an error occurred.
+    pub(crate) async fn handle_empty_response(
+        response: reqwest::Response,
+    ) -> Result<(), HandleResponseError> {
+        let status = response.status();
+
+        if status.is_success() {
+            Ok(())
+        } else {
+            let text = response.text().await?;
+            Err(HandleResponseError::Response(serde_json::from_str(&text)?))
+        }
+    }
+
     /// Updates an existing task in the Yandex Tracker.
     ///
     /// # Arguments
@@ -111,22 +273,63 @@ impl TaskApiClient {
     /// # Returns
     ///
     /// A `Result` containing `SuccessResponse` if the task was updated successfully,
-    /// or `HandleResponseError` if an error occurred.
+    /// `HandleResponseError::VersionConflict` if the update carries a version and the issue has
+    /// changed since, or another `HandleResponseError` if an error occurred.
     pub async fn update_task(
         &self,
         issue_id: &str,
         update_task: UpdatedTask,
     ) -> Result<SuccessResponse, HandleResponseError> {
-        let response = self
-            .client
-            .patch(&format!("https://api.tracker.yandex.net/v2/issues/{}", issue_id))
-            .header("Authorization", format!("OAuth {}", self.token))
-            .header("X-Org-ID", &self.org_id)
-            .json(&update_task)
-            .send()
-            .await?;
+        let mut request = self.request(Method::PATCH, &format!("/issues/{}", issue_id));
+        if let Some(version) = update_task.version {
+            request = request.query(&[("version", version)]);
+        }
+        let response = request.json(&update_task).send().await?;
 
-        TaskApiClient::handle_response(response).await
+        // Without a version, a conflict is not about the issue having changed, e.g. a duplicate `unique`.
+        match (TaskApiClient::handle_response(response).await, update_task.version) {
+            (Err(err), Some(expected)) if err.is_conflict() => Err(HandleResponseError::VersionConflict {
+                issue: issue_id.to_string(),
+                expected,
+                fields: Vec::new(),
+            }),
+            (result, _) => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use warp::http::StatusCode;
+    use warp::Filter;
+
+    #[tokio::test]
+    async fn test_update_conflict() {
+        let conflict = warp::patch().and(warp::path!("issues" / String)).map(|_| {
+            warp::reply::with_status(
+                warp::reply::json(&json!({ "errorMessages": ["Conflict"], "statusCode": 409 })),
+                StatusCode::CONFLICT,
+            )
+        });
+        let (address, server) = warp::serve(conflict).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let api_client = TaskApiClient::new(String::new(), String::new())
+            .with_base_url(&format!("http://{}", address));
+        let mut update: UpdatedTask = serde_json::from_value(json!({ "summary": "Validate cards" })).unwrap();
+
+        let err = api_client.update_task("TEST-1", update.clone()).await.unwrap_err();
+        assert!(err.is_conflict(), "{:?}", err);
+
+        update.version = Some(3);
+        let err = api_client.update_task("TEST-1", update).await.unwrap_err();
+        assert!(
+            matches!(err, HandleResponseError::VersionConflict { ref issue, expected: 3, .. } if issue == "TEST-1"),
+            "{:?}",
+            err
+        );
     }
 }
 
@@ -0,0 +1,355 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use reqwest::multipart::{Form, Part};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::success_response::User;
+use super::task_api_client::{HandleResponseError, TaskApiClient};
+
+/// The maximum size of a file that can be attached to an issue, in bytes.
+pub const MAX_ATTACHMENT_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Represents errors detected while inspecting a local file before it is uploaded.
+#[derive(Debug, Error)]
+pub enum AttachmentError {
+    /// The file cannot be read.
+    #[error("Cannot read attachment {0}: {1}")]
+    Read(PathBuf, std::io::Error),
+
+    /// The path does not point to a regular file.
+    #[error("Attachment {0} is not a file")]
+    NotAFile(PathBuf),
+
+    /// The file is empty.
+    #[error("Attachment {0} is empty")]
+    Empty(PathBuf),
+
+    /// The file exceeds `MAX_ATTACHMENT_SIZE`.
+    #[error("Attachment {0} is {1} bytes, the limit is {MAX_ATTACHMENT_SIZE} bytes")]
+    TooLarge(PathBuf, u64),
+
+    /// The MIME type cannot be detected from the file extension.
+    #[error("Cannot detect the MIME type of attachment {0}")]
+    UnknownMimeType(PathBuf),
+
+    /// Several files failed the inspection.
+    #[error("{}", .0.iter().map(|err| err.to_string()).collect::<Vec<_>>().join("\n"))]
+    Multiple(Vec<AttachmentError>),
+}
+
+/// Represents a local file that passed the inspection and can be uploaded.
+///
+/// # Fields
+///
+/// * `path` - The path to the file.
+/// * `file_name` - The name the file is uploaded with.
+/// * `mime_type` - The detected MIME type of the file.
+/// * `size` - The size of the file in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalAttachment {
+    pub path: PathBuf,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size: u64,
+}
+
+impl LocalAttachment {
+    /// Inspects a local file before it is uploaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<LocalAttachment, AttachmentError>` - The inspected file or the reason it cannot be uploaded.
+    pub fn inspect(path: &Path) -> Result<LocalAttachment, AttachmentError> {
+        let metadata = fs::metadata(path).map_err(|err| AttachmentError::Read(path.to_path_buf(), err))?;
+
+        if !metadata.is_file() {
+            return Err(AttachmentError::NotAFile(path.to_path_buf()));
+        }
+        if metadata.len() == 0 {
+            return Err(AttachmentError::Empty(path.to_path_buf()));
+        }
+        if metadata.len() > MAX_ATTACHMENT_SIZE {
+            return Err(AttachmentError::TooLarge(path.to_path_buf(), metadata.len()));
+        }
+
+        let mime_type = mime_guess::from_path(path)
+            .first()
+            .ok_or_else(|| AttachmentError::UnknownMimeType(path.to_path_buf()))?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| AttachmentError::NotAFile(path.to_path_buf()))?;
+
+        Ok(LocalAttachment {
+            path: path.to_path_buf(),
+            file_name,
+            mime_type: mime_type.essence_str().to_string(),
+            size: metadata.len(),
+        })
+    }
+
+    /// Inspects several local files and reports every file that cannot be uploaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - The paths to the files.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<LocalAttachment>, AttachmentError>` - The inspected files, or `AttachmentError::Multiple`
+    ///   listing every failure.
+    pub fn inspect_all<'a>(
+        paths: impl IntoIterator<Item = &'a PathBuf>,
+    ) -> Result<Vec<LocalAttachment>, AttachmentError> {
+        let mut attachments = Vec::new();
+        let mut errors = Vec::new();
+
+        for path in paths {
+            match LocalAttachment::inspect(path) {
+                Ok(attachment) => attachments.push(attachment),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(attachments),
+            false => Err(AttachmentError::Multiple(errors)),
+        }
+    }
+
+    /// Builds the multipart form used to upload the file.
+    async fn form(&self) -> Result<Form, HandleResponseError> {
+        let bytes = tokio::fs::read(&self.path).await?;
+        let part = Part::bytes(bytes)
+            .file_name(self.file_name.clone())
+            .mime_str(&self.mime_type)?;
+
+        Ok(Form::new().part("file", part))
+    }
+}
+
+/// Represents an attachment returned by the Yandex Tracker API.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Attachment {
+    /// The URL of the attachment.
+    ///
+    /// This field is serialized/deserialized as `self`.
+    #[serde(rename = "self")]
+    pub url: String,
+
+    /// The unique identifier of the attachment.
+    pub id: String,
+
+    /// The file name of the attachment.
+    pub name: String,
+
+    /// The download URL of the attachment.
+    pub content: String,
+
+    /// Information about the user who uploaded the attachment.
+    ///
+    /// This field is serialized/deserialized as `createdBy`.
+    #[serde(rename = "createdBy")]
+    pub created_by: Option<User>,
+
+    /// The timestamp when the attachment was uploaded.
+    ///
+    /// This field is serialized/deserialized as `createdAt`.
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<String>,
+
+    /// The MIME type of the attachment.
+    pub mimetype: Option<String>,
+
+    /// The size of the attachment in bytes.
+    pub size: Option<u64>,
+}
+
+impl TaskApiClient {
+    /// Uploads a temporary attachment that is not bound to an issue yet.
+    ///
+    /// The identifier of the returned attachment can be passed in `attachmentIds`
+    /// or `descriptionAttachmentIds` when creating or updating an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `attachment` - The inspected local file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the uploaded attachment, or `HandleResponseError` if an error occurred.
+    pub async fn upload_temporary_attachment(
+        &self,
+        attachment: &LocalAttachment,
+    ) -> Result<Attachment, HandleResponseError> {
+        let response = self
+            .request(Method::POST, "/attachments")
+            .query(&[("filename", &attachment.file_name)])
+            .multipart(attachment.form().await?)
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Uploads an attachment to an existing issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `attachment` - The inspected local file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the uploaded attachment, or `HandleResponseError` if an error occurred.
+    pub async fn upload_attachment(
+        &self,
+        issue_id: &str,
+        attachment: &LocalAttachment,
+    ) -> Result<Attachment, HandleResponseError> {
+        let response = self
+            .request(Method::POST, &format!("/issues/{}/attachments", issue_id))
+            .query(&[("filename", &attachment.file_name)])
+            .multipart(attachment.form().await?)
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Lists the attachments of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the attachments of the issue, or `HandleResponseError` if an error occurred.
+    pub async fn list_attachments(&self, issue_id: &str) -> Result<Vec<Attachment>, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/issues/{}/attachments", issue_id))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Downloads an attachment of an issue to a local file.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `attachment` - The attachment to download.
+    /// * `destination` - The path of the file the attachment is written to.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result`, or `HandleResponseError` if an error occurred.
+    pub async fn download_attachment(
+        &self,
+        issue_id: &str,
+        attachment: &Attachment,
+        destination: &Path,
+    ) -> Result<(), HandleResponseError> {
+        let response = self
+            .request(
+                Method::GET,
+                &format!("/issues/{}/attachments/{}/{}", issue_id, attachment.id, attachment.name),
+            )
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return TaskApiClient::handle_empty_response(response).await;
+        }
+
+        let bytes = response.bytes().await?;
+        tokio::fs::write(destination, bytes).await?;
+        Ok(())
+    }
+
+    /// Deletes an attachment of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `attachment_id` - The identifier of the attachment.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result`, or `HandleResponseError` if an error occurred.
+    pub async fn delete_attachment(
+        &self,
+        issue_id: &str,
+        attachment_id: &str,
+    ) -> Result<(), HandleResponseError> {
+        let response = self
+            .request(
+                Method::DELETE,
+                &format!("/issues/{}/attachments/{}/", issue_id, attachment_id),
+            )
+            .send()
+            .await?;
+
+        TaskApiClient::handle_empty_response(response).await
+    }
+
+    /// Uploads local files as temporary attachments.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - The paths to the files.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the identifiers of the uploaded attachments, in the order of `paths`.
+    pub async fn upload_temporary_attachments(
+        &self,
+        paths: &[PathBuf],
+    ) -> Result<Vec<String>, HandleResponseError> {
+        let mut ids = Vec::new();
+        for attachment in LocalAttachment::inspect_all(paths)? {
+            ids.push(self.upload_temporary_attachment(&attachment).await?.id);
+        }
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_attachment() {
+        let dir = std::env::temp_dir().join("yandex_tracker_api_rust_attachments");
+        fs::create_dir_all(&dir).unwrap();
+
+        let text = dir.join("notes.txt");
+        let empty = dir.join("empty.txt");
+        let unknown = dir.join("data.unknownext");
+        fs::write(&text, "notes").unwrap();
+        fs::write(&empty, "").unwrap();
+        fs::write(&unknown, "data").unwrap();
+
+        let attachment = LocalAttachment::inspect(&text).unwrap();
+        assert_eq!(attachment.file_name, "notes.txt");
+        assert_eq!(attachment.mime_type, "text/plain");
+        assert_eq!(attachment.size, 5);
+
+        let result = LocalAttachment::inspect_all(&[empty, unknown, dir.join("missing.txt")]);
+        match result {
+            Err(AttachmentError::Multiple(errors)) => assert_eq!(errors.len(), 3),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,24 +1,40 @@
 use crate::modules::task::{task_batch_error::TaskBatchError, CreatedTaskInfo};
+use crate::modules::task::task_batch_diagnostics::{escape_pointer_segment, BatchDiagnostics};
+use crate::modules::task::task_batch_format::BatchFormat;
+use crate::modules::task::task_batch_template::ExpandedBatch;
+use crate::modules::task::task_link::{PendingLink, TaskLinkInfo};
+use chrono::Local;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use super::UpdatedTaskInfo;
 
 /// Represents a batch of operations to be performed on tasks.
 ///
-/// The `TaskBatch` struct contains sets of tasks to be created, updated, and deleted. It provides methods to
-/// check if the batch is valid and to create a `TaskBatch` instance from a JSON file.
+/// The `TaskBatch` struct contains lists of tasks to be created and updated, processed in file order. It provides
+/// methods to check if the batch is valid and to create a `TaskBatch` instance from a JSON file.
 ///
 /// # Fields
 ///
-/// * `created` - A set of tasks to be created.
-/// * `updated` - A set of tasks to be updated, represented by their issue IDs and updated data.
+/// * `created` - A list of tasks to be created.
+/// * `updated` - A list of tasks to be updated, represented by their issue IDs and updated data.
+/// * `links` - Links of already processed tasks waiting for their targets to be created.
+/// * `resolved` - The keys of the tasks created by the batch, by their `unique`.
+/// * `ids` - The keys of the tasks created by a previous run, by their local `id`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TaskBatch {
-    pub created: HashSet<CreatedTaskInfo>,
-    pub updated: HashSet<UpdatedTaskInfo>,
+    pub created: Vec<CreatedTaskInfo>,
+    pub updated: Vec<UpdatedTaskInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<PendingLink>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub resolved: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub ids: HashMap<String, String>,
 }
 
 impl Default for TaskBatch {
@@ -30,51 +46,60 @@ impl Default for TaskBatch {
     ///
     /// A `TaskBatch` instance with default values.
     fn default() -> Self {
-        let mut created_template_mut: HashSet<CreatedTaskInfo> = HashSet::new();
-        let mut created_task_mut = CreatedTaskInfo::default();
-        let mut subtasks_mut: HashSet<CreatedTaskInfo> = HashSet::new();
+        let created_task = CreatedTaskInfo {
+            subtasks: vec![CreatedTaskInfo::default()],
+            ..CreatedTaskInfo::default()
+        };
 
-        subtasks_mut.insert(CreatedTaskInfo::default());
-        created_task_mut.subtasks = subtasks_mut;
-        created_template_mut.insert(created_task_mut);
-
-        let mut updated_template_mut: HashSet<UpdatedTaskInfo> = HashSet::new();
-        updated_template_mut.insert(UpdatedTaskInfo::default());
+        let created_template_mut: Vec<CreatedTaskInfo> = vec![created_task];
+        let updated_template_mut: Vec<UpdatedTaskInfo> = vec![UpdatedTaskInfo::default()];
 
         return TaskBatch {
             created: created_template_mut,
             updated: updated_template_mut,
+            links: Vec::new(),
+            resolved: HashMap::new(),
+            ids: HashMap::new(),
         }
     }
 }
 
 impl TaskBatch {
-    /// Saves the task batch to a JSON file.
+    /// Saves the task batch to a template file, e.g. **tasks_template.yaml**.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The format of the file.
     ///
     /// # Returns
     ///
-    /// * `Result<(), Box<dyn std::error::Error>>` - An empty result or an error.
-    pub fn save_to_file(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let config_str = serde_json::to_string_pretty(self)?;
-        fs::write("tasks_template.json", config_str)?;
-        Ok(())
+    /// * `Result<PathBuf, Box<dyn std::error::Error>>` - The path of the file or an error.
+    pub fn save_to_file(&self, format: BatchFormat) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let format = format.output_format();
+        let path = PathBuf::from(format!("tasks_template.{}", format.extension()));
+        fs::write(&path, format.to_string(self)?)?;
+        Ok(path)
     }
 
     /// Checks if the `TaskBatch` is valid.
     ///
-    /// This method returns `true` if all tasks in `created` and `updated` sets are valid.
+    /// This method returns `true` if all tasks in `created` and `updated` lists are valid.
     ///
     /// # Returns
     ///
     /// * `true` - if all tasks are valid.
     /// * `false` - if at least one task is invalid.
     pub fn is_valid(&self) -> bool {
-        !self.has_invalid_created_tasks() && !self.has_invalid_updated_tasks()
+        !self.has_invalid_created_tasks()
+            && !self.has_invalid_updated_tasks()
+            && !self.has_invalid_links()
+            && !self.has_duplicates()
+            && self.reference_problems().is_empty()
     }
 
-    /// Checks if there are any invalid tasks in the `created` set.
+    /// Checks if there are any invalid tasks in the `created` list.
     ///
-    /// This method returns `true` if there is at least one invalid task in the `created` set.
+    /// This method returns `true` if there is at least one invalid task in the `created` list.
     ///
     /// # Returns
     ///
@@ -89,9 +114,9 @@ impl TaskBatch {
         false
     }
 
-    /// Checks if there are any invalid tasks in the `updated` set.
+    /// Checks if there are any invalid tasks in the `updated` list.
     ///
-    /// This method returns `true` if there is at least one invalid task in the `updated` set.
+    /// This method returns `true` if there is at least one invalid task in the `updated` list.
     ///
     /// # Returns
     ///
@@ -106,14 +131,90 @@ impl TaskBatch {
         false
     }
 
-    /// Creates an instance of `TaskBatch` from a JSON file.
+    /// Checks if there are any links that cannot be resolved.
+    ///
+    /// A link is invalid if it does not refer to exactly one target, or if it refers to a task
+    /// that is neither created by the batch nor already resolved.
     ///
-    /// This method reads a JSON file from the given path and deserializes it into a `TaskBatch` instance.
-    /// It also checks if the batch is valid.
+    /// # Returns
+    ///
+    /// * `true` - if there is at least one invalid link.
+    /// * `false` - if all links are valid.
+    pub fn has_invalid_links(&self) -> bool {
+        let mut uniques: HashSet<&String> = self.resolved.keys().collect();
+        let mut links: Vec<&TaskLinkInfo> = self.links.iter().map(|pending| &pending.link).collect();
+
+        let mut stack: Vec<&CreatedTaskInfo> = self.created.iter().collect();
+        while let Some(task) = stack.pop() {
+            uniques.extend(task.unique.iter());
+            links.extend(task.links.iter());
+            stack.extend(task.subtasks.iter());
+        }
+        for updated_task in &self.updated {
+            links.extend(updated_task.links.iter());
+        }
+
+        links.iter().any(|link| {
+            !link.has_single_target()
+                || link.task.as_ref().is_some_and(|task| !uniques.contains(task))
+        })
+    }
+
+    /// Checks if the batch declares the same task twice.
+    ///
+    /// Two created tasks are duplicates if they share a `unique`, including the `unique`s already
+    /// resolved by a previous run. Two updated tasks are duplicates if they update the same issue.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - if there is at least one duplicate.
+    /// * `false` - if every task is declared once.
+    pub fn has_duplicates(&self) -> bool {
+        let mut uniques: HashSet<&String> = self.resolved.keys().collect();
+        let mut stack: Vec<&CreatedTaskInfo> = self.created.iter().collect();
+        while let Some(task) = stack.pop() {
+            if task.unique.as_ref().is_some_and(|unique| !uniques.insert(unique)) {
+                return true;
+            }
+            stack.extend(task.subtasks.iter());
+        }
+
+        let mut issues: HashSet<&String> = HashSet::new();
+        self.updated.iter().any(|task| !issues.insert(&task.issue_id))
+    }
+
+    /// Collects the local files referenced by the tasks of the batch.
+    ///
+    /// # Returns
+    ///
+    /// The paths listed in `attachments` and `descriptionAttachments` of every created task,
+    /// its subtasks and every updated task.
+    pub fn local_attachments(&self) -> Vec<&PathBuf> {
+        let mut paths = Vec::new();
+
+        let mut stack: Vec<&CreatedTaskInfo> = self.created.iter().collect();
+        while let Some(task) = stack.pop() {
+            paths.extend(task.attachments.iter());
+            paths.extend(task.description_attachments.iter());
+            stack.extend(task.subtasks.iter());
+        }
+        for updated_task in &self.updated {
+            paths.extend(updated_task.attachments.iter());
+            paths.extend(updated_task.description_attachments.iter());
+        }
+
+        paths
+    }
+
+    /// Creates an instance of `TaskBatch` from a batch file.
+    ///
+    /// This method reads a batch file from the given path and deserializes it into a `TaskBatch` instance,
+    /// detecting its format from the extension, see `BatchFormat::from_path`. It also checks if the batch
+    /// is valid, collecting every problem of the file instead of stopping at the first one.
     ///
     /// # Arguments
     ///
-    /// * `path` - The path to the JSON file.
+    /// * `path` - The path to the batch file.
     ///
     /// # Returns
     ///
@@ -121,14 +222,254 @@ impl TaskBatch {
     ///
     /// # Errors
     ///
-    /// * `TaskBatchError::InvalidTaskError` - if the batch is empty.
+    /// * `TaskBatchError::ReadError` - if the file cannot be read.
+    /// * `TaskBatchError::InvalidTasks` - if the file cannot be parsed or any entry is invalid.
     pub fn create_from_path(path: &Path) -> Result<TaskBatch, TaskBatchError> {
-        let tasks_json = fs::read_to_string(path)?;
-        let tasks: TaskBatch = serde_json::from_str(&tasks_json)?;
+        TaskBatch::create_from_path_with_vars(path, BatchFormat::from_path(path), &HashMap::new())
+    }
+
+    /// Creates an instance of `TaskBatch` from a batch file using variables, date helpers and loops.
+    ///
+    /// The templates of the file are expanded first, see `ExpandedBatch::expand`; undefined variables
+    /// and malformed expressions are reported at their location in the file, like any other problem.
+    /// Problems of entries repeated by a loop are reported at the entry of the loop.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the batch file.
+    /// * `format` - The format of the file.
+    /// * `vars` - The variables set on the command line, overriding the `vars` of the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<TaskBatch, TaskBatchError>` - indicating success or failure.
+    ///
+    /// # Errors
+    ///
+    /// * `TaskBatchError::ReadError` - if the file cannot be read.
+    /// * `TaskBatchError::InvalidTasks` - if the file cannot be parsed, a template cannot be expanded or any
+    ///   entry is invalid.
+    pub fn create_from_path_with_vars(
+        path: &Path,
+        format: BatchFormat,
+        vars: &HashMap<String, String>,
+    ) -> Result<TaskBatch, TaskBatchError> {
+        let source = fs::read_to_string(path)?;
+        let mut diagnostics = BatchDiagnostics::new(path);
+
+        let document = match format.parse(&source) {
+            Ok(document) => document,
+            Err(err) => {
+                diagnostics.push_syntax_error(err);
+                return Err(TaskBatchError::InvalidTasks(diagnostics));
+            }
+        };
+        let positions = format.locate_values(&source);
+
+        let expanded = match ExpandedBatch::expand(&document, vars, Local::now().date_naive()) {
+            Ok(expanded) => expanded,
+            Err(problems) => {
+                diagnostics.extend_at(&positions, &document, problems);
+                return Err(TaskBatchError::InvalidTasks(diagnostics));
+            }
+        };
 
-        match tasks.is_valid() {
-            true => Ok(tasks),
-            false => Err(TaskBatchError::InvalidTaskError),
+        let problems = TaskBatch::diagnose(&expanded.document)
+            .into_iter()
+            .map(|(pointer, message)| (expanded.source_pointer(&pointer), message))
+            .collect();
+        diagnostics.extend_at(&positions, &document, problems);
+        if !diagnostics.is_empty() {
+            return Err(TaskBatchError::InvalidTasks(diagnostics));
+        }
+
+        Ok(serde_json::from_value(expanded.document)?)
+    }
+
+    /// Finds every problem of a batch document.
+    ///
+    /// Each entry is deserialized on its own, so that a broken entry does not hide the problems
+    /// of the others, and is then checked for required fields, duplicates and resolvable links.
+    /// Local ids and `${id}` references are checked last, see `reference_problems`.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The parsed batch file.
+    ///
+    /// # Returns
+    ///
+    /// The JSON pointers and descriptions of the problems, in document order.
+    pub fn diagnose(document: &Value) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        let mut uniques: HashMap<String, String> = HashMap::new();
+        let mut issues: HashMap<String, String> = HashMap::new();
+        let mut link_tasks: Vec<(String, String)> = Vec::new();
+
+        if let Some(resolved) = document.get("resolved").and_then(Value::as_object) {
+            uniques.extend(
+                resolved
+                    .keys()
+                    .map(|unique| (unique.clone(), format!("/resolved/{}", escape_pointer_segment(unique)))),
+            );
+        }
+
+        match document.get("created").and_then(Value::as_array) {
+            Some(created) => {
+                for (index, entry) in created.iter().enumerate() {
+                    TaskBatch::diagnose_created(
+                        &format!("/created/{}", index),
+                        entry,
+                        &mut problems,
+                        &mut uniques,
+                        &mut link_tasks,
+                    );
+                }
+            }
+            None => problems.push((String::new(), "Field `created` must be a list of tasks".to_string())),
+        }
+
+        match document.get("updated").and_then(Value::as_array) {
+            Some(updated) => {
+                for (index, entry) in updated.iter().enumerate() {
+                    let pointer = format!("/updated/{}", index);
+                    if let Some(task) = diagnose_entry::<UpdatedTaskInfo>(&pointer, entry.clone(), &mut problems) {
+                        if task.is_empty() {
+                            problems.push((pointer.clone(), "Update cannot be empty".to_string()));
+                        }
+                        if let Some(first) = issues.get(&task.issue_id) {
+                            problems.push((
+                                format!("{}/issue_id", pointer),
+                                format!("Issue `{}` is already updated by {}", task.issue_id, first),
+                            ));
+                        } else {
+                            issues.insert(task.issue_id.clone(), pointer.clone());
+                        }
+                        diagnose_links(&pointer, &task.links, &mut problems, &mut link_tasks);
+                    }
+                }
+            }
+            None => problems.push((String::new(), "Field `updated` must be a list of tasks".to_string())),
+        }
+
+        for (pointer, task) in link_tasks {
+            if !uniques.contains_key(&task) {
+                problems.push((pointer, format!("No task with unique `{}` in the batch", task)));
+            }
+        }
+
+        // References span entries, so they are only checked once every entry is valid.
+        if problems.is_empty() {
+            if let Ok(task_batch) = serde_json::from_value::<TaskBatch>(document.clone()) {
+                problems.extend(task_batch.reference_problems());
+            }
+        }
+
+        problems
+    }
+
+    /// Finds the problems of a task to be created and of its subtasks.
+    fn diagnose_created(
+        pointer: &str,
+        entry: &Value,
+        problems: &mut Vec<(String, String)>,
+        uniques: &mut HashMap<String, String>,
+        link_tasks: &mut Vec<(String, String)>,
+    ) {
+        // Subtasks are diagnosed on their own below.
+        let mut own = entry.clone();
+        if let Some(subtasks) = own.get_mut("subtasks").filter(|subtasks| subtasks.is_array()) {
+            *subtasks = Value::Array(Vec::new());
+        }
+
+        if let Some(task) = diagnose_entry::<CreatedTaskInfo>(pointer, own, problems) {
+            if task.summary.is_empty() {
+                problems.push((format!("{}/summary", pointer), "Summary cannot be empty".to_string()));
+            }
+            if task.queue.is_empty() {
+                problems.push((format!("{}/queue", pointer), "Queue cannot be empty".to_string()));
+            }
+            if let Some(unique) = &task.unique {
+                match uniques.get(unique) {
+                    Some(first) => problems.push((
+                        format!("{}/unique", pointer),
+                        format!("Unique `{}` is already used by {}", unique, first),
+                    )),
+                    None => {
+                        uniques.insert(unique.clone(), pointer.to_string());
+                    }
+                }
+            }
+            diagnose_links(pointer, &task.links, problems, link_tasks);
+        }
+
+        if let Some(subtasks) = entry.get("subtasks").and_then(Value::as_array) {
+            for (index, subtask) in subtasks.iter().enumerate() {
+                TaskBatch::diagnose_created(
+                    &format!("{}/subtasks/{}", pointer, index),
+                    subtask,
+                    problems,
+                    uniques,
+                    link_tasks,
+                );
+            }
+        }
+    }
+}
+
+/// Deserializes a single entry of a batch, recording the location of the error if it fails.
+///
+/// # Arguments
+///
+/// * `pointer` - The JSON pointer of the entry.
+/// * `entry` - The entry to deserialize.
+/// * `problems` - The problems found so far.
+///
+/// # Returns
+///
+/// The deserialized entry, or `None` if it is invalid.
+fn diagnose_entry<T: DeserializeOwned>(
+    pointer: &str,
+    entry: Value,
+    problems: &mut Vec<(String, String)>,
+) -> Option<T> {
+    match serde_path_to_error::deserialize::<_, T>(entry) {
+        Ok(task) => Some(task),
+        Err(err) => {
+            let mut error_pointer = pointer.to_string();
+            for segment in err.path().iter() {
+                match segment {
+                    serde_path_to_error::Segment::Seq { index } => {
+                        error_pointer.push_str(&format!("/{}", index))
+                    }
+                    serde_path_to_error::Segment::Map { key } => {
+                        error_pointer.push_str(&format!("/{}", escape_pointer_segment(key)))
+                    }
+                    _ => {}
+                }
+            }
+            problems.push((error_pointer, err.into_inner().to_string()));
+            None
+        }
+    }
+}
+
+/// Records the links of an entry that do not refer to exactly one target, and collects the
+/// `unique`s of the tasks the links refer to.
+fn diagnose_links(
+    pointer: &str,
+    links: &[TaskLinkInfo],
+    problems: &mut Vec<(String, String)>,
+    link_tasks: &mut Vec<(String, String)>,
+) {
+    for (index, link) in links.iter().enumerate() {
+        let link_pointer = format!("{}/links/{}", pointer, index);
+        if !link.has_single_target() {
+            problems.push((
+                link_pointer,
+                "Link must refer to exactly one of `issue` and `task`".to_string(),
+            ));
+        } else if let Some(task) = &link.task {
+            link_tasks.push((format!("{}/task", link_pointer), task.clone()));
         }
     }
 }
@@ -243,4 +584,158 @@ mod tests {
         let task_batch: TaskBatch = serde_json::from_str(json_data).unwrap();
         assert!(!task_batch.is_valid());
     }
+
+    #[test]
+    fn test_task_batch_links() {
+        let json_data = r#"
+        {
+            "created": [
+                {
+                    "queue": "main_queue",
+                    "summary": "Epic",
+                    "unique": "epic",
+                    "subtasks": []
+                },
+                {
+                    "queue": "main_queue",
+                    "summary": "Story",
+                    "links": [
+                        { "relationship": "has epic", "task": "epic" },
+                        { "relationship": "relates", "issue": "TASK-1" }
+                    ],
+                    "subtasks": []
+                }
+            ],
+            "updated": [
+                {
+                    "issue_id": "TASK-2",
+                    "followers": [],
+                    "links": [{ "relationship": "is blocked by", "task": "epic" }]
+                }
+            ]
+        }"#;
+
+        let task_batch: TaskBatch = serde_json::from_str(json_data).unwrap();
+        assert!(task_batch.is_valid());
+
+        let json_data = r#"
+        {
+            "created": [
+                {
+                    "queue": "main_queue",
+                    "summary": "Story",
+                    "links": [{ "relationship": "has epic", "task": "missing" }],
+                    "subtasks": []
+                }
+            ],
+            "updated": []
+        }"#;
+
+        let task_batch: TaskBatch = serde_json::from_str(json_data).unwrap();
+        assert!(!task_batch.is_valid());
+    }
+
+    #[test]
+    fn test_task_batch_diagnose() {
+        let document = serde_json::json!({
+            "created": [
+                {
+                    "queue": "main_queue",
+                    "summary": "",
+                    "links": [{ "relationship": "relates", "task": "missing" }],
+                    "subtasks": [
+                        { "queue": "main_queue", "summary": "Subtask", "priority": 1, "subtasks": [] }
+                    ]
+                }
+            ],
+            "updated": [
+                { "issue_id": "TASK-1", "followers": [] },
+                { "issue_id": "TASK-2", "followers": [], "links": [{ "relationship": "relates" }] }
+            ]
+        });
+
+        let pointers: Vec<String> = TaskBatch::diagnose(&document)
+            .into_iter()
+            .map(|(pointer, _)| pointer)
+            .collect();
+
+        assert_eq!(
+            pointers,
+            vec![
+                "/created/0/summary",
+                "/created/0/subtasks/0/priority",
+                "/updated/0",
+                "/updated/1/links/0",
+                "/created/0/links/0/task",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_task_batch_keeps_order_and_same_summaries() {
+        let json_data = r#"
+        {
+            "created": [
+                {
+                    "queue": "main_queue",
+                    "summary": "Feature",
+                    "subtasks": [
+                        { "queue": "main_queue", "summary": "Write tests", "subtasks": [] },
+                        { "queue": "main_queue", "summary": "Write code", "subtasks": [] },
+                        { "queue": "main_queue", "summary": "Write tests", "subtasks": [] }
+                    ]
+                },
+                { "queue": "main_queue", "summary": "Release", "subtasks": [] }
+            ],
+            "updated": []
+        }"#;
+
+        let task_batch: TaskBatch = serde_json::from_str(json_data).unwrap();
+        assert!(task_batch.is_valid());
+
+        let summaries: Vec<&str> = task_batch.created[0]
+            .subtasks
+            .iter()
+            .map(|task| task.summary.as_str())
+            .collect();
+        assert_eq!(summaries, vec!["Write tests", "Write code", "Write tests"]);
+        assert_eq!(task_batch.created[1].summary, "Release");
+    }
+
+    #[test]
+    fn test_task_batch_duplicates() {
+        let document = serde_json::json!({
+            "created": [
+                {
+                    "queue": "main_queue",
+                    "summary": "Feature",
+                    "unique": "feature",
+                    "subtasks": [
+                        { "queue": "main_queue", "summary": "Feature", "unique": "feature", "subtasks": [] }
+                    ]
+                }
+            ],
+            "updated": [
+                { "issue_id": "TASK-1", "summary": "First", "followers": [] },
+                { "issue_id": "TASK-1", "summary": "Second", "followers": [] }
+            ]
+        });
+
+        assert_eq!(
+            TaskBatch::diagnose(&document),
+            vec![
+                (
+                    "/created/0/subtasks/0/unique".to_string(),
+                    "Unique `feature` is already used by /created/0".to_string()
+                ),
+                (
+                    "/updated/1/issue_id".to_string(),
+                    "Issue `TASK-1` is already updated by /updated/0".to_string()
+                ),
+            ]
+        );
+
+        let task_batch: TaskBatch = serde_json::from_value(document).unwrap();
+        assert!(task_batch.has_duplicates());
+    }
 }
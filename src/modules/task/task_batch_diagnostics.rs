@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use super::task_batch_format::SyntaxError;
+
+/// Represents a single problem found in a batch file.
+///
+/// # Fields
+///
+/// * `file` - The path of the batch file.
+/// * `pointer` - The JSON pointer of the offending value, e.g. `/created/0/summary`.
+/// * `line` - The line of the offending value, starting at 1 (optional).
+/// * `column` - The column of the offending value, starting at 1 (optional).
+/// * `entry` - The summary or the unique of the entry the value belongs to (optional).
+/// * `message` - The description of the problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    pub pointer: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub entry: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    /// Formats the diagnostic like a compiler error:
+    ///
+    /// ```text
+    /// error: Summary cannot be empty
+    ///   --> tasks.json:14:24 (/created/1/summary)
+    ///    = entry: "Write tests"
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "error: {}", self.message)?;
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "  --> {}:{}:{}", self.file.display(), line, column)?,
+            _ => write!(f, "  --> {}", self.file.display())?,
+        }
+        if !self.pointer.is_empty() {
+            write!(f, " ({})", self.pointer)?;
+        }
+        if let Some(entry) = &self.entry {
+            write!(f, "\n   = entry: {}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents all problems found in a batch file.
+///
+/// # Fields
+///
+/// * `file` - The path of the batch file.
+/// * `diagnostics` - The problems, in document order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchDiagnostics {
+    pub file: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl BatchDiagnostics {
+    /// Creates an empty `BatchDiagnostics` instance for a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The path of the batch file.
+    pub fn new(file: &Path) -> Self {
+        BatchDiagnostics {
+            file: file.to_path_buf(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Checks if no problems were found.
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Records a syntax error of the file, at its line and column when the parser reports them.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The syntax error.
+    pub fn push_syntax_error(&mut self, error: SyntaxError) {
+        self.diagnostics.push(Diagnostic {
+            file: self.file.clone(),
+            pointer: String::new(),
+            line: error.position.map(|(line, _)| line),
+            column: error.position.map(|(_, column)| column),
+            entry: None,
+            message: error.message,
+        });
+    }
+
+    /// Records problems identified by JSON pointers into a parsed document.
+    ///
+    /// The line and column of every pointer are looked up in the source text,
+    /// and the entry is described by the `summary`, `unique` or `issue_id` of the
+    /// closest `created`, `subtasks` or `updated` element containing the pointer.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source text of the batch file.
+    /// * `document` - The parsed batch file.
+    /// * `problems` - The JSON pointers and descriptions of the problems.
+    pub fn extend_located(&mut self, source: &str, document: &Value, problems: Vec<(String, String)>) {
+        self.extend_at(&locate_values(source), document, problems);
+    }
+
+    /// Records problems identified by JSON pointers, at the positions of the values they point to.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - The line and column of every value, by JSON pointer; pointers without a
+    ///   position are reported without one.
+    /// * `document` - The parsed batch file.
+    /// * `problems` - The JSON pointers and descriptions of the problems.
+    pub fn extend_at(
+        &mut self,
+        positions: &HashMap<String, (usize, usize)>,
+        document: &Value,
+        problems: Vec<(String, String)>,
+    ) {
+        for (pointer, message) in problems {
+            let position = positions.get(&pointer).copied();
+            self.diagnostics.push(Diagnostic {
+                file: self.file.clone(),
+                entry: describe_entry(document, &pointer),
+                line: position.map(|(line, _)| line),
+                column: position.map(|(_, column)| column),
+                pointer,
+                message,
+            });
+        }
+    }
+}
+
+impl fmt::Display for BatchDiagnostics {
+    /// Formats every diagnostic followed by a summary line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}\n", diagnostic)?;
+        }
+        write!(
+            f,
+            "error: could not load `{}` due to {} previous error{}",
+            self.file.display(),
+            self.diagnostics.len(),
+            if self.diagnostics.len() == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Escapes a key for use as a JSON pointer segment.
+pub fn escape_pointer_segment(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// Describes the batch entry that contains a JSON pointer.
+///
+/// # Returns
+///
+/// A description such as `"Write tests" (unique: tests-1)`, or `None` if the pointer is outside of any entry.
+fn describe_entry(document: &Value, pointer: &str) -> Option<String> {
+    let segments: Vec<&str> = pointer.split('/').skip(1).collect();
+
+    let mut entry_end = None;
+    for index in 1..segments.len() {
+        let is_collection = matches!(segments[index - 1], "created" | "updated" | "subtasks");
+        if is_collection && segments[index].parse::<usize>().is_ok() {
+            entry_end = Some(index + 1);
+        }
+    }
+
+    let entry_pointer: String = segments[..entry_end?]
+        .iter()
+        .map(|segment| format!("/{}", segment))
+        .collect();
+    let entry = document.pointer(&entry_pointer)?;
+
+    let summary = entry.get("summary").and_then(Value::as_str);
+    let unique = entry.get("unique").and_then(Value::as_str);
+    let issue_id = entry.get("issue_id").and_then(Value::as_str);
+
+    match (summary, unique, issue_id) {
+        (Some(summary), Some(unique), _) => Some(format!("\"{}\" (unique: {})", summary, unique)),
+        (Some(summary), None, _) => Some(format!("\"{}\"", summary)),
+        (None, Some(unique), _) => Some(format!("unique: {}", unique)),
+        (None, None, Some(issue_id)) => Some(issue_id.to_string()),
+        (None, None, None) => None,
+    }
+}
+
+/// Finds the line and column where every value of a JSON document starts.
+///
+/// # Arguments
+///
+/// * `source` - The source text of a syntactically valid JSON document.
+///
+/// # Returns
+///
+/// A map from the JSON pointer of every value to its line and column, starting at 1.
+pub fn locate_values(source: &str) -> HashMap<String, (usize, usize)> {
+    let mut locator = JsonLocator {
+        chars: source.chars().collect(),
+        index: 0,
+        line: 1,
+        column: 1,
+        positions: HashMap::new(),
+    };
+    locator.value(String::new());
+    locator.positions
+}
+
+/// A minimal JSON scanner recording the position of every value.
+struct JsonLocator {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+    positions: HashMap<String, (usize, usize)>,
+}
+
+impl JsonLocator {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.advance();
+        }
+    }
+
+    fn value(&mut self, pointer: String) {
+        self.skip_whitespace();
+        self.positions.insert(pointer.clone(), (self.line, self.column));
+
+        match self.peek() {
+            Some('{') => self.object(pointer),
+            Some('[') => self.array(pointer),
+            Some('"') => {
+                self.string();
+            }
+            Some(_) => {
+                while self
+                    .peek()
+                    .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | ']' | '}'))
+                {
+                    self.advance();
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn object(&mut self, pointer: String) {
+        self.advance();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('"') => {
+                    let key = self.string();
+                    self.skip_whitespace();
+                    self.advance();
+                    self.value(format!("{}/{}", pointer, escape_pointer_segment(&key)));
+                }
+                Some(',') => {
+                    self.advance();
+                }
+                Some('}') => {
+                    self.advance();
+                    return;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn array(&mut self, pointer: String) {
+        self.advance();
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.advance();
+                }
+                Some(']') => {
+                    self.advance();
+                    return;
+                }
+                Some(_) => {
+                    self.value(format!("{}/{}", pointer, index));
+                    index += 1;
+                }
+                None => return,
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let mut value = String::new();
+        self.advance();
+        while let Some(c) = self.advance() {
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(escaped) = self.advance() {
+                        value.push(escaped);
+                    }
+                }
+                _ => value.push(c),
+            }
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"{
+    "created": [
+        {
+            "queue": "TEST",
+            "summary": "",
+            "unique": "empty-1"
+        }
+    ],
+    "updated": [{ "issue_id": "TEST-1" }]
+}"#;
+
+    #[test]
+    fn test_locate_values() {
+        let positions = locate_values(SOURCE);
+
+        assert_eq!(positions[""], (1, 1));
+        assert_eq!(positions["/created/0"], (3, 9));
+        assert_eq!(positions["/created/0/summary"], (5, 24));
+        assert_eq!(positions["/updated/0/issue_id"], (9, 31));
+    }
+
+    #[test]
+    fn test_render_diagnostics() {
+        let document: Value = serde_json::from_str(SOURCE).unwrap();
+        let mut diagnostics = BatchDiagnostics::new(Path::new("tasks.json"));
+        diagnostics.extend_located(
+            SOURCE,
+            &document,
+            vec![
+                ("/created/0/summary".to_string(), "Summary cannot be empty".to_string()),
+                ("/updated/0".to_string(), "Update cannot be empty".to_string()),
+            ],
+        );
+
+        assert_eq!(
+            diagnostics.to_string(),
+            "error: Summary cannot be empty\n  --> tasks.json:5:24 (/created/0/summary)\n   = entry: \"\" (unique: empty-1)\n\n\
+             error: Update cannot be empty\n  --> tasks.json:9:17 (/updated/0)\n   = entry: TEST-1\n\n\
+             error: could not load `tasks.json` due to 2 previous errors"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use super::task_batch_diagnostics::BatchDiagnostics;
+
 /// Represents possible errors when working with task batches.
 ///
 /// The `TaskBatchError` enum defines various errors that can occur when reading, deserializing,
@@ -9,19 +11,18 @@ use thiserror::Error;
 ///
 /// * `ReadError` - Indicates an error occurred while reading the file.
 /// * `DeserializeError` - Indicates an error occurred while deserializing the JSON data.
-/// * `EmptyTasksError` - Indicates that the tasks cannot be empty.
-/// * `EmptyTaskOperationsError` - Indicates that the task operations cannot be empty.
+/// * `InvalidTasks` - Indicates that the batch file has problems, with their locations.
 #[derive(Error, Debug)]
 pub enum TaskBatchError {
     /// Error reading the file.
-    #[error("Error reading the file")]
+    #[error("Error reading the file: {0}")]
     ReadError(#[from] std::io::Error),
 
     /// Error deserializing JSON.
-    #[error("Error deserializing JSON")]
+    #[error("Error deserializing JSON: {0}")]
     DeserializeError(#[from] serde_json::Error),
 
-    /// Tasks cannot be empty.
-    #[error("Task invalid")]
-    InvalidTaskError,
+    /// The batch file has problems.
+    #[error("{0}")]
+    InvalidTasks(BatchDiagnostics),
 }
@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::task_batch::TaskBatch;
+use super::task_batch_format::BatchFormat;
+use super::task_journal::{JournalAction, JournalEntry, JournalStatus};
+use super::task_fields::IssueFields;
+use super::task_link::PendingLink;
+use super::task_list_update::ListUpdate;
+use super::{CreatedTaskInfo, UpdatedTaskInfo};
+
+/// The name of the file holding the failed entries of a run, without its extension.
+pub const FAILED_FILE_STEM: &str = "failed";
+
+impl TaskBatch {
+    /// Collects the entries of a run that failed or were not processed into a new batch.
+    ///
+    /// The new batch can be fixed and run directly:
+    ///
+    /// * Created tasks keep the `unique` they were sent with, so tasks that already exist are
+    ///   reused instead of created twice; subtasks of a created task get its key as `parent`.
+    /// * Links of tasks that exist are moved to `links`, and their failed comments to an update
+    ///   of the issue. The keys of the tasks created by the run are listed in `resolved` and `ids`
+    ///   so that links and references to them still resolve.
+    /// * Every failed entry carries the `error` it failed with, which is ignored when the batch is loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `journal` - The entries of the journal of the run.
+    ///
+    /// # Returns
+    ///
+    /// The new batch as a JSON document, or `None` if every entry was processed successfully.
+    pub fn failed_entries(&self, journal: &[JournalEntry]) -> Option<Value> {
+        let mut failures = FailedBatch {
+            outcomes: latest_outcomes(journal),
+            resolved: self.resolved.clone(),
+            ids: self.ids.clone(),
+            created: Vec::new(),
+            updated: Vec::new(),
+            links: Vec::new(),
+        };
+
+        for (index, pending_link) in self.links.iter().enumerate() {
+            failures.collect_link(&format!("$.links[{}]", index), pending_link.clone());
+        }
+        for (index, task) in self.created.iter().enumerate() {
+            failures.collect_created(&format!("$.created[{}]", index), task, None);
+        }
+        for (index, task) in self.updated.iter().enumerate() {
+            failures.collect_updated(&format!("$.updated[{}]", index), task);
+        }
+
+        if failures.created.is_empty() && failures.updated.is_empty() && failures.links.is_empty() {
+            return None;
+        }
+
+        let mut document = Map::new();
+        document.insert("created".to_string(), Value::Array(failures.created));
+        document.insert("updated".to_string(), Value::Array(failures.updated));
+        if !failures.links.is_empty() {
+            document.insert("links".to_string(), Value::Array(failures.links));
+        }
+        if !failures.resolved.is_empty() {
+            document.insert("resolved".to_string(), to_value(&failures.resolved));
+        }
+        if !failures.ids.is_empty() {
+            document.insert("ids".to_string(), to_value(&failures.ids));
+        }
+        Some(Value::Object(document))
+    }
+
+    /// Saves the failed entries of a run as `failed.json`, or `failed.yaml` etc., in the directory of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory of the run.
+    /// * `format` - The format of the batch that was run, see `BatchFormat::output_format`.
+    /// * `journal` - The entries of the journal of the run.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the path of the file, `None` if nothing failed, or `io::Error`.
+    pub fn save_failed_entries(
+        &self,
+        dir: &Path,
+        format: BatchFormat,
+        journal: &[JournalEntry],
+    ) -> Result<Option<PathBuf>, io::Error> {
+        let Some(failed) = self.failed_entries(journal) else {
+            return Ok(None);
+        };
+
+        let format = format.output_format();
+        let path = dir.join(FAILED_FILE_STEM).with_extension(format.extension());
+        let text = format
+            .to_string(&failed)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        fs::write(&path, text)?;
+        Ok(Some(path))
+    }
+}
+
+/// The failed entries of a run, collected while walking its input.
+struct FailedBatch<'a> {
+    /// The latest outcome of every operation, by local id and action.
+    outcomes: HashMap<(&'a str, JournalAction), &'a JournalEntry>,
+
+    /// The keys of the tasks created by the run, by their `unique`.
+    resolved: HashMap<String, String>,
+
+    /// The keys of the tasks created by the run, by their local `id`.
+    ids: HashMap<String, String>,
+
+    /// The created entries to run again.
+    created: Vec<Value>,
+
+    /// The updated entries to run again.
+    updated: Vec<Value>,
+
+    /// The links to create again.
+    links: Vec<Value>,
+}
+
+impl FailedBatch<'_> {
+    /// Returns the latest outcome of an operation.
+    fn outcome(&self, entry: &str, action: JournalAction) -> Option<&JournalEntry> {
+        self.outcomes.get(&(entry, action)).copied()
+    }
+
+    /// Returns the error of an operation that failed or `not processed` if it never ran.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the operation succeeded.
+    fn failure(&self, entry: &str, action: JournalAction) -> Option<String> {
+        match self.outcome(entry, action) {
+            Some(outcome) if outcome.status == JournalStatus::Failed => {
+                Some(outcome.error.clone().unwrap_or_default())
+            }
+            Some(_) => None,
+            None => Some("not processed".to_string()),
+        }
+    }
+
+    /// Collects a created task and its subtasks.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The JSON path of the task in the input.
+    /// * `task` - The task as declared in the input.
+    /// * `parent` - The key and the queue of the parent task, once it exists.
+    fn collect_created(&mut self, path: &str, task: &CreatedTaskInfo, parent: Option<(&str, &str)>) {
+        let pinned = match self.outcome(path, JournalAction::Unique).and_then(|outcome| outcome.unique.as_deref()) {
+            Some(unique) => task.with_unique(unique),
+            // The task was never sent, so the next run may generate another `unique`.
+            None => task.clone(),
+        };
+        let pinned = match parent {
+            Some((parent, queue)) => pinned.set(parent.to_string(), queue.to_string()),
+            None => pinned,
+        };
+
+        let key = match self.outcome(path, JournalAction::Create) {
+            Some(outcome) if outcome.status != JournalStatus::Failed => outcome.key.clone().unwrap_or_default(),
+            _ => {
+                // The whole entry runs again, subtasks included.
+                let error = self.failure(path, JournalAction::Create);
+                self.created.push(with_error(to_value(&pinned), error));
+                return;
+            }
+        };
+
+        let checklist_path = format!("{}.checklist", path);
+        let checklist_error = if task.checklist.is_empty() {
+            None
+        } else {
+            self.failure(&checklist_path, JournalAction::AddChecklist)
+        };
+        if checklist_error.is_some() {
+            // Running the task again reuses the existing issue and adds the missing items.
+            self.created.push(with_error(
+                to_value(&CreatedTaskInfo {
+                    links: Vec::new(),
+                    comments: Vec::new(),
+                    subtasks: Vec::new(),
+                    ..pinned.clone()
+                }),
+                checklist_error,
+            ));
+        } else {
+            if let Some(unique) = &task.unique {
+                self.resolved.insert(unique.clone(), key.clone());
+            }
+            if let Some(id) = &task.id {
+                self.ids.insert(id.clone(), key.clone());
+            }
+        }
+        self.collect_comments(path, &key, &task.comments);
+
+        for (index, link) in task.links.iter().enumerate() {
+            self.collect_link(
+                &format!("{}.links[{}]", path, index),
+                PendingLink {
+                    issue: key.clone(),
+                    link: link.clone(),
+                },
+            );
+        }
+
+        for (index, subtask) in task.subtasks.iter().enumerate() {
+            self.collect_created(
+                &format!("{}.subtasks[{}]", path, index),
+                subtask,
+                Some((&key, &pinned.queue)),
+            );
+        }
+    }
+
+    /// Collects an updated task if its update or its checklist did not succeed.
+    fn collect_updated(&mut self, path: &str, task: &UpdatedTaskInfo) {
+        let update_error = if task.has_field_changes() {
+            self.failure(path, JournalAction::Update)
+        } else {
+            None
+        };
+        let checklist_error = if task.checklist.is_empty() {
+            None
+        } else {
+            self.failure(&format!("{}.checklist", path), JournalAction::AddChecklist)
+        };
+
+        match update_error.or(checklist_error) {
+            Some(error) => {
+                self.updated.push(with_error(to_value(task), Some(error)));
+            }
+            None => {
+                for (index, link) in task.links.iter().enumerate() {
+                    self.collect_link(
+                        &format!("{}.links[{}]", path, index),
+                        PendingLink {
+                            issue: task.issue_id.clone(),
+                            link: link.clone(),
+                        },
+                    );
+                }
+                self.collect_comments(path, &task.issue_id, &task.comments);
+            }
+        }
+    }
+
+    /// Collects the comments of an existing issue that were not posted into an update of the issue.
+    fn collect_comments(&mut self, path: &str, issue_id: &str, comments: &[String]) {
+        let mut error = None;
+        let mut failed = Vec::new();
+        for (index, comment) in comments.iter().enumerate() {
+            if let Some(comment_error) = self.failure(&format!("{}.comments[{}]", path, index), JournalAction::AddComment) {
+                error.get_or_insert(comment_error);
+                failed.push(comment.clone());
+            }
+        }
+        if failed.is_empty() {
+            return;
+        }
+
+        let update = UpdatedTaskInfo {
+            issue_id: issue_id.to_string(),
+            summary: None,
+            parent: None,
+            epic: None,
+            description: None,
+            sprint: ListUpdate::default(),
+            task_type: None,
+            priority: None,
+            followers: ListUpdate::default(),
+            tags: ListUpdate::default(),
+            components: ListUpdate::default(),
+            affected_versions: ListUpdate::default(),
+            fix_versions: ListUpdate::default(),
+            attachment_ids: Vec::new(),
+            description_attachment_ids: Vec::new(),
+            attachments: Vec::new(),
+            description_attachments: Vec::new(),
+            links: Vec::new(),
+            checklist: Vec::new(),
+            comments: failed,
+            version: None,
+            fields: IssueFields::default(),
+            error: None,
+        };
+        self.updated.push(with_error(to_value(&update), error));
+    }
+
+    /// Collects a link if it was not created.
+    fn collect_link(&mut self, path: &str, pending_link: PendingLink) {
+        if let Some(error) = self.failure(path, JournalAction::Link) {
+            self.links.push(with_error(to_value(&pending_link), Some(error)));
+        }
+    }
+}
+
+/// Keeps the latest outcome of every operation of a journal.
+fn latest_outcomes(journal: &[JournalEntry]) -> HashMap<(&str, JournalAction), &JournalEntry> {
+    journal
+        .iter()
+        .map(|journal_entry| ((journal_entry.entry.as_str(), journal_entry.action), journal_entry))
+        .collect()
+}
+
+/// Serializes a value of the batch, which cannot fail for the batch types.
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+/// Adds the `error` of a failed entry to its JSON object.
+fn with_error(mut value: Value, error: Option<String>) -> Value {
+    if let (Value::Object(object), Some(error)) = (&mut value, error) {
+        object.insert("error".to_string(), Value::String(error));
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::task::task_link::TaskLinkInfo;
+    use chrono::Local;
+
+    fn journal_entry(entry: &str, action: JournalAction, key: Option<&str>, error: Option<&str>) -> JournalEntry {
+        JournalEntry {
+            entry: entry.to_string(),
+            action,
+            key: key.map(str::to_string),
+            status: if error.is_some() { JournalStatus::Failed } else { JournalStatus::Ok },
+            error: error.map(str::to_string),
+            original: None,
+            changes: Vec::new(),
+            unique: None,
+            at: Local::now().fixed_offset(),
+        }
+    }
+
+    fn unique_entry(entry: &str, unique: &str) -> JournalEntry {
+        JournalEntry {
+            unique: Some(unique.to_string()),
+            ..journal_entry(entry, JournalAction::Unique, None, None)
+        }
+    }
+
+    #[test]
+    fn test_failed_entries() {
+        let task_batch: TaskBatch = serde_json::from_str(
+            r#"{
+                "created": [
+                    {
+                        "queue": "TEST",
+                        "summary": "Epic",
+                        "unique": "epic",
+                        "id": "epic",
+                        "comments": ["Kick-off done"],
+                        "subtasks": [
+                            { "queue": "TEST", "summary": "Done", "subtasks": [] },
+                            { "queue": "TEST", "summary": "Broken", "subtasks": [] }
+                        ]
+                    },
+                    {
+                        "queue": "TEST",
+                        "summary": "Story",
+                        "links": [{ "relationship": "has epic", "task": "epic" }],
+                        "subtasks": [{ "queue": "TEST", "summary": "Skipped", "subtasks": [] }]
+                    }
+                ],
+                "updated": [
+                    { "issue_id": "TEST-1", "summary": "Renamed", "followers": [] },
+                    { "issue_id": "TEST-2", "summary": "Renamed", "followers": [] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let journal = vec![
+            journal_entry("$.created[0]", JournalAction::Create, Some("TEST-3"), None),
+            journal_entry("$.created[0].comments[0]", JournalAction::AddComment, None, Some("Forbidden")),
+            journal_entry("$.created[0].subtasks[0]", JournalAction::Create, Some("TEST-4"), None),
+            unique_entry("$.created[0].subtasks[1]", "batch-run-1-broken"),
+            journal_entry("$.created[0].subtasks[1]", JournalAction::Create, None, Some("Bad type")),
+            unique_entry("$.created[1]", "batch-run-1-story"),
+            journal_entry("$.created[1]", JournalAction::Create, None, Some("Bad queue")),
+            journal_entry("$.updated[0]", JournalAction::Update, Some("TEST-1"), None),
+            journal_entry("$.updated[1]", JournalAction::Update, None, Some("Not found")),
+        ];
+
+        let failed = task_batch.failed_entries(&journal).unwrap();
+
+        let created = failed["created"].as_array().unwrap();
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[0]["summary"], "Broken");
+        assert_eq!(created[0]["parent"], "TEST-3");
+        assert_eq!(created[0]["error"], "Bad type");
+        assert_eq!(created[0]["unique"], "batch-run-1-broken");
+        assert_eq!(created[1]["summary"], "Story");
+        assert_eq!(created[1]["unique"], "batch-run-1-story");
+        assert_eq!(created[1]["subtasks"][0]["summary"], "Skipped");
+        assert!(created[1]["subtasks"][0]["unique"].is_null());
+        let links: Vec<TaskLinkInfo> = serde_json::from_value(created[1]["links"].clone()).unwrap();
+        assert_eq!(links[0].task.as_deref(), Some("epic"));
+
+        let updated = failed["updated"].as_array().unwrap();
+        assert_eq!(updated.len(), 2);
+        assert_eq!(updated[0]["issue_id"], "TEST-3");
+        assert_eq!(updated[0]["comments"][0], "Kick-off done");
+        assert_eq!(updated[0]["error"], "Forbidden");
+        assert_eq!(updated[1]["issue_id"], "TEST-2");
+        assert_eq!(failed["resolved"]["epic"], "TEST-3");
+        assert_eq!(failed["ids"]["epic"], "TEST-3");
+
+        let rerun: TaskBatch = serde_json::from_value(failed).unwrap();
+        assert!(rerun.is_valid());
+        assert!(TaskBatch::diagnose(&serde_json::to_value(&rerun).unwrap()).is_empty());
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::task_batch_diagnostics::locate_values;
+use crate::modules::import::markdown_import::MarkdownOutline;
+
+/// Represents the file formats a batch can be written in.
+///
+/// # Variants
+///
+/// * `Json` - JSON, the default.
+/// * `Jsonc` - JSON with `//` and `/* */` comments and trailing commas.
+/// * `Yaml` - YAML, handy for multi-line descriptions written as block scalars.
+/// * `Toml` - TOML, with `[[created]]` tables for the tasks.
+/// * `Markdown` - A Markdown outline of created tasks, read only, see `MarkdownOutline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BatchFormat {
+    #[default]
+    Json,
+    Jsonc,
+    Yaml,
+    Toml,
+    Markdown,
+}
+
+/// Represents a syntax error in a batch file.
+///
+/// # Fields
+///
+/// * `position` - The line and column of the error, starting at 1 (optional).
+/// * `message` - The description of the error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl BatchFormat {
+    /// Detects the format of a batch file from its extension, JSON if the extension is unknown.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the batch file.
+    pub fn from_path(path: &Path) -> BatchFormat {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| extension.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns the file extension of the format, e.g. `yaml`.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BatchFormat::Json => "json",
+            BatchFormat::Jsonc => "jsonc",
+            BatchFormat::Yaml => "yaml",
+            BatchFormat::Toml => "toml",
+            BatchFormat::Markdown => "md",
+        }
+    }
+
+    /// Returns the format batches read in this format are written in, e.g. failed entries.
+    ///
+    /// Markdown outlines cannot hold every field of a batch, so they are written as YAML.
+    pub fn output_format(&self) -> BatchFormat {
+        match self {
+            BatchFormat::Markdown => BatchFormat::Yaml,
+            format => *format,
+        }
+    }
+
+    /// Parses the source text of a batch file.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source text of the batch file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the document, or the `SyntaxError` of the source.
+    pub fn parse(&self, source: &str) -> Result<Value, SyntaxError> {
+        match self {
+            BatchFormat::Json => parse_json(source),
+            BatchFormat::Jsonc => parse_json(&strip_comments(source)),
+            BatchFormat::Yaml => serde_yaml::from_str(source).map_err(|err| SyntaxError {
+                position: err.location().map(|location| (location.line(), location.column())),
+                message: without_location(err.to_string()),
+            }),
+            BatchFormat::Toml => toml::from_str(source).map_err(|err| SyntaxError {
+                position: err.span().map(|span| line_and_column(source, span.start)),
+                message: err.message().to_string(),
+            }),
+            BatchFormat::Markdown => Ok(MarkdownOutline::parse(source).document),
+        }
+    }
+
+    /// Finds the line and column where every value of a parsed batch file starts.
+    ///
+    /// Positions are known for JSON, JSONC and the tasks of Markdown outlines; problems of YAML and
+    /// TOML files are reported by their JSON pointer alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source text of a syntactically valid batch file.
+    ///
+    /// # Returns
+    ///
+    /// A map from the JSON pointer of every value to its line and column, starting at 1.
+    pub fn locate_values(&self, source: &str) -> HashMap<String, (usize, usize)> {
+        match self {
+            BatchFormat::Json => locate_values(source),
+            BatchFormat::Jsonc => locate_values(&strip_comments(source)),
+            BatchFormat::Markdown => MarkdownOutline::parse(source).positions,
+            BatchFormat::Yaml | BatchFormat::Toml => HashMap::new(),
+        }
+    }
+
+    /// Writes a value, such as a `TaskBatch`, in the format.
+    ///
+    /// TOML has no `null`, so fields without a value are left out. Markdown is written as YAML,
+    /// see `output_format`.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to write.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the text, or the error of the serializer.
+    pub fn to_string<T: Serialize>(&self, value: &T) -> Result<String, Box<dyn std::error::Error>> {
+        match self {
+            BatchFormat::Json | BatchFormat::Jsonc => Ok(serde_json::to_string_pretty(value)?),
+            BatchFormat::Yaml | BatchFormat::Markdown => Ok(serde_yaml::to_string(value)?),
+            BatchFormat::Toml => Ok(toml::to_string_pretty(&without_nulls(serde_json::to_value(value)?))?),
+        }
+    }
+}
+
+impl FromStr for BatchFormat {
+    type Err = String;
+
+    /// Parses a format name or file extension: `json`, `jsonc`, `yaml`, `yml`, `toml`, `md` or `markdown`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Ok(BatchFormat::Json),
+            "jsonc" => Ok(BatchFormat::Jsonc),
+            "yaml" | "yml" => Ok(BatchFormat::Yaml),
+            "toml" => Ok(BatchFormat::Toml),
+            "md" | "markdown" => Ok(BatchFormat::Markdown),
+            _ => Err(format!("unknown format `{}`, expected json, jsonc, yaml, toml or markdown", name)),
+        }
+    }
+}
+
+impl fmt::Display for BatchFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+/// Parses JSON, reporting the position of a syntax error separately from its message.
+fn parse_json(source: &str) -> Result<Value, SyntaxError> {
+    serde_json::from_str(source).map_err(|err| SyntaxError {
+        position: Some((err.line(), err.column())),
+        message: without_location(err.to_string()),
+    })
+}
+
+/// Removes the ` at line … column …` suffix of a parser message, which is reported on its own.
+fn without_location(message: String) -> String {
+    match message.rfind(" at line ") {
+        Some(position) => message[..position].to_string(),
+        None => message,
+    }
+}
+
+/// Converts a byte offset of a source text into a line and a column, starting at 1.
+fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |position| position + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+/// Turns JSONC into JSON by blanking out comments and trailing commas.
+///
+/// Every removed character is replaced with a space, and line breaks are kept, so that the
+/// lines and columns of the values stay the same.
+fn strip_comments(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut stripped: Vec<char> = Vec::with_capacity(chars.len());
+    let mut index = 0;
+    // The position in `stripped` of a comma that is trailing if a closing bracket follows.
+    let mut comma: Option<usize> = None;
+
+    while index < chars.len() {
+        let c = chars[index];
+        match (c, chars.get(index + 1)) {
+            ('"', _) => {
+                comma = None;
+                stripped.push(c);
+                index += 1;
+                while index < chars.len() {
+                    stripped.push(chars[index]);
+                    index += 1;
+                    match chars[index - 1] {
+                        '\\' if index < chars.len() => {
+                            stripped.push(chars[index]);
+                            index += 1;
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            ('/', Some('/')) => {
+                while index < chars.len() && chars[index] != '\n' {
+                    stripped.push(' ');
+                    index += 1;
+                }
+            }
+            ('/', Some('*')) => {
+                let end = (index + 2..chars.len().saturating_sub(1))
+                    .find(|position| chars[*position] == '*' && chars[*position + 1] == '/')
+                    .map_or(chars.len(), |position| position + 2);
+                stripped.extend(chars[index..end].iter().map(|c| if *c == '\n' { '\n' } else { ' ' }));
+                index = end;
+            }
+            _ => {
+                match c {
+                    ',' => comma = Some(stripped.len()),
+                    ']' | '}' => {
+                        if let Some(position) = comma.take() {
+                            stripped[position] = ' ';
+                        }
+                    }
+                    _ if c.is_whitespace() => {}
+                    _ => comma = None,
+                }
+                stripped.push(c);
+                index += 1;
+            }
+        }
+    }
+    stripped.into_iter().collect()
+}
+
+/// Removes the `null` fields of every object of a value.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .filter(|(_, field)| !field.is_null())
+                .map(|(key, field)| (key, without_nulls(field)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(without_nulls).collect()),
+        _ => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::task::task_batch::TaskBatch;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(BatchFormat::from_path(Path::new("tasks.yml")), BatchFormat::Yaml);
+        assert_eq!(BatchFormat::from_path(Path::new("sprint.TOML")), BatchFormat::Toml);
+        assert_eq!(BatchFormat::from_path(Path::new("tasks.jsonc")), BatchFormat::Jsonc);
+        assert_eq!(BatchFormat::from_path(Path::new("epic.md")), BatchFormat::Markdown);
+        assert_eq!(BatchFormat::from_path(Path::new("tasks")), BatchFormat::Json);
+    }
+
+    #[test]
+    fn test_parse_jsonc() {
+        let source = "{\n  // The sprint tasks\n  \"created\": [ /* none yet */ ],\n  \"updated\": [\"x\", ],\n}";
+
+        let document = BatchFormat::Jsonc.parse(source).unwrap();
+
+        assert_eq!(document, serde_json::json!({ "created": [], "updated": ["x"] }));
+        assert_eq!(BatchFormat::Jsonc.locate_values(source)["/updated/0"], (4, 15));
+    }
+
+    #[test]
+    fn test_parse_yaml_and_toml() {
+        let yaml = "created:\n  - queue: TEST\n    summary: Release\n    description: |\n      First line\n      Second line\n    subtasks: []\nupdated: []\n";
+        let toml = "updated = []\n\n[[created]]\nqueue = \"TEST\"\nsummary = \"Release\"\ndescription = \"\"\"\nFirst line\nSecond line\n\"\"\"\nsubtasks = []\n";
+
+        for (format, source) in [(BatchFormat::Yaml, yaml), (BatchFormat::Toml, toml)] {
+            let document = format.parse(source).unwrap();
+            assert_eq!(document["created"][0]["description"], "First line\nSecond line\n");
+            assert_eq!(document["updated"], serde_json::json!([]));
+        }
+
+        let error = BatchFormat::Toml.parse("updated = []\ncreated = [\n").unwrap_err();
+        assert_eq!(error.position.map(|(line, _)| line), Some(3));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let task_batch = TaskBatch::default();
+
+        for format in [BatchFormat::Json, BatchFormat::Yaml, BatchFormat::Toml] {
+            let text = format.to_string(&task_batch).unwrap();
+            let document = format.parse(&text).unwrap();
+            assert_eq!(document["created"][0]["subtasks"].as_array().map(Vec::len), Some(1), "{}", format);
+            assert_eq!(document["updated"].as_array().map(Vec::len), Some(1), "{}", format);
+        }
+    }
+}
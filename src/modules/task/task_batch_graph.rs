@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+use super::task_batch::TaskBatch;
+use super::task_batch_diagnostics::escape_pointer_segment;
+use super::task_link::TaskLinkInfo;
+use super::task_reference::{is_valid_id, references};
+use super::CreatedTaskInfo;
+
+/// Represents a created entry of a batch, taken out of the tree of subtasks.
+///
+/// # Fields
+///
+/// * `path` - The JSON path of the entry, e.g. `$.created[0].subtasks[1]`.
+/// * `pointer` - The JSON pointer of the entry, e.g. `/created/0/subtasks/1`.
+/// * `nesting` - The JSON path of the entry holding this one as a subtask (optional).
+/// * `task` - The task as declared in the batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatedEntry<'a> {
+    pub path: String,
+    pub pointer: String,
+    pub nesting: Option<String>,
+    pub task: &'a CreatedTaskInfo,
+}
+
+impl TaskBatch {
+    /// Lists the created entries of the batch in file order, every task before its subtasks.
+    pub fn created_entries(&self) -> Vec<CreatedEntry<'_>> {
+        let mut entries = Vec::new();
+        let mut stack: Vec<(String, String, Option<String>, &CreatedTaskInfo)> = self
+            .created
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, task)| (format!("$.created[{}]", index), format!("/created/{}", index), None, task))
+            .collect();
+
+        while let Some((path, pointer, nesting, task)) = stack.pop() {
+            stack.extend(task.subtasks.iter().enumerate().rev().map(|(index, subtask)| {
+                (
+                    format!("{}.subtasks[{}]", path, index),
+                    format!("{}/subtasks/{}", pointer, index),
+                    Some(path.clone()),
+                    subtask,
+                )
+            }));
+            entries.push(CreatedEntry {
+                path,
+                pointer,
+                nesting,
+                task,
+            });
+        }
+        entries
+    }
+
+    /// Lists the created entries of the batch in the order they are created.
+    ///
+    /// Every task depends on its parent: the task it is nested in, or the task its `parent`
+    /// refers to as `${id}`. Tasks are created after the task they depend on and otherwise
+    /// in file order, so a batch without references is created in file order.
+    ///
+    /// The dependencies are expected to be free of cycles, which `reference_problems` reports.
+    pub fn creation_order(&self) -> Vec<CreatedEntry<'_>> {
+        let entries = self.created_entries();
+        let positions = entry_positions(&entries);
+
+        let mut emitted = vec![false; entries.len()];
+        let mut order = Vec::with_capacity(entries.len());
+        for start in 0..entries.len() {
+            // Every task has at most one dependency, so the tasks to create first form a chain.
+            let mut chain = Vec::new();
+            let mut current = Some(start);
+            while let Some(index) = current {
+                if emitted[index] || chain.contains(&index) {
+                    break;
+                }
+                chain.push(index);
+                current = dependency(&entries, &positions, index);
+            }
+
+            for index in chain.into_iter().rev() {
+                emitted[index] = true;
+                order.push(entries[index].clone());
+            }
+        }
+        order
+    }
+
+    /// Finds the problems of the local ids and `${id}` references of the batch.
+    ///
+    /// Ids must be valid and declared once, including the ids resolved by a previous run.
+    /// Every reference must name a declared id, subtasks cannot refer to another parent,
+    /// the epic of a created task must already exist, and the parents of the created tasks
+    /// cannot form a cycle.
+    ///
+    /// # Returns
+    ///
+    /// The JSON pointers and descriptions of the problems.
+    pub fn reference_problems(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        let entries = self.created_entries();
+
+        let mut declared: HashMap<&str, String> = self
+            .ids
+            .keys()
+            .map(|id| (id.as_str(), format!("/ids/{}", escape_pointer_segment(id))))
+            .collect();
+        for entry in &entries {
+            let Some(id) = entry.task.id.as_deref() else {
+                continue;
+            };
+            let id_pointer = format!("{}/id", entry.pointer);
+            if !is_valid_id(id) {
+                problems.push((
+                    id_pointer,
+                    format!("Id `{}` may only contain letters, digits, `_`, `-` and `.`", id),
+                ));
+            } else if let Some(first) = declared.get(id) {
+                problems.push((id_pointer, format!("Id `{}` is already used by {}", id, first)));
+            } else {
+                declared.insert(id, entry.pointer.clone());
+            }
+        }
+
+        let mut values: Vec<(String, &str)> = Vec::new();
+        for entry in &entries {
+            if let (Some(parent), Some(_)) = (&entry.task.parent, &entry.nesting) {
+                if !references(parent).is_empty() {
+                    problems.push((
+                        format!("{}/parent", entry.pointer),
+                        "A subtask is created under the task it is nested in and cannot refer to another parent"
+                            .to_string(),
+                    ));
+                }
+            }
+            if entry.task.epic.as_deref().is_some_and(|epic| !references(epic).is_empty()) {
+                problems.push((
+                    format!("{}/epic", entry.pointer),
+                    "The epic of a created task must be an existing issue; set it with an update instead".to_string(),
+                ));
+            }
+            values.extend(entry.task.parent.iter().map(|parent| (format!("{}/parent", entry.pointer), parent.as_str())));
+            reference_values(&mut values, &entry.pointer, &entry.task.links, &entry.task.comments);
+        }
+        for (index, task) in self.updated.iter().enumerate() {
+            let pointer = format!("/updated/{}", index);
+            values.push((format!("{}/issue_id", pointer), task.issue_id.as_str()));
+            values.extend(task.parent.iter().map(|parent| (format!("{}/parent", pointer), parent.as_str())));
+            values.extend(task.epic.iter().map(|epic| (format!("{}/epic", pointer), epic.as_str())));
+            reference_values(&mut values, &pointer, &task.links, &task.comments);
+        }
+        for (index, pending_link) in self.links.iter().enumerate() {
+            let pointer = format!("/links/{}", index);
+            values.push((format!("{}/issue", pointer), pending_link.issue.as_str()));
+            values.extend(pending_link.link.issue.iter().map(|issue| (format!("{}/link/issue", pointer), issue.as_str())));
+        }
+
+        for (pointer, value) in values {
+            for id in references(value) {
+                if !declared.contains_key(id) {
+                    problems.push((pointer.clone(), format!("No task with id `{}` in the batch", id)));
+                }
+            }
+        }
+
+        problems.extend(dependency_cycles(&entries));
+        problems
+    }
+}
+
+/// The positions of the entries in the list of entries, by JSON path and by local id.
+struct EntryPositions<'a> {
+    paths: HashMap<&'a str, usize>,
+    ids: HashMap<&'a str, usize>,
+}
+
+/// Maps the JSON paths and the local ids of the entries to their position in the list of entries.
+fn entry_positions<'a>(entries: &'a [CreatedEntry]) -> EntryPositions<'a> {
+    let mut positions = EntryPositions {
+        paths: HashMap::new(),
+        ids: HashMap::new(),
+    };
+    for (index, entry) in entries.iter().enumerate() {
+        positions.paths.insert(entry.path.as_str(), index);
+        if let Some(id) = entry.task.id.as_deref() {
+            positions.ids.entry(id).or_insert(index);
+        }
+    }
+    positions
+}
+
+/// Returns the position of the entry a created entry depends on: the entry it is nested in,
+/// or the entry its `parent` refers to.
+fn dependency(entries: &[CreatedEntry], positions: &EntryPositions, index: usize) -> Option<usize> {
+    let entry = &entries[index];
+    match &entry.nesting {
+        Some(nesting) => positions.paths.get(nesting.as_str()).copied(),
+        None => {
+            let parent = entry.task.parent.as_deref()?;
+            references(parent).first().and_then(|id| positions.ids.get(id).copied())
+        }
+    }
+}
+
+/// Collects the values of the links and comments of an entry that may hold references.
+fn reference_values<'a>(values: &mut Vec<(String, &'a str)>, pointer: &str, links: &'a [TaskLinkInfo], comments: &'a [String]) {
+    values.extend(
+        links
+            .iter()
+            .enumerate()
+            .filter_map(|(index, link)| link.issue.as_deref().map(|issue| (format!("{}/links/{}/issue", pointer, index), issue))),
+    );
+    values.extend(
+        comments
+            .iter()
+            .enumerate()
+            .map(|(index, comment)| (format!("{}/comments/{}", pointer, index), comment.as_str())),
+    );
+}
+
+/// Finds the cycles among the parents of the created entries.
+///
+/// # Returns
+///
+/// One problem per cycle, reported at the `parent` of its first entry in file order.
+fn dependency_cycles(entries: &[CreatedEntry]) -> Vec<(String, String)> {
+    let positions = entry_positions(entries);
+    let mut problems = Vec::new();
+
+    for start in 0..entries.len() {
+        let mut cycle = vec![start];
+        let mut current = dependency(entries, &positions, start);
+        while let Some(index) = current {
+            if index == start {
+                if cycle.iter().all(|member| *member >= start) {
+                    let members: Vec<&str> = cycle
+                        .iter()
+                        .chain([&start])
+                        .map(|member| entries[*member].pointer.as_str())
+                        .collect();
+                    problems.push((
+                        format!("{}/parent", entries[start].pointer),
+                        format!("Parents form a cycle: {}", members.join(" -> ")),
+                    ));
+                }
+                break;
+            }
+            if cycle.contains(&index) {
+                break;
+            }
+            cycle.push(index);
+            current = dependency(entries, &positions, index);
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(json: &str) -> TaskBatch {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_creation_order() {
+        let task_batch = batch(
+            r#"{
+                "created": [
+                    { "queue": "TEST", "summary": "Story", "parent": "${epic}", "subtasks": [] },
+                    {
+                        "queue": "TEST",
+                        "summary": "Release",
+                        "subtasks": [{ "queue": "TEST", "summary": "Epic", "id": "epic", "subtasks": [] }]
+                    },
+                    { "queue": "TEST", "summary": "Docs", "subtasks": [] }
+                ],
+                "updated": [{ "issue_id": "${epic}", "summary": "Renamed", "followers": [] }]
+            }"#,
+        );
+
+        let order: Vec<String> = task_batch.creation_order().into_iter().map(|entry| entry.path).collect();
+
+        assert_eq!(order, vec!["$.created[1]", "$.created[1].subtasks[0]", "$.created[0]", "$.created[2]"]);
+        assert!(task_batch.reference_problems().is_empty());
+    }
+
+    #[test]
+    fn test_reference_problems() {
+        let task_batch = batch(
+            r#"{
+                "created": [
+                    { "queue": "TEST", "summary": "A", "id": "a", "parent": "${b}", "subtasks": [] },
+                    { "queue": "TEST", "summary": "B", "id": "b", "parent": "${a}", "subtasks": [] },
+                    { "queue": "TEST", "summary": "C", "id": "a", "epic": "${b}", "comments": ["See ${story}"], "subtasks": [] }
+                ],
+                "updated": []
+            }"#,
+        );
+
+        assert_eq!(
+            task_batch.reference_problems(),
+            vec![
+                ("/created/2/id".to_string(), "Id `a` is already used by /created/0".to_string()),
+                (
+                    "/created/2/epic".to_string(),
+                    "The epic of a created task must be an existing issue; set it with an update instead".to_string()
+                ),
+                ("/created/2/comments/0".to_string(), "No task with id `story` in the batch".to_string()),
+                (
+                    "/created/0/parent".to_string(),
+                    "Parents form a cycle: /created/0 -> /created/1 -> /created/0".to_string()
+                ),
+            ]
+        );
+    }
+}
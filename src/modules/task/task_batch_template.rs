@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+use serde_json::{Map, Value};
+
+use super::task_batch_diagnostics::escape_pointer_segment;
+
+/// The format of the dates produced and accepted by the date helpers, e.g. `2024-05-10`.
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The field of an entry that repeats the entry once per value of a list.
+pub const FOR_EACH: &str = "for_each";
+
+/// The built-in variable holding the current date, unless the batch or the command line sets it.
+const TODAY: &str = "today";
+
+/// The text standing for a literal `{{`, which would otherwise open an expression.
+const ESCAPED_BRACES: &str = "{{{{";
+
+/// Represents a batch document with its variables, date helpers and loops expanded.
+///
+/// # Fields
+///
+/// * `document` - The expanded document, without its `vars`.
+/// * `origins` - The JSON pointers of the expanded entries that moved, with the pointers of their source entries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpandedBatch {
+    pub document: Value,
+    origins: Vec<(String, String)>,
+}
+
+impl ExpandedBatch {
+    /// Expands the templates of a batch document.
+    ///
+    /// Variables are declared in the `vars` object of the document and may be overridden from the
+    /// command line. Every string of the `created` and `updated` entries may use them as `{{ name }}`,
+    /// `{{ item.field }}` for objects, or `{{ name + 14d }}` to shift a date by days or weeks; `today`
+    /// holds the current date. A string made of a single `{{ name }}` takes the value of the variable
+    /// as is, so that a list variable can fill a list field. An entry with a `for_each` object such as
+    /// `{ "service": ["auth", "billing"] }` is repeated once per value of the list, or of the
+    /// comma-separated text. `{{{{` stands for a literal `{{`.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The parsed batch file.
+    /// * `vars` - The variables set on the command line.
+    /// * `today` - The date of `today`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the expanded batch, or the JSON pointers into `document` and descriptions
+    /// of every undefined variable and malformed expression or loop.
+    pub fn expand(
+        document: &Value,
+        vars: &HashMap<String, String>,
+        today: NaiveDate,
+    ) -> Result<ExpandedBatch, Vec<(String, String)>> {
+        let mut expander = TemplateExpander {
+            problems: Vec::new(),
+            origins: Vec::new(),
+        };
+
+        let mut scope: HashMap<String, Value> =
+            HashMap::from([(TODAY.to_string(), Value::String(today.format(DATE_FORMAT).to_string()))]);
+        match document.get("vars") {
+            None => {}
+            Some(Value::Object(declared)) => {
+                for (name, value) in declared {
+                    if !is_variable_name(name) {
+                        expander.push(
+                            format!("/vars/{}", escape_pointer_segment(name)),
+                            format!("Variable `{}` may only contain letters, digits and `_`", name),
+                        );
+                    }
+                    scope.insert(name.clone(), value.clone());
+                }
+            }
+            Some(_) => expander.push("/vars".to_string(), "Field `vars` must be an object".to_string()),
+        }
+        scope.extend(vars.iter().map(|(name, value)| (name.clone(), Value::String(value.clone()))));
+
+        let mut expanded = Map::new();
+        if let Some(fields) = document.as_object() {
+            for (key, value) in fields {
+                match (key.as_str(), value) {
+                    ("vars", _) => {}
+                    ("created" | "updated", Value::Array(entries)) => {
+                        let pointer = format!("/{}", key);
+                        let entries = expander.expand_array(entries, &pointer, &pointer, &scope);
+                        expanded.insert(key.clone(), Value::Array(entries));
+                    }
+                    _ => {
+                        expanded.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        if !expander.problems.is_empty() {
+            return Err(expander.problems);
+        }
+        Ok(ExpandedBatch {
+            document: Value::Object(expanded),
+            origins: expander.origins,
+        })
+    }
+
+    /// Finds the pointer of the source value an expanded value comes from.
+    ///
+    /// # Arguments
+    ///
+    /// * `pointer` - A JSON pointer into the expanded document.
+    ///
+    /// # Returns
+    ///
+    /// The JSON pointer into the source document, e.g. `/created/0/summary` for the `summary` of the
+    /// second task repeated by the loop of `/created/0`.
+    pub fn source_pointer(&self, pointer: &str) -> String {
+        self.origins
+            .iter()
+            .filter(|(expanded, _)| {
+                pointer.strip_prefix(expanded.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|(expanded, _)| expanded.len())
+            .map(|(expanded, source)| format!("{}{}", source, &pointer[expanded.len()..]))
+            .unwrap_or_else(|| pointer.to_string())
+    }
+}
+
+/// Expands the values of a batch document, collecting problems instead of stopping at the first one.
+struct TemplateExpander {
+    problems: Vec<(String, String)>,
+    origins: Vec<(String, String)>,
+}
+
+impl TemplateExpander {
+    /// Records a problem once, even if the entry holding it is repeated by a loop.
+    fn push(&mut self, pointer: String, message: String) {
+        if !self.problems.iter().any(|(known, text)| *known == pointer && *text == message) {
+            self.problems.push((pointer, message));
+        }
+    }
+
+    /// Expands a value found at `source` in the source document and at `target` in the expanded one.
+    fn expand_value(&mut self, value: &Value, source: &str, target: &str, scope: &HashMap<String, Value>) -> Value {
+        match value {
+            Value::String(text) => self.render(text, source, scope),
+            Value::Array(items) => Value::Array(self.expand_array(items, source, target, scope)),
+            Value::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, field)| {
+                        let segment = escape_pointer_segment(key);
+                        let field_source = format!("{}/{}", source, segment);
+                        let field_target = format!("{}/{}", target, segment);
+                        (key.clone(), self.expand_value(field, &field_source, &field_target, scope))
+                    })
+                    .collect(),
+            ),
+            _ => value.clone(),
+        }
+    }
+
+    /// Expands the items of a list, repeating every item with a `for_each` loop once per value.
+    fn expand_array(&mut self, items: &[Value], source: &str, target: &str, scope: &HashMap<String, Value>) -> Vec<Value> {
+        let mut expanded = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            let item_source = format!("{}/{}", source, index);
+
+            let Some(Value::Object(fields)) = Some(item).filter(|item| item.get(FOR_EACH).is_some()) else {
+                let item_target = format!("{}/{}", target, expanded.len());
+                self.record_origin(&item_target, &item_source);
+                expanded.push(self.expand_value(item, &item_source, &item_target, scope));
+                continue;
+            };
+
+            let Some((name, values)) = self.loop_values(&fields[FOR_EACH], &item_source, scope) else {
+                continue;
+            };
+            let mut body = fields.clone();
+            body.remove(FOR_EACH);
+            let body = Value::Object(body);
+
+            for value in values {
+                let mut item_scope = scope.clone();
+                item_scope.insert(name.clone(), value);
+                let item_target = format!("{}/{}", target, expanded.len());
+                self.record_origin(&item_target, &item_source);
+                expanded.push(self.expand_value(&body, &item_source, &item_target, &item_scope));
+            }
+        }
+        expanded
+    }
+
+    /// Remembers where an expanded entry comes from, if it moved.
+    fn record_origin(&mut self, target: &str, source: &str) {
+        if target != source {
+            self.origins.push((target.to_string(), source.to_string()));
+        }
+    }
+
+    /// Reads the variable and the values of a `for_each` loop.
+    fn loop_values(
+        &mut self,
+        for_each: &Value,
+        source: &str,
+        scope: &HashMap<String, Value>,
+    ) -> Option<(String, Vec<Value>)> {
+        let pointer = format!("{}/{}", source, FOR_EACH);
+        let (name, list) = match for_each.as_object().filter(|loop_fields| loop_fields.len() == 1) {
+            Some(loop_fields) => loop_fields.iter().next()?,
+            None => {
+                self.push(
+                    pointer,
+                    "Loop must name one variable and its list, e.g. `{ \"service\": [\"auth\", \"billing\"] }`"
+                        .to_string(),
+                );
+                return None;
+            }
+        };
+
+        let list_pointer = format!("{}/{}", pointer, escape_pointer_segment(name));
+        if !is_variable_name(name) {
+            self.push(list_pointer, format!("Variable `{}` may only contain letters, digits and `_`", name));
+            return None;
+        }
+
+        let problems = self.problems.len();
+        let values = match self.expand_value(list, &list_pointer, &list_pointer, scope) {
+            Value::Array(values) => values,
+            Value::String(text) => text
+                .split(',')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| Value::String(value.to_string()))
+                .collect(),
+            _ => {
+                self.push(list_pointer, format!("Loop over `{}` must go over a list", name));
+                return None;
+            }
+        };
+        (self.problems.len() == problems).then(|| (name.clone(), values))
+    }
+
+    /// Replaces the `{{ … }}` expressions of a string with their values.
+    fn render(&mut self, text: &str, pointer: &str, scope: &HashMap<String, Value>) -> Value {
+        // A single expression keeps the type of its value, e.g. a list of followers.
+        if let Some(expression) = text
+            .strip_prefix("{{")
+            .and_then(|rest| rest.strip_suffix("}}"))
+            .filter(|expression| !expression.contains("{{") && !expression.contains("}}"))
+        {
+            return match evaluate(expression, scope) {
+                Ok(value) => value,
+                Err(message) => {
+                    self.push(pointer.to_string(), message);
+                    Value::String(text.to_string())
+                }
+            };
+        }
+
+        let mut rendered = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            rendered.push_str(&rest[..start]);
+            if rest[start..].starts_with(ESCAPED_BRACES) {
+                rendered.push_str("{{");
+                rest = &rest[start + ESCAPED_BRACES.len()..];
+                continue;
+            }
+            let Some(end) = rest[start..].find("}}") else {
+                self.push(pointer.to_string(), "Expression is missing its closing `}}`".to_string());
+                return Value::String(text.to_string());
+            };
+
+            let expression = &rest[start + 2..start + end];
+            match evaluate(expression, scope) {
+                Ok(Value::String(value)) => rendered.push_str(&value),
+                Ok(value @ (Value::Number(_) | Value::Bool(_))) => rendered.push_str(&value.to_string()),
+                Ok(_) => self.push(
+                    pointer.to_string(),
+                    format!("`{{{{{}}}}}` is not a text and can only be used as the whole value", expression),
+                ),
+                Err(message) => self.push(pointer.to_string(), message),
+            }
+            rest = &rest[start + end + 2..];
+        }
+        rendered.push_str(rest);
+        Value::String(rendered)
+    }
+}
+
+/// Checks if a variable can be used in expressions: letters, digits and `_`, not starting with a digit.
+fn is_variable_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|first| !first.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Evaluates the expression of a `{{ … }}`: a variable, optionally shifted by a number of days or weeks.
+///
+/// # Arguments
+///
+/// * `expression` - The text between the braces, e.g. ` today + 14d `.
+/// * `scope` - The variables, by name.
+///
+/// # Returns
+///
+/// A `Result` containing the value, or the description of the problem.
+fn evaluate(expression: &str, scope: &HashMap<String, Value>) -> Result<Value, String> {
+    let expression = expression.trim();
+    let name_end = expression
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+        .unwrap_or(expression.len());
+    let (name, offset) = (&expression[..name_end], expression[name_end..].trim());
+
+    let mut segments = name.split('.');
+    let variable = segments.next().filter(|variable| is_variable_name(variable));
+    let Some(mut value) = variable.and_then(|variable| scope.get(variable)) else {
+        return match variable {
+            Some(_) => Err(format!("Undefined variable `{}`", name)),
+            None => Err(format!("Invalid expression `{{{{ {} }}}}`", expression)),
+        };
+    };
+    for segment in segments {
+        value = value.get(segment).ok_or_else(|| format!("Undefined variable `{}`", name))?;
+    }
+
+    if offset.is_empty() {
+        return Ok(value.clone());
+    }
+
+    let days = parse_offset(offset)
+        .ok_or_else(|| format!("Invalid date offset `{}`, expected e.g. `+ 14d` or `- 2w`", offset))?;
+    let date = value
+        .as_str()
+        .and_then(|date| NaiveDate::parse_from_str(date, DATE_FORMAT).ok())
+        .ok_or_else(|| format!("Variable `{}` is not a date like `2024-05-10`", name))?;
+    let shifted = date
+        .checked_add_signed(Duration::days(days))
+        .ok_or_else(|| format!("Date `{} {}` is out of range", name, offset))?;
+    Ok(Value::String(shifted.format(DATE_FORMAT).to_string()))
+}
+
+/// Parses a date offset such as `+ 14d` or `-2w` into a number of days.
+fn parse_offset(offset: &str) -> Option<i64> {
+    let (sign, amount) = if let Some(amount) = offset.strip_prefix('+') {
+        (1, amount.trim())
+    } else if let Some(amount) = offset.strip_prefix('-') {
+        (-1, amount.trim())
+    } else {
+        return None;
+    };
+    let (count, days) = if let Some(count) = amount.strip_suffix('d') {
+        (count, 1)
+    } else if let Some(count) = amount.strip_suffix('w') {
+        (count, 7)
+    } else {
+        return None;
+    };
+    let count: i64 = count.parse().ok()?;
+    count.checked_mul(days * sign)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, 10).unwrap()
+    }
+
+    #[test]
+    fn test_expand_template() {
+        let document = json!({
+            "vars": { "sprint": "Sprint 1", "team": ["alice", "bob"], "services": [{ "name": "auth" }, { "name": "billing" }] },
+            "created": [
+                {
+                    "for_each": { "service": "{{ services }}" },
+                    "queue": "TEST",
+                    "summary": "Deploy {{ service.name }} in {{ sprint }}",
+                    "followers": "{{ team }}",
+                    "subtasks": [
+                        { "for_each": { "step": "build, release" }, "queue": "TEST", "summary": "{{ step }} {{ service.name }}", "subtasks": [] }
+                    ]
+                },
+                { "queue": "TEST", "summary": "Retro", "deadline": "{{ today + 2w }}", "subtasks": [] }
+            ],
+            "updated": [{
+                "issue_id": "TEST-1",
+                "summary": "Due {{ today - 1d }}",
+                "description": "{{{{ sprint }} is {{ sprint }}",
+                "followers": []
+            }]
+        });
+        let vars = HashMap::from([("sprint".to_string(), "Sprint 2".to_string())]);
+
+        let expanded = ExpandedBatch::expand(&document, &vars, today()).unwrap();
+
+        assert!(expanded.document.get("vars").is_none());
+        let created = expanded.document["created"].as_array().unwrap();
+        assert_eq!(created.len(), 3);
+        assert_eq!(created[0]["summary"], "Deploy auth in Sprint 2");
+        assert_eq!(created[0]["followers"], json!(["alice", "bob"]));
+        assert!(created[0].get(FOR_EACH).is_none());
+        assert_eq!(created[1]["subtasks"][1]["summary"], "release billing");
+        assert_eq!(created[2]["deadline"], "2024-05-24");
+        assert_eq!(expanded.document["updated"][0]["summary"], "Due 2024-05-09");
+        assert_eq!(expanded.document["updated"][0]["description"], "{{ sprint }} is Sprint 2");
+
+        assert_eq!(expanded.source_pointer("/created/1/subtasks/1/summary"), "/created/0/subtasks/0/summary");
+        assert_eq!(expanded.source_pointer("/created/2/deadline"), "/created/1/deadline");
+        assert_eq!(expanded.source_pointer("/updated/0/summary"), "/updated/0/summary");
+    }
+
+    #[test]
+    fn test_template_problems() {
+        let document = json!({
+            "vars": { "start": "soon" },
+            "created": [
+                {
+                    "for_each": { "service": ["auth", "billing"] },
+                    "queue": "TEST",
+                    "summary": "Deploy {{ service }} by {{ owner }}",
+                    "deadline": "{{ start + 3d }}",
+                    "subtasks": []
+                },
+                { "for_each": { "service": "{{ services }}" }, "queue": "TEST", "summary": "Test", "subtasks": [] }
+            ],
+            "updated": [{
+                "issue_id": "TEST-1",
+                "summary": "Due {{ today + 3x }}",
+                "description": "{{ today + 14д }} {{ todayé }}",
+                "followers": []
+            }]
+        });
+
+        assert_eq!(
+            ExpandedBatch::expand(&document, &HashMap::new(), today()).unwrap_err(),
+            vec![
+                (
+                    "/created/0/deadline".to_string(),
+                    "Variable `start` is not a date like `2024-05-10`".to_string()
+                ),
+                ("/created/0/summary".to_string(), "Undefined variable `owner`".to_string()),
+                ("/created/1/for_each/service".to_string(), "Undefined variable `services`".to_string()),
+                (
+                    "/updated/0/description".to_string(),
+                    "Invalid date offset `+ 14д`, expected e.g. `+ 14d` or `- 2w`".to_string()
+                ),
+                (
+                    "/updated/0/description".to_string(),
+                    "Invalid date offset `é`, expected e.g. `+ 14d` or `- 2w`".to_string()
+                ),
+                (
+                    "/updated/0/summary".to_string(),
+                    "Invalid date offset `+ 3x`, expected e.g. `+ 14d` or `- 2w`".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,631 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use super::task_api_client::HandleResponseError;
+use super::task_batch::TaskBatch;
+use super::task_checklist::ChecklistItemInfo;
+use super::task_fields::IssueFields;
+use super::task_link::TaskLinkInfo;
+use super::task_list_update::ListUpdate;
+use super::task_reference::references;
+use super::{CreatedTaskInfo, UpdatedTaskInfo};
+use crate::modules::queue::metadata_cache::MetadataCache;
+
+/// Represents a problem found in a batch by the pre-flight validation.
+///
+/// # Fields
+///
+/// * `path` - The JSON path of the offending value, e.g. `$.created[0].priority`.
+/// * `message` - The description of the problem.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ValidationProblem {
+    pub path: String,
+    pub message: String,
+}
+
+/// Represents the result of the pre-flight validation of a batch.
+///
+/// # Fields
+///
+/// * `problems` - Every problem found in the batch.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub problems: Vec<ValidationProblem>,
+}
+
+impl ValidationReport {
+    /// Checks if the batch passed the validation.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - If no problems were found.
+    /// * `false` - Otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Records a problem.
+    fn push(&mut self, path: String, message: String) {
+        self.problems.push(ValidationProblem { path, message });
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    /// Formats the report with one problem per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.problems {
+            writeln!(f, "{}: {}", problem.path, problem.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Validates a batch against the live queue metadata before any task is written.
+///
+/// The validator checks that queues exist, that issue types and priorities are valid for the
+/// queue, that sprints, users, parents and linked issues exist. Every problem is collected
+/// into a `ValidationReport` instead of failing on the first one.
+pub struct TaskBatchValidator<'a> {
+    /// The metadata used to check the values of the batch.
+    cache: &'a MetadataCache,
+
+    /// Issues by key: the key of their queue, or `None` if the issue does not exist.
+    issues: HashMap<String, Option<String>>,
+}
+
+impl<'a> TaskBatchValidator<'a> {
+    /// Creates a new `TaskBatchValidator` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The metadata used to check the values of the batch.
+    pub fn new(cache: &'a MetadataCache) -> Self {
+        TaskBatchValidator {
+            cache,
+            issues: HashMap::new(),
+        }
+    }
+
+    /// Validates every entry of a batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_batch` - The batch to validate.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the report of all problems found,
+    /// or `HandleResponseError` if the metadata could not be loaded.
+    pub async fn validate(&mut self, task_batch: &TaskBatch) -> Result<ValidationReport, HandleResponseError> {
+        let mut report = ValidationReport::default();
+
+        // Subtasks are created in the queue of their parent, and their parent is set on creation.
+        // Entries are pushed in reverse so that problems are reported in file order.
+        let mut stack: Vec<(String, &CreatedTaskInfo, &str, bool)> = task_batch
+            .created
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, task)| (entry_path("$", "created", index), task, task.queue.as_str(), false))
+            .collect();
+
+        while let Some((path, task, queue, is_subtask)) = stack.pop() {
+            self.validate_created(&mut report, &path, task, queue, is_subtask)
+                .await?;
+
+            stack.extend(
+                task.subtasks
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, subtask)| (entry_path(&path, "subtasks", index), subtask, queue, true)),
+            );
+        }
+
+        for (index, task) in task_batch.updated.iter().enumerate() {
+            let path = entry_path("$", "updated", index);
+            self.validate_updated(&mut report, &path, task).await?;
+        }
+
+        Ok(report)
+    }
+
+    /// Validates a task to be created.
+    async fn validate_created(
+        &mut self,
+        report: &mut ValidationReport,
+        path: &str,
+        task: &CreatedTaskInfo,
+        queue: &str,
+        is_subtask: bool,
+    ) -> Result<(), HandleResponseError> {
+        let queue_exists = self.cache.queue(queue).await?.is_some();
+        if !queue_exists {
+            report.push(format!("{}.queue", path), format!("Queue '{}' does not exist", queue));
+        }
+
+        if let Some(task_type) = &task.task_type {
+            if queue_exists && self.cache.resolve_issue_type(queue, task_type).await?.is_none() {
+                report.push(
+                    format!("{}.type", path),
+                    format!("Issue type '{}' is not available in queue '{}'", task_type, queue),
+                );
+            }
+        }
+
+        if queue_exists {
+            self.check_components(report, queue, indexed(path, "components", &task.components))
+                .await?;
+            self.check_versions(report, queue, indexed(path, "affectedVersions", &task.affected_versions))
+                .await?;
+            self.check_versions(report, queue, indexed(path, "fixVersions", &task.fix_versions))
+                .await?;
+            self.check_fields(report, path, queue, &task.fields).await?;
+        }
+
+        if !is_subtask {
+            if let Some(parent) = &task.parent {
+                self.check_issue(report, format!("{}.parent", path), parent).await?;
+            }
+        }
+        if let Some(epic) = &task.epic {
+            self.check_issue(report, format!("{}.epic", path), epic).await?;
+        }
+
+        self.check_priority(report, path, task.priority.as_deref()).await?;
+        self.check_sprints(report, indexed(path, "sprint", &task.sprint)).await?;
+        self.check_users(
+            report,
+            path,
+            task.assignee.as_deref(),
+            task.author.as_deref(),
+            indexed(path, "followers", &task.followers),
+            &task.checklist,
+        )
+        .await?;
+        self.check_links(report, path, &task.links).await
+    }
+
+    /// Validates a task to be updated.
+    async fn validate_updated(
+        &mut self,
+        report: &mut ValidationReport,
+        path: &str,
+        task: &UpdatedTaskInfo,
+    ) -> Result<(), HandleResponseError> {
+        let queue = self
+            .check_issue(report, format!("{}.issue_id", path), &task.issue_id)
+            .await?;
+
+        if let (Some(task_type), Some(queue)) = (&task.task_type, &queue) {
+            if self.cache.resolve_issue_type(queue, task_type).await?.is_none() {
+                report.push(
+                    format!("{}.type", path),
+                    format!("Issue type '{}' is not available in queue '{}'", task_type, queue),
+                );
+            }
+        }
+
+        if let Some(parent) = &task.parent {
+            self.check_issue(report, format!("{}.parent", path), parent).await?;
+        }
+        if let Some(epic) = &task.epic {
+            self.check_issue(report, format!("{}.epic", path), epic).await?;
+        }
+
+        if let Some(queue) = &queue {
+            self.check_components(report, queue, operations(path, "components", &task.components))
+                .await?;
+            self.check_versions(report, queue, operations(path, "affectedVersions", &task.affected_versions))
+                .await?;
+            self.check_versions(report, queue, operations(path, "fixVersions", &task.fix_versions))
+                .await?;
+            self.check_fields(report, path, queue, &task.fields).await?;
+        }
+
+        self.check_priority(report, path, task.priority.as_deref()).await?;
+        self.check_sprints(report, operations(path, "sprint", &task.sprint)).await?;
+        self.check_users(report, path, None, None, operations(path, "followers", &task.followers), &task.checklist)
+            .await?;
+        self.check_links(report, path, &task.links).await
+    }
+
+    /// Checks that an issue exists.
+    ///
+    /// References to tasks created by the batch, written `${id}`, do not exist yet and are not checked.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the key of the queue of the issue, or `None` if it does not exist
+    /// or is a reference.
+    async fn check_issue(
+        &mut self,
+        report: &mut ValidationReport,
+        path: String,
+        key: &str,
+    ) -> Result<Option<String>, HandleResponseError> {
+        if !references(key).is_empty() {
+            return Ok(None);
+        }
+
+        if !self.issues.contains_key(key) {
+            let queue = match self.cache.api_client().get_task(key).await {
+                Ok(issue) => Some(issue.queue.and_then(|queue| queue.key).unwrap_or_default()),
+                Err(err) if err.is_not_found() => None,
+                Err(err) => return Err(err),
+            };
+            self.issues.insert(key.to_string(), queue);
+        }
+
+        let queue = self.issues[key].clone();
+        if queue.is_none() {
+            report.push(path, format!("Issue '{}' does not exist", key));
+        }
+        Ok(queue)
+    }
+
+    /// Checks that a priority exists.
+    async fn check_priority(
+        &self,
+        report: &mut ValidationReport,
+        path: &str,
+        priority: Option<&str>,
+    ) -> Result<(), HandleResponseError> {
+        if let Some(priority) = priority {
+            if self.cache.resolve_priority(priority).await?.is_none() {
+                report.push(
+                    format!("{}.priority", path),
+                    format!("Priority '{}' does not exist", priority),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the extra fields of a task exist in its queue, can be changed and have values
+    /// of the type of the field.
+    ///
+    /// # Arguments
+    ///
+    /// * `report` - The report the problems are added to.
+    /// * `path` - The JSON path of the task.
+    /// * `queue` - The key of the queue of the task.
+    /// * `fields` - The fields of the task.
+    async fn check_fields(
+        &self,
+        report: &mut ValidationReport,
+        path: &str,
+        queue: &str,
+        fields: &IssueFields,
+    ) -> Result<(), HandleResponseError> {
+        for (name, value) in &fields.extra {
+            let field_path = format!("{}.{}", path, name);
+            match self.cache.resolve_field(queue, name).await? {
+                None => report.push(field_path, format!("Field '{}' does not exist in queue '{}'", name, queue)),
+                Some(field) if field.readonly => report.push(field_path, format!("Field '{}' is read-only", name)),
+                Some(field) => {
+                    if let Some(schema) = field.schema.filter(|schema| !schema.accepts(value)) {
+                        report.push(
+                            field_path,
+                            format!("Field '{}' expects a value of type {}, got {}", name, schema.value_type, value),
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every sprint exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `report` - The report the problems are added to.
+    /// * `sprints` - The sprints with their JSON paths.
+    async fn check_sprints(
+        &self,
+        report: &mut ValidationReport,
+        sprints: Vec<(String, &str)>,
+    ) -> Result<(), HandleResponseError> {
+        for (sprint_path, sprint) in sprints {
+            if self.cache.resolve_sprint(sprint).await?.is_none() {
+                report.push(sprint_path, format!("Sprint '{}' does not exist", sprint));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every component exists in a queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `report` - The report the problems are added to.
+    /// * `queue` - The key of the queue.
+    /// * `components` - The components with their JSON paths.
+    async fn check_components(
+        &self,
+        report: &mut ValidationReport,
+        queue: &str,
+        components: Vec<(String, &str)>,
+    ) -> Result<(), HandleResponseError> {
+        for (component_path, component) in components {
+            if self.cache.resolve_component(queue, component).await?.is_none() {
+                report.push(
+                    component_path,
+                    format!("Component '{}' does not exist in queue '{}'", component, queue),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every version exists in a queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `report` - The report the problems are added to.
+    /// * `queue` - The key of the queue.
+    /// * `versions` - The versions with their JSON paths.
+    async fn check_versions(
+        &self,
+        report: &mut ValidationReport,
+        queue: &str,
+        versions: Vec<(String, &str)>,
+    ) -> Result<(), HandleResponseError> {
+        for (version_path, version) in versions {
+            if self.cache.resolve_version(queue, version).await?.is_none() {
+                report.push(
+                    version_path,
+                    format!("Version '{}' does not exist in queue '{}'", version, queue),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the assignee, the author, the followers and the checklist assignees exist.
+    async fn check_users(
+        &self,
+        report: &mut ValidationReport,
+        path: &str,
+        assignee: Option<&str>,
+        author: Option<&str>,
+        followers: Vec<(String, &str)>,
+        checklist: &[ChecklistItemInfo],
+    ) -> Result<(), HandleResponseError> {
+        let mut users: Vec<(String, &str)> = Vec::new();
+        users.extend(assignee.map(|user| (format!("{}.assignee", path), user)));
+        users.extend(author.map(|user| (format!("{}.author", path), user)));
+        users.extend(followers);
+        users.extend(checklist.iter().enumerate().filter_map(|(index, item)| {
+            item.assignee
+                .as_deref()
+                .map(|user| (format!("{}.checklist[{}].assignee", path, index), user))
+        }));
+
+        for (user_path, user) in users {
+            match self.cache.user(user).await? {
+                None => report.push(user_path, format!("User '{}' does not exist", user)),
+                Some(found) if found.dismissed => {
+                    report.push(user_path, format!("User '{}' is dismissed", user))
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every linked existing issue exists.
+    async fn check_links(
+        &mut self,
+        report: &mut ValidationReport,
+        path: &str,
+        links: &[TaskLinkInfo],
+    ) -> Result<(), HandleResponseError> {
+        for (index, link) in links.iter().enumerate() {
+            if let Some(issue) = &link.issue {
+                self.check_issue(report, format!("{}.links[{}].issue", path, index), issue)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the JSON path of an entry of a collection.
+///
+/// # Arguments
+///
+/// * `parent` - The JSON path of the object holding the collection.
+/// * `collection` - The name of the collection.
+/// * `index` - The position of the entry in the collection.
+///
+/// # Returns
+///
+/// A JSON path such as `$.created[0].subtasks[2]`.
+fn entry_path(parent: &str, collection: &str, index: usize) -> String {
+    format!("{}.{}[{}]", parent, collection, index)
+}
+
+/// Pairs every value of a list field with its JSON path, e.g. `$.created[0].followers[1]`.
+fn indexed<'a>(path: &str, field: &str, values: &'a [String]) -> Vec<(String, &'a str)> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| (entry_path(path, field, index), value.as_str()))
+        .collect()
+}
+
+/// Pairs every value of a list update with its JSON path, e.g. `$.updated[0].followers.add[1]`.
+fn operations<'a>(path: &str, field: &str, update: &'a ListUpdate) -> Vec<(String, &'a str)> {
+    update
+        .values()
+        .into_iter()
+        .map(|(value_path, value)| (format!("{}.{}{}", path, field, value_path), value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::task::task_api_client::TaskApiClient;
+    use serde_json::{from_value, json};
+
+    /// Builds a cache holding the metadata of the queue `TEST`, a missing queue `NOPE` and a few users.
+    fn cache() -> MetadataCache {
+        let queue = json!({
+            "id": 1,
+            "key": "TEST",
+            "name": "Test",
+            "issueTypes": [{ "self": "", "id": "2", "key": "task", "display": "Task" }]
+        });
+        let fields = json!([
+            { "id": "points", "name": "Points", "schema": { "type": "float" } },
+            { "id": "votes", "name": "Votes", "readonly": true }
+        ]);
+        let local_fields = json!([{ "id": "abc--team", "key": "team", "name": "Team", "schema": { "type": "string" } }]);
+        let priorities = json!([
+            { "id": 2, "key": "normal", "name": "Normal" },
+            { "id": 4, "key": "critical", "name": "Critical" }
+        ]);
+
+        MetadataCache::new(TaskApiClient::new(String::new(), String::new()))
+            .with_queue(
+                "TEST",
+                Some(from_value(queue).unwrap()),
+                vec![from_value(json!({ "id": 7, "name": "Backend" })).unwrap()],
+                vec![from_value(json!({ "id": 5, "name": "1.0" })).unwrap()],
+                from_value(local_fields).unwrap(),
+            )
+            .with_queue("NOPE", None, Vec::new(), Vec::new(), Vec::new())
+            .with_globals(
+                from_value(priorities).unwrap(),
+                vec![from_value(json!({ "id": 42, "name": "Sprint 1" })).unwrap()],
+                from_value(fields).unwrap(),
+            )
+            .with_user("jdoe", Some(from_value(json!({ "uid": 1, "login": "jdoe" })).unwrap()))
+            .with_user("left", Some(from_value(json!({ "uid": 2, "login": "left", "dismissed": true })).unwrap()))
+            .with_user("ghost", None)
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_every_problem() {
+        let task_batch: TaskBatch = from_value(json!({
+            "created": [
+                {
+                    "queue": "NOPE",
+                    "summary": "Unknown queue",
+                    "priority": "urgent",
+                    "sprint": ["Sprint 9"],
+                    "assignee": "ghost",
+                    "subtasks": []
+                },
+                {
+                    "queue": "TEST",
+                    "summary": "Feature",
+                    "type": "bug",
+                    "priority": "normal",
+                    "parent": "TEST-404",
+                    "epic": "TEST-1",
+                    "sprint": ["Sprint 1"],
+                    "followers": ["jdoe", "left"],
+                    "components": ["Backend", "Frontend"],
+                    "fixVersions": ["2.0"],
+                    "storyPoints": 3,
+                    "team": "Core",
+                    "points": "many",
+                    "votes": 1,
+                    "unknown": true,
+                    "subtasks": [{ "queue": "TEST", "summary": "Tests", "type": "task", "priority": "urgent", "subtasks": [] }]
+                }
+            ],
+            "updated": [
+                {
+                    "issue_id": "TEST-1",
+                    "epic": "TEST-404",
+                    "priority": "critical",
+                    "followers": { "add": ["ghost"] },
+                    "affectedVersions": { "remove": ["0.9"] },
+                    "fixVersions": ["1.0"]
+                }
+            ]
+        }))
+        .unwrap();
+
+        let cache = cache();
+        let mut validator = TaskBatchValidator::new(&cache);
+        validator.issues.insert("TEST-1".to_string(), Some("TEST".to_string()));
+        validator.issues.insert("TEST-404".to_string(), None);
+        let report = validator.validate(&task_batch).await.unwrap();
+
+        let problems: Vec<(&str, &str)> = report
+            .problems
+            .iter()
+            .map(|problem| (problem.path.as_str(), problem.message.as_str()))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                ("$.created[0].queue", "Queue 'NOPE' does not exist"),
+                ("$.created[0].priority", "Priority 'urgent' does not exist"),
+                ("$.created[0].sprint[0]", "Sprint 'Sprint 9' does not exist"),
+                ("$.created[0].assignee", "User 'ghost' does not exist"),
+                ("$.created[1].type", "Issue type 'bug' is not available in queue 'TEST'"),
+                ("$.created[1].components[1]", "Component 'Frontend' does not exist in queue 'TEST'"),
+                ("$.created[1].fixVersions[0]", "Version '2.0' does not exist in queue 'TEST'"),
+                ("$.created[1].points", "Field 'points' expects a value of type float, got \"many\""),
+                ("$.created[1].unknown", "Field 'unknown' does not exist in queue 'TEST'"),
+                ("$.created[1].votes", "Field 'votes' is read-only"),
+                ("$.created[1].parent", "Issue 'TEST-404' does not exist"),
+                ("$.created[1].followers[1]", "User 'left' is dismissed"),
+                ("$.created[1].subtasks[0].priority", "Priority 'urgent' does not exist"),
+                ("$.updated[0].epic", "Issue 'TEST-404' does not exist"),
+                ("$.updated[0].affectedVersions.remove[0]", "Version '0.9' does not exist in queue 'TEST'"),
+                ("$.updated[0].followers.add[0]", "User 'ghost' does not exist"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_valid_batch() {
+        let task_batch: TaskBatch = from_value(json!({
+            "created": [{
+                "queue": "TEST",
+                "summary": "Feature",
+                "type": "Task",
+                "priority": "Critical",
+                "sprint": ["42"],
+                "components": ["backend"],
+                "affectedVersions": ["1.0"],
+                "team": "Core",
+                "points": 2.5,
+                "subtasks": []
+            }],
+            "updated": []
+        }))
+        .unwrap();
+
+        let cache = cache();
+        let report = TaskBatchValidator::new(&cache).validate(&task_batch).await.unwrap();
+        assert!(report.is_empty(), "{}", report);
+    }
+
+    #[test]
+    fn test_entry_path() {
+        let epic = entry_path("$", "created", 1);
+        assert_eq!(epic, "$.created[1]");
+        assert_eq!(entry_path(&epic, "subtasks", 0), "$.created[1].subtasks[0]");
+    }
+
+    #[test]
+    fn test_report_display() {
+        let mut report = ValidationReport::default();
+        assert!(report.is_empty());
+
+        report.push("$.created[0].queue".to_string(), "Queue 'NOPE' does not exist".to_string());
+        assert!(!report.is_empty());
+        assert_eq!(report.to_string(), "$.created[0].queue: Queue 'NOPE' does not exist\n");
+    }
+}
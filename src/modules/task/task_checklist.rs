@@ -0,0 +1,313 @@
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use super::task_api_client::{HandleResponseError, TaskApiClient};
+use crate::modules::tracker_time::tracker_datetime;
+
+/// Represents a checklist item declared on a task of a batch.
+///
+/// # Fields
+///
+/// * `text` - The text of the item.
+/// * `checked` - Whether the item is done (optional, `false` by default).
+/// * `assignee` - The login or ID of the user responsible for the item (optional).
+/// * `deadline` - The date the item is due, e.g. `2024-05-10` (optional).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChecklistItemInfo {
+    pub text: String,
+    #[serde(default)]
+    pub checked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<NaiveDate>,
+}
+
+/// Represents the deadline of a checklist item.
+///
+/// # Fields
+///
+/// * `date` - The date and time the item is due.
+/// * `deadline_type` - The kind of the deadline, `date` for a whole day.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChecklistDeadline {
+    #[serde(with = "tracker_datetime")]
+    pub date: DateTime<FixedOffset>,
+    #[serde(rename = "deadlineType")]
+    pub deadline_type: String,
+}
+
+impl From<NaiveDate> for ChecklistDeadline {
+    /// Converts a date into a whole-day deadline starting at midnight UTC.
+    fn from(value: NaiveDate) -> Self {
+        ChecklistDeadline {
+            date: value.and_time(Default::default()).and_utc().fixed_offset(),
+            deadline_type: "date".to_string(),
+        }
+    }
+}
+
+/// Represents the body of a checklist item creation request.
+///
+/// # Fields
+///
+/// * `text` - The text of the item.
+/// * `checked` - Whether the item is done.
+/// * `assignee` - The login or ID of the user responsible for the item (optional).
+/// * `deadline` - The deadline of the item (optional).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatedChecklistItemBody {
+    pub text: String,
+    pub checked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<ChecklistDeadline>,
+}
+
+impl From<ChecklistItemInfo> for CreatedChecklistItemBody {
+    /// Converts a `ChecklistItemInfo` instance into a `CreatedChecklistItemBody` instance.
+    fn from(value: ChecklistItemInfo) -> Self {
+        CreatedChecklistItemBody {
+            text: value.text,
+            checked: value.checked,
+            assignee: value.assignee,
+            deadline: value.deadline.map(ChecklistDeadline::from),
+        }
+    }
+}
+
+/// Represents the body of a checklist item update request.
+///
+/// Only the fields that are set are changed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UpdatedChecklistItemBody {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checked: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<ChecklistDeadline>,
+}
+
+/// Represents a checklist item returned by the Yandex Tracker API.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChecklistItem {
+    /// The identifier of the item.
+    pub id: String,
+
+    /// The text of the item.
+    pub text: String,
+
+    /// Whether the item is done.
+    pub checked: bool,
+
+    /// The user responsible for the item.
+    pub assignee: Option<ChecklistAssignee>,
+
+    /// The deadline of the item.
+    pub deadline: Option<ChecklistDeadline>,
+}
+
+/// Represents the user responsible for a checklist item.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChecklistAssignee {
+    /// The unique identifier of the user.
+    pub id: String,
+
+    /// The display name of the user.
+    pub display: String,
+
+    /// The login of the user.
+    pub login: Option<String>,
+}
+
+/// Represents the issue returned by checklist modification requests.
+#[derive(Deserialize, Debug)]
+struct ChecklistIssue {
+    #[serde(rename = "checklistItems", default)]
+    checklist_items: Vec<ChecklistItem>,
+}
+
+impl TaskApiClient {
+    /// Reads the checklist of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the checklist items, or `HandleResponseError` if an error occurred.
+    pub async fn get_checklist(&self, issue_id: &str) -> Result<Vec<ChecklistItem>, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/issues/{}/checklistItems", issue_id))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Adds an item to the checklist of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `item` - The text, state, assignee and deadline of the item.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the checklist after the change, or `HandleResponseError` if an error occurred.
+    pub async fn add_checklist_item(
+        &self,
+        issue_id: &str,
+        item: CreatedChecklistItemBody,
+    ) -> Result<Vec<ChecklistItem>, HandleResponseError> {
+        let response = self
+            .request(Method::POST, &format!("/issues/{}/checklistItems", issue_id))
+            .json(&item)
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response::<ChecklistIssue>(response)
+            .await
+            .map(|issue| issue.checklist_items)
+    }
+
+    /// Edits an item of the checklist of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `item_id` - The identifier of the item.
+    /// * `item` - The fields to change.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the checklist after the change, or `HandleResponseError` if an error occurred.
+    pub async fn update_checklist_item(
+        &self,
+        issue_id: &str,
+        item_id: &str,
+        item: UpdatedChecklistItemBody,
+    ) -> Result<Vec<ChecklistItem>, HandleResponseError> {
+        let response = self
+            .request(
+                Method::PATCH,
+                &format!("/issues/{}/checklistItems/{}", issue_id, item_id),
+            )
+            .json(&item)
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response::<ChecklistIssue>(response)
+            .await
+            .map(|issue| issue.checklist_items)
+    }
+
+    /// Marks an item of the checklist of an issue as done or not done.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `item_id` - The identifier of the item.
+    /// * `checked` - Whether the item is done.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the checklist after the change, or `HandleResponseError` if an error occurred.
+    pub async fn check_checklist_item(
+        &self,
+        issue_id: &str,
+        item_id: &str,
+        checked: bool,
+    ) -> Result<Vec<ChecklistItem>, HandleResponseError> {
+        let item = UpdatedChecklistItemBody {
+            checked: Some(checked),
+            ..Default::default()
+        };
+        self.update_checklist_item(issue_id, item_id, item).await
+    }
+
+    /// Deletes an item of the checklist of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `item_id` - The identifier of the item.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the checklist after the change, or `HandleResponseError` if an error occurred.
+    pub async fn delete_checklist_item(
+        &self,
+        issue_id: &str,
+        item_id: &str,
+    ) -> Result<Vec<ChecklistItem>, HandleResponseError> {
+        let response = self
+            .request(
+                Method::DELETE,
+                &format!("/issues/{}/checklistItems/{}", issue_id, item_id),
+            )
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response::<ChecklistIssue>(response)
+            .await
+            .map(|issue| issue.checklist_items)
+    }
+
+    /// Adds the checklist items declared on a task of a batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `items` - The declared items, added in order.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result`, or `HandleResponseError` if an error occurred.
+    pub async fn add_checklist(
+        &self,
+        issue_id: &str,
+        items: &[ChecklistItemInfo],
+    ) -> Result<(), HandleResponseError> {
+        for item in items {
+            self.add_checklist_item(issue_id, CreatedChecklistItemBody::from(item.clone()))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_checklist_item_body() {
+        let item: ChecklistItemInfo = serde_json::from_value(json!({
+            "text": "Unit tests are written",
+            "assignee": "jdoe",
+            "deadline": "2024-05-10"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(CreatedChecklistItemBody::from(item)).unwrap(),
+            json!({
+                "text": "Unit tests are written",
+                "checked": false,
+                "assignee": "jdoe",
+                "deadline": {
+                    "date": "2024-05-10T00:00:00.000+0000",
+                    "deadlineType": "date"
+                }
+            })
+        );
+    }
+}
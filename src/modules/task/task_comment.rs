@@ -0,0 +1,49 @@
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use super::task_api_client::{HandleResponseError, TaskApiClient};
+
+/// Represents the body of a comment creation request.
+///
+/// # Fields
+///
+/// * `text` - The text of the comment.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CreatedCommentBody {
+    pub text: String,
+}
+
+/// Represents a comment of an issue returned by the Yandex Tracker API.
+///
+/// # Fields
+///
+/// * `id` - The identifier of the comment.
+/// * `text` - The text of the comment.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub id: u64,
+    #[serde(default)]
+    pub text: String,
+}
+
+impl TaskApiClient {
+    /// Adds a comment to an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `text` - The text of the comment.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the created comment, or `HandleResponseError` if an error occurred.
+    pub async fn add_comment(&self, issue_id: &str, text: &str) -> Result<Comment, HandleResponseError> {
+        let response = self
+            .request(Method::POST, &format!("/issues/{}/comments", issue_id))
+            .json(&CreatedCommentBody { text: text.to_string() })
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+}
@@ -0,0 +1,182 @@
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::task_plan::{is_empty_value, plain_value};
+
+/// The fields of an update that always change the issue when they are set.
+const ATTACHMENT_FIELDS: [&str; 2] = ["attachmentIds", "descriptionAttachmentIds"];
+
+/// The ANSI escape code of removed values.
+const RED: &str = "\x1b[31m";
+
+/// The ANSI escape code of added values.
+const GREEN: &str = "\x1b[32m";
+
+/// The ANSI escape code ending a coloured value.
+const RESET: &str = "\x1b[0m";
+
+/// Represents the change of a single field of an existing issue.
+///
+/// # Fields
+///
+/// * `field` - The name of the field in the request body.
+/// * `current` - The current value of the field, or `null` if it is not set.
+/// * `planned` - The value sent by the batch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub current: Value,
+    pub planned: Value,
+}
+
+/// Compares the body of an update with the current fields of the issue.
+///
+/// Fields the body leaves untouched, `null` or empty lists, are skipped. Attachments always count
+/// as changes since they are added to the issue.
+///
+/// # Arguments
+///
+/// * `body` - The body of the update, a JSON object.
+/// * `current` - The issue as returned by the API.
+///
+/// # Returns
+///
+/// The fields whose value would change, in the order of the body; the current values are reduced
+/// to their key or display name, see `plain_value`.
+pub fn diff_fields(body: &Value, current: &Value) -> Vec<FieldChange> {
+    body.as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, planned)| !is_empty_value(planned))
+        .filter(|(field, planned)| {
+            ATTACHMENT_FIELDS.contains(&field.as_str()) || !matches_current(planned, current.get(field.as_str()))
+        })
+        .map(|(field, planned)| FieldChange {
+            field: field.clone(),
+            current: current.get(field.as_str()).map(plain_value).unwrap_or(Value::Null),
+            planned: planned.clone(),
+        })
+        .collect()
+}
+
+/// Checks if a desired value matches the current value of a field.
+///
+/// References such as `{"key": "bug", "display": "Bug"}` match their key, ID or display name,
+/// compared case-insensitively, like `Reference::matches`. Lists match regardless of order, texts
+/// regardless of trailing whitespace, and numbers regardless of their representation, `3` and `3.0`.
+/// List operations, see `ListUpdate`, match when the added values are already there and the
+/// removed ones are not.
+pub(crate) fn matches_current(desired: &Value, current: Option<&Value>) -> bool {
+    match (desired, current) {
+        (Value::Object(operations), _) if is_list_update(operations) => {
+            let current_values = current.and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+            let contains = |value: &Value| current_values.iter().any(|current| matches_current(value, Some(current)));
+            let values = |operation: &str| operations.get(operation).and_then(Value::as_array).into_iter().flatten();
+            operations.get("set").is_none_or(|set| matches_current(set, current))
+                && values("add").all(contains)
+                && !values("remove").any(contains)
+        }
+        (Value::Array(desired), None | Some(Value::Null)) => desired.is_empty(),
+        (Value::Array(desired), Some(Value::Array(current))) => {
+            desired.len() == current.len()
+                && desired.iter().all(|value| current.iter().any(|current| matches_current(value, Some(current))))
+        }
+        (Value::String(desired), Some(Value::String(current))) => desired.trim_end() == current.trim_end(),
+        (Value::String(desired), Some(Value::Object(current))) => ["key", "id", "display", "login"]
+            .iter()
+            .filter_map(|field| current.get(*field))
+            .any(|value| match value {
+                Value::String(value) => value.eq_ignore_ascii_case(desired),
+                Value::Number(value) => value.to_string() == *desired,
+                _ => false,
+            }),
+        (Value::String(desired), Some(Value::Number(current))) => current.to_string() == *desired,
+        (Value::Number(desired), Some(Value::Number(current))) => desired.as_f64() == current.as_f64(),
+        (desired, Some(current)) => desired == current,
+        (_, None) => false,
+    }
+}
+
+/// Checks if an object of a request body is a `ListUpdate` written as operations.
+fn is_list_update(object: &Map<String, Value>) -> bool {
+    !object.is_empty() && object.keys().all(|key| ["add", "remove", "set"].contains(&key.as_str()))
+}
+
+/// Checks if diffs printed to the standard output should be coloured.
+///
+/// Colours are used when the output is a terminal, unless the `NO_COLOR` variable is set.
+pub fn colour_enabled() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+/// Writes field changes, one per line, each line starting with `indent`.
+///
+/// Changes are written as `~ field: current -> planned`, or `+ field: planned` for fields without
+/// a value. With the alternate flag, `{:#}`, they are written as a red `- field: current` line
+/// followed by a green `+ field: planned` line.
+pub(crate) fn write_changes(f: &mut fmt::Formatter<'_>, changes: &[FieldChange], indent: &str) -> fmt::Result {
+    for change in changes {
+        if f.alternate() {
+            if !change.current.is_null() {
+                write!(f, "\n{}{}- {}: {}{}", indent, RED, change.field, change.current, RESET)?;
+            }
+            write!(f, "\n{}{}+ {}: {}{}", indent, GREEN, change.field, change.planned, RESET)?;
+        } else if change.current.is_null() {
+            write!(f, "\n{}+ {}: {}", indent, change.field, change.planned)?;
+        } else {
+            write!(f, "\n{}~ {}: {} -> {}", indent, change.field, change.current, change.planned)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_fields() {
+        let body = json!({
+            "summary": "Validate cards",
+            "description": "Use the Luhn check",
+            "type": "bug",
+            "followers": ["jdoe", "1120000000016876"],
+            "priority": null,
+            "attachmentIds": ["42"]
+        });
+        let current = json!({
+            "summary": "Validate card numbers",
+            "description": "Use the Luhn check\n",
+            "type": { "id": "1", "key": "bug", "display": "Bug" },
+            "followers": [{ "id": "1120000000016876", "display": "Jane Doe" }, { "id": "7", "display": "jdoe" }]
+        });
+
+        let changes = diff_fields(&body, &current);
+
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    field: "attachmentIds".to_string(),
+                    current: Value::Null,
+                    planned: json!(["42"]),
+                },
+                FieldChange {
+                    field: "summary".to_string(),
+                    current: json!("Validate card numbers"),
+                    planned: json!("Validate cards"),
+                },
+            ]
+        );
+        assert!(diff_fields(&json!({ "type": "Bug" }), &current).is_empty());
+        assert!(diff_fields(&json!({ "storyPoints": 3.0 }), &json!({ "storyPoints": 3 })).is_empty());
+        assert!(diff_fields(&json!({ "followers": { "add": ["jdoe"], "remove": ["asmith"] } }), &current).is_empty());
+        assert_eq!(diff_fields(&json!({ "followers": { "remove": ["jdoe"] } }), &current).len(), 1);
+        assert_eq!(diff_fields(&json!({ "followers": { "set": [] } }), &current).len(), 1);
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use reqwest::Method;
+use serde_json::Value;
+
+use super::task_api_client::{HandleResponseError, TaskApiClient};
+use super::task_batch::TaskBatch;
+use super::task_fields::IssueFields;
+use super::task_list_update::ListUpdate;
+use super::task_plan::issue_version;
+use super::task_rollback::restorable_value;
+use super::{CreatedTaskInfo, UpdatedTaskInfo};
+
+/// The number of issues requested per page when searching issues.
+const SEARCH_PER_PAGE: usize = 100;
+
+/// Represents how exported issues are written to the batch.
+///
+/// # Variants
+///
+/// * `Updated` - As `updated` entries of the issues, to edit them in bulk and apply the changes.
+/// * `Created` - As `created` entries without keys, nested under their parents, to clone the structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportMode {
+    #[default]
+    Updated,
+    Created,
+}
+
+impl FromStr for ExportMode {
+    type Err = String;
+
+    /// Parses a mode name: `updated` or `created`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "updated" => Ok(ExportMode::Updated),
+            "created" => Ok(ExportMode::Created),
+            _ => Err(format!("unknown mode `{}`, expected updated or created", name)),
+        }
+    }
+}
+
+impl fmt::Display for ExportMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportMode::Updated => write!(f, "updated"),
+            ExportMode::Created => write!(f, "created"),
+        }
+    }
+}
+
+/// Represents the issues to export.
+///
+/// # Fields
+///
+/// * `keys` - The keys of the issues.
+/// * `query` - A query in the Tracker query language, e.g. `Queue: TEST Status: open` (optional).
+/// * `subtasks` - Whether to export the subtasks of the issues, at every depth.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ExportSelection {
+    pub keys: Vec<String>,
+    pub query: Option<String>,
+    pub subtasks: bool,
+}
+
+/// Exports existing issues to a batch.
+pub struct TaskExporter {
+    api_client: TaskApiClient,
+}
+
+impl TaskExporter {
+    /// Creates a new `TaskExporter` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_client` - The API client used to read the issues.
+    pub fn new(api_client: TaskApiClient) -> Self {
+        TaskExporter { api_client }
+    }
+
+    /// Reads the selected issues and writes them to a batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `selection` - The issues to export.
+    /// * `mode` - How the issues are written, see `TaskBatch::from_issues`.
+    /// * `queue` - The queue of the created entries, the queue of each issue if `None`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the batch, or `HandleResponseError` if an issue cannot be read.
+    pub async fn export(
+        &self,
+        selection: &ExportSelection,
+        mode: ExportMode,
+        queue: Option<&str>,
+    ) -> Result<TaskBatch, HandleResponseError> {
+        let mut issues = Vec::new();
+        for key in &selection.keys {
+            issues.push(self.api_client.get_task_fields(key).await?);
+        }
+        if let Some(query) = &selection.query {
+            issues.extend(self.api_client.search_task_fields(&serde_json::json!({ "query": query })).await?);
+        }
+
+        if selection.subtasks {
+            let mut parents: Vec<String> = issues.iter().filter_map(issue_key).collect();
+            while let Some(parent) = parents.pop() {
+                let subtasks = self
+                    .api_client
+                    .search_task_fields(&serde_json::json!({ "filter": { "parent": parent } }))
+                    .await?;
+                parents.extend(subtasks.iter().filter_map(issue_key));
+                issues.extend(subtasks);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        issues.retain(|issue| issue_key(issue).is_some_and(|key| seen.insert(key)));
+        Ok(TaskBatch::from_issues(&issues, mode, queue))
+    }
+}
+
+impl TaskBatch {
+    /// Writes issues, as read from the API, to a batch.
+    ///
+    /// Issues are ordered so that every issue comes right before its subtasks, otherwise in the
+    /// given order. In `Updated` mode every issue becomes an update of its current fields, with its
+    /// `parent` and its `version`, so that running the batch fails on issues changed since the
+    /// export. In `Created` mode keys, `unique`s and authors are stripped, and subtasks are
+    /// nested in the `subtasks` of their parent; the `parent` of issues whose parent is not
+    /// exported is kept. Issues moved to another `queue` lose their components, versions and
+    /// sprints, which belong to their source queue and its boards.
+    ///
+    /// # Arguments
+    ///
+    /// * `issues` - The issues as JSON objects.
+    /// * `mode` - How the issues are written.
+    /// * `queue` - The queue of the created entries, the queue of each issue if `None`.
+    pub fn from_issues(issues: &[Value], mode: ExportMode, queue: Option<&str>) -> TaskBatch {
+        let keys: HashMap<String, usize> = issues
+            .iter()
+            .enumerate()
+            .filter_map(|(index, issue)| issue_key(issue).map(|key| (key, index)))
+            .collect();
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); issues.len()];
+        let mut roots = Vec::new();
+        for (index, issue) in issues.iter().enumerate() {
+            match field(issue, "parent").and_then(|parent| keys.get(&parent)) {
+                Some(parent) if *parent != index => children[*parent].push(index),
+                _ => roots.push(index),
+            }
+        }
+
+        let mut task_batch = TaskBatch {
+            created: Vec::new(),
+            updated: Vec::new(),
+            links: Vec::new(),
+            resolved: HashMap::new(),
+            ids: HashMap::new(),
+        };
+        match mode {
+            ExportMode::Updated => {
+                let mut stack: Vec<usize> = roots.into_iter().rev().collect();
+                while let Some(index) = stack.pop() {
+                    task_batch.updated.push(updated_task(&issues[index]));
+                    stack.extend(children[index].iter().rev());
+                }
+            }
+            ExportMode::Created => {
+                task_batch.created = roots
+                    .into_iter()
+                    .map(|index| created_task(issues, &children, index, queue, true))
+                    .collect();
+            }
+        }
+        task_batch
+    }
+}
+
+impl TaskApiClient {
+    /// Searches issues, reading every page of the results.
+    ///
+    /// # Arguments
+    ///
+    /// * `search` - The body of the search, e.g. `{"query": "Queue: TEST"}` or `{"filter": {"parent": "TEST-1"}}`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the issues as JSON objects, or `HandleResponseError` if an error occurred.
+    pub async fn search_task_fields(&self, search: &Value) -> Result<Vec<Value>, HandleResponseError> {
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let response = self
+                .request(Method::POST, "/issues/_search")
+                .query(&[("perPage", SEARCH_PER_PAGE), ("page", page)])
+                .json(search)
+                .send()
+                .await?;
+
+            let found: Vec<Value> = TaskApiClient::handle_response(response).await?;
+            let last = found.len() < SEARCH_PER_PAGE;
+            issues.extend(found);
+            if last {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+}
+
+/// Returns the key of an issue.
+fn issue_key(issue: &Value) -> Option<String> {
+    issue.get("key").and_then(Value::as_str).map(str::to_string)
+}
+
+/// Reads a field of an issue as text, references being reduced to their key or ID.
+fn field(issue: &Value, name: &str) -> Option<String> {
+    match issue.get(name).map(restorable_value)? {
+        Value::String(value) => Some(value),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads a list field of an issue as texts, references being reduced to their key or ID.
+fn list_field(issue: &Value, name: &str) -> Vec<String> {
+    match issue.get(name).map(restorable_value) {
+        Some(Value::Array(values)) => values
+            .into_iter()
+            .filter_map(|value| match value {
+                Value::String(value) => Some(value),
+                Value::Number(value) => Some(value.to_string()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Writes an issue as an update of its current fields.
+fn updated_task(issue: &Value) -> UpdatedTaskInfo {
+    UpdatedTaskInfo {
+        issue_id: issue_key(issue).unwrap_or_default(),
+        summary: field(issue, "summary"),
+        parent: field(issue, "parent"),
+        epic: field(issue, "epic"),
+        description: field(issue, "description"),
+        sprint: ListUpdate::set(list_field(issue, "sprint")),
+        task_type: field(issue, "type"),
+        priority: field(issue, "priority"),
+        followers: ListUpdate::set(list_field(issue, "followers")),
+        tags: ListUpdate::set(list_field(issue, "tags")),
+        components: ListUpdate::set(list_field(issue, "components")),
+        affected_versions: ListUpdate::set(list_field(issue, "affectedVersions")),
+        fix_versions: ListUpdate::set(list_field(issue, "fixVersions")),
+        attachment_ids: Vec::new(),
+        description_attachment_ids: Vec::new(),
+        attachments: Vec::new(),
+        description_attachments: Vec::new(),
+        links: Vec::new(),
+        checklist: Vec::new(),
+        comments: Vec::new(),
+        version: issue_version(issue),
+        fields: IssueFields::from_issue(issue),
+        error: None,
+    }
+}
+
+/// Writes an issue as a task to create, with its exported subtasks.
+///
+/// The components, versions and sprints of an issue moved to another queue are dropped, their IDs
+/// referring to the source queue.
+fn created_task(
+    issues: &[Value],
+    children: &[Vec<usize>],
+    index: usize,
+    queue: Option<&str>,
+    is_root: bool,
+) -> CreatedTaskInfo {
+    let issue = &issues[index];
+    let moved = queue.is_some_and(|queue| field(issue, "queue").as_deref() != Some(queue));
+    let scoped_field = |name: &str| if moved { Vec::new() } else { list_field(issue, name) };
+    CreatedTaskInfo {
+        queue: queue.map(str::to_string).or_else(|| field(issue, "queue")).unwrap_or_default(),
+        summary: field(issue, "summary").unwrap_or_default(),
+        parent: if is_root { field(issue, "parent") } else { None },
+        epic: field(issue, "epic"),
+        description: field(issue, "description"),
+        sprint: scoped_field("sprint"),
+        task_type: field(issue, "type"),
+        priority: field(issue, "priority"),
+        followers: list_field(issue, "followers"),
+        tags: list_field(issue, "tags"),
+        components: scoped_field("components"),
+        affected_versions: scoped_field("affectedVersions"),
+        fix_versions: scoped_field("fixVersions"),
+        assignee: field(issue, "assignee"),
+        author: None,
+        unique: None,
+        id: None,
+        attachment_ids: Vec::new(),
+        description_attachment_ids: Vec::new(),
+        attachments: Vec::new(),
+        description_attachments: Vec::new(),
+        links: Vec::new(),
+        checklist: Vec::new(),
+        comments: Vec::new(),
+        subtasks: children[index]
+            .iter()
+            .map(|child| created_task(issues, children, *child, queue, false))
+            .collect(),
+        fields: IssueFields::from_issue(issue),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    fn issues() -> Vec<Value> {
+        vec![
+            json!({
+                "key": "TEST-2",
+                "summary": "Write tests",
+                "queue": { "key": "TEST", "display": "Test" },
+                "parent": { "key": "TEST-1", "display": "Feature" },
+                "type": { "key": "task", "display": "Task" },
+                "assignee": { "id": "1120000000016876", "display": "Jane Doe" },
+                "unique": "tests-1"
+            }),
+            json!({
+                "key": "TEST-1",
+                "summary": "Feature",
+                "description": "The feature",
+                "queue": { "key": "TEST", "display": "Test" },
+                "parent": { "key": "TEST-0", "display": "Epic" },
+                "priority": { "key": "critical", "display": "Critical" },
+                "version": 7,
+                "sprint": [{ "id": "42", "display": "Sprint 1" }],
+                "followers": [{ "id": "1120000000016876", "display": "Jane Doe" }],
+                "tags": ["backend"],
+                "fixVersions": [{ "id": "5", "display": "1.0" }],
+                "epic": { "key": "TEST-10", "display": "Checkout" },
+                "deadline": "2024-06-28"
+            }),
+            json!({ "key": "TEST-3", "summary": "Release", "queue": { "key": "TEST" } }),
+        ]
+    }
+
+    #[test]
+    fn test_export_updated() {
+        let task_batch = TaskBatch::from_issues(&issues(), ExportMode::Updated, None);
+
+        let keys: Vec<&str> = task_batch.updated.iter().map(|task| task.issue_id.as_str()).collect();
+        assert_eq!(keys, vec!["TEST-1", "TEST-2", "TEST-3"]);
+        assert_eq!(task_batch.updated[0].parent.as_deref(), Some("TEST-0"));
+        assert_eq!(task_batch.updated[0].sprint, ListUpdate::set(vec!["42".to_string()]));
+        assert_eq!(task_batch.updated[0].followers, ListUpdate::set(vec!["1120000000016876".to_string()]));
+        assert_eq!(task_batch.updated[0].version, Some(7));
+        assert_eq!(task_batch.updated[0].fix_versions, ListUpdate::set(vec!["5".to_string()]));
+        assert_eq!(task_batch.updated[0].epic.as_deref(), Some("TEST-10"));
+        assert_eq!(task_batch.updated[1].parent.as_deref(), Some("TEST-1"));
+        assert!(task_batch.created.is_empty());
+        assert!(task_batch.is_valid());
+    }
+
+    #[test]
+    fn test_export_created() {
+        let task_batch = TaskBatch::from_issues(&issues(), ExportMode::Created, Some("COPY"));
+
+        assert_eq!(task_batch.created.len(), 2);
+        let feature = &task_batch.created[0];
+        assert_eq!(feature.queue, "COPY");
+        assert_eq!(feature.parent.as_deref(), Some("TEST-0"));
+        assert_eq!(feature.priority.as_deref(), Some("critical"));
+        assert!(feature.sprint.is_empty());
+        assert!(feature.fix_versions.is_empty());
+        assert_eq!(feature.tags, vec!["backend"]);
+        assert_eq!(feature.fields.deadline, NaiveDate::from_ymd_opt(2024, 6, 28));
+
+        let tests = &feature.subtasks[0];
+        assert_eq!(tests.summary, "Write tests");
+        assert_eq!(tests.parent, None);
+        assert_eq!(tests.unique, None);
+        assert_eq!(tests.assignee.as_deref(), Some("1120000000016876"));
+        assert_eq!(task_batch.created[1].summary, "Release");
+        assert!(task_batch.updated.is_empty());
+
+        for queue in [None, Some("TEST")] {
+            let feature = &TaskBatch::from_issues(&issues(), ExportMode::Created, queue).created[0];
+            assert_eq!(feature.queue, "TEST");
+            assert_eq!(feature.sprint, vec!["42"]);
+            assert_eq!(feature.fix_versions, vec!["5"]);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::modules::tracker_time::TrackerDuration;
+
+/// Represents the issue fields of a task beyond its summary, type, people and lists.
+///
+/// The standard fields are typed: dates are written `2024-05-10` and estimations as durations
+/// such as `PT4H` or `2d 4h`. Any other field of the batch entry, e.g. a local field of the queue
+/// such as `team`, is kept in `extra` and sent to the API as is.
+///
+/// # Fields
+///
+/// * `story_points` - The story points of the task (optional).
+/// * `deadline` - The deadline of the task (optional).
+/// * `start` - The planned start date of the task (optional).
+/// * `end` - The planned end date of the task (optional).
+/// * `original_estimation` - The original estimation of the work (optional).
+/// * `extra` - The other fields, by their ID or key in the API.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct IssueFields {
+    #[serde(rename = "storyPoints", default, skip_serializing_if = "Option::is_none")]
+    pub story_points: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<NaiveDate>,
+    #[serde(rename = "originalEstimation", default, skip_serializing_if = "Option::is_none")]
+    pub original_estimation: Option<TrackerDuration>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+impl IssueFields {
+    /// Checks if no field is set.
+    pub fn is_empty(&self) -> bool {
+        self.story_points.is_none()
+            && self.deadline.is_none()
+            && self.start.is_none()
+            && self.end.is_none()
+            && self.original_estimation.is_none()
+            && self.extra.is_empty()
+    }
+
+    /// Returns example values of the standard fields, shown in the templates of `template_tasks`.
+    pub fn example() -> IssueFields {
+        IssueFields {
+            story_points: Some(3.0),
+            deadline: NaiveDate::from_ymd_opt(2024, 6, 28),
+            start: NaiveDate::from_ymd_opt(2024, 6, 3),
+            end: NaiveDate::from_ymd_opt(2024, 6, 28),
+            original_estimation: TrackerDuration::parse("2d").ok(),
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the standard fields of an issue as returned by the API, skipping the values that
+    /// cannot be read.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue` - The issue as a JSON object.
+    pub fn from_issue(issue: &Value) -> IssueFields {
+        let date = |field: &str| issue.get(field).and_then(Value::as_str).and_then(|date| date.parse().ok());
+        IssueFields {
+            story_points: issue.get("storyPoints").and_then(Value::as_f64),
+            deadline: date("deadline"),
+            start: date("start"),
+            end: date("end"),
+            original_estimation: issue
+                .get("originalEstimation")
+                .and_then(Value::as_str)
+                .and_then(|duration| TrackerDuration::parse(duration).ok()),
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_issue_fields() {
+        let fields: IssueFields = serde_json::from_value(json!({
+            "storyPoints": 3,
+            "deadline": "2024-05-10",
+            "originalEstimation": "2d 4h",
+            "team": "Backend"
+        }))
+        .unwrap();
+
+        assert_eq!(fields.story_points, Some(3.0));
+        assert_eq!(fields.extra["team"], "Backend");
+        assert_eq!(
+            serde_json::to_value(&fields).unwrap(),
+            json!({ "storyPoints": 3.0, "deadline": "2024-05-10", "originalEstimation": "PT20H", "team": "Backend" })
+        );
+        assert!(serde_json::from_value::<IssueFields>(json!({ "deadline": "10.05.2024" })).is_err());
+        assert_eq!(IssueFields::from_issue(&json!({ "deadline": "2024-05-10", "storyPoints": 3 })).story_points, Some(3.0));
+    }
+}
@@ -0,0 +1,487 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::task_api_client::HandleResponseError;
+use super::task_diff::FieldChange;
+
+/// The directory holding one subdirectory per run.
+pub const RUNS_DIR: &str = "runs";
+
+/// The name of the journal file of a run.
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// The name of the file holding the path of the batch file of a run.
+pub const BATCH_FILE: &str = "batch";
+
+/// Represents an operation performed on a batch entry.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalAction {
+    Create,
+    Update,
+    AddChecklist,
+    Link,
+    AddComment,
+    /// The issue was moved to another status.
+    Transition,
+    /// The fields of an issue were read right before it was updated, to be restored by a rollback.
+    Snapshot,
+    /// An operation of the entry was undone by a rollback.
+    Rollback,
+    /// The `unique` generated for a created task, recorded before the task is first sent so that
+    /// a resumed run sends it again.
+    Unique,
+}
+
+/// Represents the outcome of an operation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalStatus {
+    Ok,
+    /// The issue or link already existed, e.g. it was created by an interrupted run.
+    Existing,
+    /// The update would not change the issue and was not sent.
+    Unchanged,
+    Failed,
+}
+
+/// Represents a line of the journal of a run.
+///
+/// # Fields
+///
+/// * `entry` - The local id of the batch entry: its JSON path in the input file, e.g. `$.created[0].subtasks[1]`.
+/// * `action` - The operation performed.
+/// * `key` - The key of the issue the operation resulted in (optional).
+/// * `status` - The outcome of the operation.
+/// * `error` - The error of a failed operation (optional).
+/// * `original` - The values of the updated fields before the update, for snapshots (optional).
+/// * `changes` - The values of the changed fields before and after an update (optional).
+/// * `unique` - The `unique` generated for a created task (optional).
+/// * `at` - The time the operation finished.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub entry: String,
+    pub action: JournalAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub status: JournalStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original: Option<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<FieldChange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unique: Option<String>,
+    pub at: DateTime<FixedOffset>,
+}
+
+/// An append-only journal of the operations of a run, stored in `runs/<run-id>/journal.jsonl`.
+///
+/// Every operation is written and flushed as soon as it finishes, so the journal stays accurate
+/// even if the process dies in the middle of a run. The input file is never modified.
+pub struct TaskJournal {
+    /// The identifier of the run.
+    run_id: String,
+
+    /// The directory of the run.
+    dir: PathBuf,
+
+    /// The journal file, opened for appending.
+    file: File,
+
+    /// The entries written so far.
+    entries: Vec<JournalEntry>,
+}
+
+impl TaskJournal {
+    /// Starts the journal of a new run.
+    ///
+    /// # Arguments
+    ///
+    /// * `runs_dir` - The directory holding the runs, usually `RUNS_DIR`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the journal, or `io::Error` if the run directory cannot be created.
+    pub fn create(runs_dir: &Path) -> Result<Self, io::Error> {
+        let run_id = Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+        fs::create_dir_all(runs_dir.join(&run_id))?;
+        TaskJournal::open(runs_dir, &run_id)
+    }
+
+    /// Reopens the journal of a previous run to resume it.
+    ///
+    /// New entries are appended to the same journal, and the operations that succeeded
+    /// are reported by `completed` so that they are not performed twice.
+    ///
+    /// # Arguments
+    ///
+    /// * `runs_dir` - The directory holding the runs, usually `RUNS_DIR`.
+    /// * `run_id` - The identifier of the run.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the journal, or `io::Error` if the run does not exist.
+    pub fn open(runs_dir: &Path, run_id: &str) -> Result<Self, io::Error> {
+        let dir = runs_dir.join(run_id);
+        if !dir.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Run '{}' not found in {}", run_id, runs_dir.display()),
+            ));
+        }
+
+        let path = dir.join(JOURNAL_FILE);
+        let entries = if path.exists() {
+            TaskJournal::load(&path)?
+        } else {
+            Vec::new()
+        };
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(TaskJournal {
+            run_id: run_id.to_string(),
+            dir,
+            file,
+            entries,
+        })
+    }
+
+    /// Finds the identifier of the latest run.
+    ///
+    /// # Arguments
+    ///
+    /// * `runs_dir` - The directory holding the runs, usually `RUNS_DIR`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the identifier, `None` if there is no run, or `io::Error`.
+    pub fn latest_run_id(runs_dir: &Path) -> Result<Option<String>, io::Error> {
+        if !runs_dir.is_dir() {
+            return Ok(None);
+        }
+
+        let mut run_ids = Vec::new();
+        for dir_entry in fs::read_dir(runs_dir)? {
+            let dir_entry = dir_entry?;
+            if dir_entry.file_type()?.is_dir() {
+                run_ids.push(dir_entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        // Run identifiers start with their timestamp, so they sort chronologically.
+        Ok(run_ids.into_iter().max())
+    }
+
+    /// Returns the identifier of the run.
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Returns the directory of the run.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Records the batch file the run processes, see `unfinished_run`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The path of the batch file.
+    pub fn record_batch(&self, file: &Path) -> Result<(), io::Error> {
+        let file = fs::canonicalize(file)?;
+        fs::write(self.dir.join(BATCH_FILE), file.to_string_lossy().as_bytes())
+    }
+
+    /// Returns the entries written so far, including those of the resumed run.
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Finds an operation of an entry that has already succeeded.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The local id of the batch entry.
+    /// * `action` - The operation.
+    ///
+    /// # Returns
+    ///
+    /// The key the operation resulted in, or `None` if it has not succeeded yet.
+    pub fn completed(&self, entry: &str, action: JournalAction) -> Option<&str> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|journal_entry| journal_entry.entry == entry && journal_entry.action == action)
+            .filter(|journal_entry| journal_entry.status != JournalStatus::Failed)
+            .and_then(|journal_entry| journal_entry.key.as_deref())
+    }
+
+    /// Appends the outcome of an operation to the journal.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The local id of the batch entry.
+    /// * `action` - The operation performed.
+    /// * `result` - The key of the resulting issue, or the error of the operation.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result`, or `io::Error` if the journal cannot be written.
+    pub fn record(
+        &mut self,
+        entry: &str,
+        action: JournalAction,
+        result: Result<&str, &HandleResponseError>,
+    ) -> Result<(), io::Error> {
+        self.write(JournalEntry {
+            entry: entry.to_string(),
+            action,
+            key: result.ok().map(str::to_string),
+            status: match result {
+                Ok(_) => JournalStatus::Ok,
+                Err(_) => JournalStatus::Failed,
+            },
+            error: result.err().map(|err| err.to_string()),
+            original: None,
+            changes: Vec::new(),
+            unique: None,
+            at: Local::now().fixed_offset(),
+        })
+    }
+
+    /// Appends an operation that found its issue or link already existing.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The local id of the batch entry.
+    /// * `action` - The operation.
+    /// * `key` - The key of the existing issue.
+    pub fn record_existing(&mut self, entry: &str, action: JournalAction, key: &str) -> Result<(), io::Error> {
+        self.write(JournalEntry {
+            entry: entry.to_string(),
+            action,
+            key: Some(key.to_string()),
+            status: JournalStatus::Existing,
+            error: None,
+            original: None,
+            changes: Vec::new(),
+            unique: None,
+            at: Local::now().fixed_offset(),
+        })
+    }
+
+    /// Appends the values of the fields of an issue read right before updating it.
+    ///
+    /// The snapshot is written before the update is sent, so the original values are kept
+    /// even if the process dies right after the update.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The local id of the batch entry.
+    /// * `key` - The key of the issue.
+    /// * `original` - The values of the fields the update changes, by field name.
+    pub fn record_snapshot(&mut self, entry: &str, key: &str, original: Value) -> Result<(), io::Error> {
+        self.write(JournalEntry {
+            entry: entry.to_string(),
+            action: JournalAction::Snapshot,
+            key: Some(key.to_string()),
+            status: JournalStatus::Ok,
+            error: None,
+            original: Some(original),
+            changes: Vec::new(),
+            unique: None,
+            at: Local::now().fixed_offset(),
+        })
+    }
+
+    /// Appends a successful update with the values of the fields it changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The local id of the batch entry.
+    /// * `key` - The key of the updated issue.
+    /// * `changes` - The changed fields, before and after the update; empty if the update was not sent.
+    pub fn record_update(&mut self, entry: &str, key: &str, changes: Vec<FieldChange>) -> Result<(), io::Error> {
+        self.write(JournalEntry {
+            entry: entry.to_string(),
+            action: JournalAction::Update,
+            key: Some(key.to_string()),
+            status: if changes.is_empty() { JournalStatus::Unchanged } else { JournalStatus::Ok },
+            error: None,
+            original: None,
+            changes,
+            unique: None,
+            at: Local::now().fixed_offset(),
+        })
+    }
+
+    /// Returns the `unique` of a created task without one of its own.
+    ///
+    /// The first call for an entry generates a `unique` from the run and the entry and records it;
+    /// later calls, including those of a resumed run, return the recorded one. A task that a
+    /// crashed attempt has already created is thus found by its `unique` instead of being created
+    /// twice, while another run of the same batch creates new tasks.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The local id of the batch entry.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `unique`, or `io::Error` if the journal cannot be written.
+    pub fn unique(&mut self, entry: &str) -> Result<String, io::Error> {
+        if let Some(unique) = recorded_unique(&self.entries, entry) {
+            return Ok(unique.to_string());
+        }
+
+        let unique = run_unique(&self.run_id, entry);
+        self.write(JournalEntry {
+            entry: entry.to_string(),
+            action: JournalAction::Unique,
+            key: None,
+            status: JournalStatus::Ok,
+            error: None,
+            original: None,
+            changes: Vec::new(),
+            unique: Some(unique.clone()),
+            at: Local::now().fixed_offset(),
+        })?;
+        Ok(unique)
+    }
+
+    /// Writes and flushes a line of the journal.
+    fn write(&mut self, journal_entry: JournalEntry) -> Result<(), io::Error> {
+        writeln!(self.file, "{}", serde_json::to_string(&journal_entry)?)?;
+        self.file.flush()?;
+        self.entries.push(journal_entry);
+        Ok(())
+    }
+
+    /// Reads the journal of a previous run.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the journal file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the entries in the order they were written, or `io::Error`.
+    /// A truncated last line, left by a crash, is ignored.
+    pub fn load(path: &Path) -> Result<Vec<JournalEntry>, io::Error> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut entries = Vec::new();
+
+        for line in reader.lines() {
+            match serde_json::from_str(&line?) {
+                Ok(entry) => entries.push(entry),
+                Err(err) if err.is_eof() => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Finds the `unique` recorded for a created task.
+///
+/// # Arguments
+///
+/// * `entries` - The entries of the journal.
+/// * `entry` - The local id of the batch entry.
+pub fn recorded_unique<'a>(entries: &'a [JournalEntry], entry: &str) -> Option<&'a str> {
+    entries
+        .iter()
+        .find(|journal_entry| journal_entry.entry == entry && journal_entry.action == JournalAction::Unique)
+        .and_then(|journal_entry| journal_entry.unique.as_deref())
+}
+
+/// Generates the `unique` of a created task for a run.
+///
+/// # Arguments
+///
+/// * `run_id` - The identifier of the run.
+/// * `entry` - The local id of the batch entry.
+///
+/// # Returns
+///
+/// A `unique` such as `batch-20240510-101500-123-4f2a9c1e7b3d5a60`.
+pub fn run_unique(run_id: &str, entry: &str) -> String {
+    // 64-bit FNV-1a, stable across platforms and Rust versions unlike `DefaultHasher`.
+    let hash = entry
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3));
+    format!("batch-{}-{:016x}", run_id, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_record_and_load() {
+        let runs_dir = std::env::temp_dir().join(format!("journal-test-{}", std::process::id()));
+        let mut journal = TaskJournal::create(&runs_dir).unwrap();
+
+        journal
+            .record("$.created[0]", JournalAction::Create, Ok("TEST-1"))
+            .unwrap();
+        journal
+            .record(
+                "$.created[0].links[0]",
+                JournalAction::Link,
+                Err(&HandleResponseError::UnresolvedLink("epic".to_string())),
+            )
+            .unwrap();
+        let change = FieldChange {
+            field: "summary".to_string(),
+            current: Value::String("Draft".to_string()),
+            planned: Value::String("Release".to_string()),
+        };
+        journal.record_update("$.updated[0]", "TEST-2", vec![change.clone()]).unwrap();
+        journal.record_update("$.updated[1]", "TEST-3", Vec::new()).unwrap();
+
+        let entries = TaskJournal::load(&journal.dir().join(JOURNAL_FILE)).unwrap();
+        let run_id = journal.run_id().to_string();
+        let latest = TaskJournal::latest_run_id(&runs_dir).unwrap();
+        let resumed = TaskJournal::open(&runs_dir, &run_id).unwrap();
+        fs::remove_dir_all(&runs_dir).unwrap();
+
+        assert_eq!(entries, journal.entries());
+        assert_eq!(entries[0].key.as_deref(), Some("TEST-1"));
+        assert_eq!(entries[1].status, JournalStatus::Failed);
+        assert_eq!(entries[1].error.as_deref(), Some("Unresolved link target: epic"));
+        assert_eq!(entries[2].changes, vec![change]);
+        assert_eq!(entries[3].status, JournalStatus::Unchanged);
+
+        assert_eq!(latest, Some(run_id));
+        assert_eq!(resumed.completed("$.created[0]", JournalAction::Create), Some("TEST-1"));
+        assert_eq!(resumed.completed("$.created[0].links[0]", JournalAction::Link), None);
+        assert_eq!(resumed.completed("$.updated[1]", JournalAction::Update), Some("TEST-3"));
+    }
+
+    #[test]
+    fn test_journal_unique() {
+        let runs_dir = std::env::temp_dir().join(format!("journal-unique-test-{}", std::process::id()));
+        let mut journal = TaskJournal::create(&runs_dir).unwrap();
+
+        let unique = journal.unique("$.created[0]").unwrap();
+        let again = journal.unique("$.created[0]").unwrap();
+        let other = journal.unique("$.created[1]").unwrap();
+        let mut resumed = TaskJournal::open(&runs_dir, journal.run_id()).unwrap();
+        let resumed_unique = resumed.unique("$.created[0]").unwrap();
+        fs::remove_dir_all(&runs_dir).unwrap();
+
+        assert!(unique.starts_with(&format!("batch-{}-", journal.run_id())));
+        assert_eq!(again, unique);
+        assert_ne!(other, unique);
+        assert_eq!(resumed_unique, unique);
+        assert_eq!(resumed.entries().len(), 2);
+        assert_ne!(run_unique("20240510-101500-123", "$.created[0]"), run_unique("20240517-101500-123", "$.created[0]"));
+    }
+}
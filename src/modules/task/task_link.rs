@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use super::task_api_client::{HandleResponseError, TaskApiClient};
+
+/// Represents the relationship between two linked issues in Yandex Tracker.
+///
+/// The relationship is always read from the point of view of the issue the link is created on.
+/// `is blocked by` and `blocks` are accepted as aliases of `depends on` and `is dependent by`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkRelationship {
+    /// A simple link between two issues.
+    #[serde(rename = "relates")]
+    Relates,
+
+    /// The issue is blocked by the linked issue.
+    #[serde(rename = "depends on", alias = "is blocked by")]
+    DependsOn,
+
+    /// The issue blocks the linked issue.
+    #[serde(rename = "is dependent by", alias = "blocks")]
+    IsDependentBy,
+
+    /// The issue duplicates the linked issue.
+    #[serde(rename = "duplicates")]
+    Duplicates,
+
+    /// The issue is duplicated by the linked issue.
+    #[serde(rename = "is duplicated by")]
+    IsDuplicatedBy,
+
+    /// The issue is an epic of the linked issue.
+    #[serde(rename = "is epic of")]
+    IsEpicOf,
+
+    /// The linked issue is the epic of the issue.
+    #[serde(rename = "has epic")]
+    HasEpic,
+
+    /// The issue is a subtask of the linked issue.
+    #[serde(rename = "is subtask for")]
+    IsSubtaskFor,
+
+    /// The issue is the parent of the linked issue.
+    #[serde(rename = "is parent task for")]
+    IsParentTaskFor,
+}
+
+impl LinkRelationship {
+    /// Returns how the relationship appears on the links of the issue it is created on.
+    ///
+    /// # Returns
+    ///
+    /// A tuple containing the identifier of the Tracker link type and the direction of the link,
+    /// or `None` as the direction if the link type is symmetric.
+    pub fn link_type(&self) -> (&'static str, Option<&'static str>) {
+        match self {
+            LinkRelationship::Relates => ("relates", None),
+            LinkRelationship::DependsOn => ("depends", Some("outward")),
+            LinkRelationship::IsDependentBy => ("depends", Some("inward")),
+            LinkRelationship::Duplicates => ("duplicates", Some("outward")),
+            LinkRelationship::IsDuplicatedBy => ("duplicates", Some("inward")),
+            LinkRelationship::IsEpicOf => ("epic", Some("inward")),
+            LinkRelationship::HasEpic => ("epic", Some("outward")),
+            LinkRelationship::IsSubtaskFor => ("subtask", Some("outward")),
+            LinkRelationship::IsParentTaskFor => ("subtask", Some("inward")),
+        }
+    }
+}
+
+/// Represents a link declared on a task of a batch.
+///
+/// Exactly one of `issue` and `task` must be set.
+///
+/// # Fields
+///
+/// * `relationship` - The relationship between the task and the linked issue.
+/// * `issue` - The key of an existing issue (optional).
+/// * `task` - The `unique` of a task created in the same batch (optional).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TaskLinkInfo {
+    pub relationship: LinkRelationship,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+}
+
+impl TaskLinkInfo {
+    /// Checks if the link refers to exactly one target.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - If exactly one of `issue` and `task` is set and not empty.
+    /// * `false` - Otherwise.
+    pub fn has_single_target(&self) -> bool {
+        match (&self.issue, &self.task) {
+            (Some(issue), None) => !issue.is_empty(),
+            (None, Some(task)) => !task.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Resolves the key of the linked issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `resolved` - A map from the `unique` of tasks created in the batch to their keys.
+    ///
+    /// # Returns
+    ///
+    /// The key of the linked issue, or `None` if the link refers to a task that has not been created yet.
+    pub fn target(&self, resolved: &HashMap<String, String>) -> Option<String> {
+        match (&self.issue, &self.task) {
+            (Some(issue), _) => Some(issue.clone()),
+            (None, Some(task)) => resolved.get(task).cloned(),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Represents a link waiting to be created once all the tasks it refers to exist.
+///
+/// # Fields
+///
+/// * `issue` - The key of the issue the link is created on.
+/// * `link` - The declared link.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PendingLink {
+    pub issue: String,
+    pub link: TaskLinkInfo,
+}
+
+/// Represents the body of a link creation request.
+///
+/// # Fields
+///
+/// * `relationship` - The relationship between the issues.
+/// * `issue` - The key of the linked issue.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatedLinkBody {
+    pub relationship: LinkRelationship,
+    pub issue: String,
+}
+
+/// Represents a link of an issue returned by the Yandex Tracker API.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IssueLink {
+    /// The URL of the link.
+    ///
+    /// This field is serialized/deserialized as `self`.
+    #[serde(rename = "self")]
+    pub url: String,
+
+    /// The identifier of the link.
+    pub id: u64,
+
+    /// The type of the link.
+    ///
+    /// This field is serialized/deserialized as `type`.
+    #[serde(rename = "type")]
+    pub link_type: LinkType,
+
+    /// The direction of the link, `inward` or `outward`.
+    pub direction: String,
+
+    /// The linked issue.
+    pub object: LinkedIssue,
+}
+
+impl IssueLink {
+    /// Checks if the link connects to the given issue with the given relationship.
+    ///
+    /// # Arguments
+    ///
+    /// * `relationship` - The declared relationship.
+    /// * `key` - The key of the linked issue.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - If the link points at `key` with the link type and direction of `relationship`.
+    /// * `false` - Otherwise.
+    pub fn matches(&self, relationship: LinkRelationship, key: &str) -> bool {
+        let (link_type, direction) = relationship.link_type();
+        self.object.key == key
+            && self.link_type.id == link_type
+            && direction.is_none_or(|direction| self.direction == direction)
+    }
+}
+
+/// Represents the type of an issue link.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LinkType {
+    /// The identifier of the link type.
+    pub id: String,
+
+    /// The name of the link type for the inward direction.
+    pub inward: String,
+
+    /// The name of the link type for the outward direction.
+    pub outward: String,
+}
+
+/// Represents the issue on the other side of a link.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LinkedIssue {
+    /// The URL of the issue.
+    ///
+    /// This field is serialized/deserialized as `self`.
+    #[serde(rename = "self")]
+    pub url: String,
+
+    /// The unique identifier of the issue.
+    pub id: String,
+
+    /// The key of the issue.
+    pub key: String,
+
+    /// The display name of the issue.
+    pub display: String,
+}
+
+impl TaskApiClient {
+    /// Lists the links of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the links of the issue, or `HandleResponseError` if an error occurred.
+    pub async fn list_links(&self, issue_id: &str) -> Result<Vec<IssueLink>, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/issues/{}/links", issue_id))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Links an issue to another issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue the link is created on.
+    /// * `link` - The relationship and the key of the linked issue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the created link, or `HandleResponseError` if an error occurred.
+    pub async fn create_link(
+        &self,
+        issue_id: &str,
+        link: CreatedLinkBody,
+    ) -> Result<IssueLink, HandleResponseError> {
+        let response = self
+            .request(Method::POST, &format!("/issues/{}/links", issue_id))
+            .json(&link)
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Deletes a link of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `link_id` - The identifier of the link.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result`, or `HandleResponseError` if an error occurred.
+    pub async fn delete_link(&self, issue_id: &str, link_id: u64) -> Result<(), HandleResponseError> {
+        let response = self
+            .request(Method::DELETE, &format!("/issues/{}/links/{}", issue_id, link_id))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_empty_response(response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_relationship_aliases() {
+        let link: TaskLinkInfo = serde_json::from_value(json!({
+            "relationship": "is blocked by",
+            "issue": "TASK-1"
+        }))
+        .unwrap();
+
+        assert_eq!(link.relationship, LinkRelationship::DependsOn);
+        assert_eq!(
+            serde_json::to_value(CreatedLinkBody {
+                relationship: link.relationship,
+                issue: "TASK-1".to_string(),
+            })
+            .unwrap(),
+            json!({ "relationship": "depends on", "issue": "TASK-1" })
+        );
+    }
+
+    #[test]
+    fn test_link_target() {
+        let mut resolved = HashMap::new();
+        resolved.insert("epic".to_string(), "TASK-7".to_string());
+
+        let sibling = TaskLinkInfo {
+            relationship: LinkRelationship::HasEpic,
+            issue: None,
+            task: Some("epic".to_string()),
+        };
+        let both = TaskLinkInfo {
+            relationship: LinkRelationship::Relates,
+            issue: Some("TASK-1".to_string()),
+            task: Some("epic".to_string()),
+        };
+
+        assert!(sibling.has_single_target());
+        assert_eq!(sibling.target(&resolved), Some("TASK-7".to_string()));
+        assert_eq!(sibling.target(&HashMap::new()), None);
+        assert!(!both.has_single_target());
+    }
+
+    #[test]
+    fn test_link_matches() {
+        let link: IssueLink = serde_json::from_value(json!({
+            "self": "https://api.tracker.yandex.net/v2/issues/TASK-2/links/1",
+            "id": 1,
+            "type": { "id": "epic", "inward": "is epic of", "outward": "has epic" },
+            "direction": "outward",
+            "object": { "self": "https://api.tracker.yandex.net/v2/issues/TASK-1", "id": "1", "key": "TASK-1", "display": "Epic" }
+        }))
+        .unwrap();
+
+        assert!(link.matches(LinkRelationship::HasEpic, "TASK-1"));
+        assert!(!link.matches(LinkRelationship::IsEpicOf, "TASK-1"));
+        assert!(!link.matches(LinkRelationship::Relates, "TASK-1"));
+        assert!(!link.matches(LinkRelationship::HasEpic, "TASK-3"));
+    }
+}
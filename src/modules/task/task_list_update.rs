@@ -0,0 +1,150 @@
+use std::fmt;
+
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Represents the change of a list field of an existing issue, such as its followers or tags.
+///
+/// A batch declares it as a list, which replaces the values of the issue, or as an object of
+/// operations: `{"add": [...]}` and `{"remove": [...]}`, which may be combined, or `{"set": [...]}`.
+/// A single value stands for a list of one value. An empty list leaves the field untouched; use
+/// `{"set": []}` to clear it.
+///
+/// # Fields
+///
+/// * `set` - The values replacing those of the issue (optional).
+/// * `add` - The values added to those of the issue.
+/// * `remove` - The values removed from those of the issue.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct ListUpdate {
+    pub set: Option<Vec<String>>,
+    pub add: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+impl ListUpdate {
+    /// Creates a `ListUpdate` replacing the values of the issue, or leaving them untouched if
+    /// `values` is empty.
+    pub fn set(values: Vec<String>) -> Self {
+        ListUpdate {
+            set: Some(values).filter(|values| !values.is_empty()),
+            ..ListUpdate::default()
+        }
+    }
+
+    /// Checks if the update leaves the field untouched.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_none() && self.add.is_empty() && self.remove.is_empty()
+    }
+
+    /// Lists the values the update mentions, with their JSON path relative to the field.
+    ///
+    /// Replacing values are listed as `[0]`, `[1]`…, the others as `.add[0]` or `.remove[0]`.
+    pub fn values(&self) -> Vec<(String, &str)> {
+        let set = self.set.iter().flatten().enumerate().map(|(index, value)| (format!("[{}]", index), value));
+        let add = self.add.iter().enumerate().map(|(index, value)| (format!(".add[{}]", index), value));
+        let remove = self.remove.iter().enumerate().map(|(index, value)| (format!(".remove[{}]", index), value));
+        set.chain(add).chain(remove).map(|(path, value)| (path, value.as_str())).collect()
+    }
+}
+
+impl Serialize for ListUpdate {
+    /// Writes the update as a list if it only replaces the values with some values, and as an
+    /// object of operations otherwise.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.set {
+            Some(values) if !values.is_empty() && self.add.is_empty() && self.remove.is_empty() => {
+                values.serialize(serializer)
+            }
+            _ => {
+                let mut map = serializer.serialize_map(None)?;
+                if let Some(values) = &self.set {
+                    map.serialize_entry("set", values)?;
+                }
+                if !self.add.is_empty() {
+                    map.serialize_entry("add", &self.add)?;
+                }
+                if !self.remove.is_empty() {
+                    map.serialize_entry("remove", &self.remove)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ListUpdate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ListUpdateVisitor)
+    }
+}
+
+/// Reads a `ListUpdate` from a value, a list or an object of operations.
+struct ListUpdateVisitor;
+
+impl<'de> Visitor<'de> for ListUpdateVisitor {
+    type Value = ListUpdate;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a list or an object with `add`, `remove` or `set` lists")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(ListUpdate::set(vec![value.to_string()]))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(ListUpdate::default())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(ListUpdate::default())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(ListUpdate::set(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut update = ListUpdate::default();
+        while let Some(operation) = map.next_key::<String>()? {
+            match operation.as_str() {
+                "set" => update.set = Some(map.next_value()?),
+                "add" => update.add = map.next_value()?,
+                "remove" => update.remove = map.next_value()?,
+                _ => return Err(de::Error::unknown_field(&operation, &["add", "remove", "set"])),
+            }
+        }
+        if update.set.is_some() && !(update.add.is_empty() && update.remove.is_empty()) {
+            return Err(de::Error::custom("`set` cannot be combined with `add` or `remove`"));
+        }
+        Ok(update)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_list_update_forms() {
+        let parse = |value| serde_json::from_value::<ListUpdate>(value);
+
+        assert_eq!(parse(json!("Sprint 1")).unwrap(), ListUpdate::set(vec!["Sprint 1".to_string()]));
+        assert!(parse(json!([])).unwrap().is_empty());
+        assert_eq!(parse(json!({ "set": [] })).unwrap().set, Some(Vec::new()));
+        assert!(parse(json!({ "set": ["a"], "add": ["b"] })).is_err());
+        assert!(parse(json!({ "append": ["a"] })).is_err());
+
+        let update = parse(json!({ "add": ["jdoe"], "remove": ["asmith"] })).unwrap();
+        assert_eq!(serde_json::to_value(&update).unwrap(), json!({ "add": ["jdoe"], "remove": ["asmith"] }));
+        assert_eq!(update.values()[1], (".remove[0]".to_string(), "asmith"));
+        assert_eq!(serde_json::to_value(ListUpdate::set(vec!["a".to_string()])).unwrap(), json!(["a"]));
+    }
+}
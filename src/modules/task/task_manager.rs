@@ -1,15 +1,67 @@
-use std::fs;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
 
 use super::task_api_client::{HandleResponseError, TaskApiClient};
+use super::task_attachment::LocalAttachment;
 use super::task_batch::TaskBatch;
-use super::{CreatedTaskBody, CreatedTaskInfo, UpdatedTask};
+use super::task_checklist::ChecklistItemInfo;
+use super::task_diff::{diff_fields, FieldChange};
+use super::task_journal::{JournalAction, TaskJournal};
+use super::task_link::{CreatedLinkBody, PendingLink, TaskLinkInfo};
+use super::task_plan::{is_empty_value, issue_version};
+use super::task_reference::{references, resolve_references};
+use super::{CreatedTaskBody, CreatedTaskInfo, UpdatedTask, UpdatedTaskInfo};
 
 use tokio::time::{sleep, Duration};
 
+/// Represents what to do with an update when its issue was changed by someone else during the run.
+///
+/// # Variants
+///
+/// * `Fail` - The update fails with `HandleResponseError::VersionConflict`, the default.
+/// * `Rebase` - The update is sent anyway if none of its fields were changed meanwhile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictStrategy {
+    #[default]
+    Fail,
+    Rebase,
+}
+
+impl FromStr for ConflictStrategy {
+    type Err = String;
+
+    /// Parses a strategy name: `fail` or `rebase`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "fail" => Ok(ConflictStrategy::Fail),
+            "rebase" => Ok(ConflictStrategy::Rebase),
+            _ => Err(format!("unknown strategy `{}`, expected fail or rebase", name)),
+        }
+    }
+}
+
+impl fmt::Display for ConflictStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictStrategy::Fail => write!(f, "fail"),
+            ConflictStrategy::Rebase => write!(f, "rebase"),
+        }
+    }
+}
+
 /// Handles batch processing of tasks using the Yandex Tracker API client.
 pub struct TaskBatchHandler {
     /// The API client used for interacting with the Yandex Tracker.
     api_client: TaskApiClient,
+
+    /// Whether to keep processing independent entries after an operation fails.
+    keep_going: bool,
+
+    /// What to do with updates of issues changed by someone else during the run.
+    conflict_strategy: ConflictStrategy,
 }
 
 impl TaskBatchHandler {
@@ -19,37 +71,117 @@ impl TaskBatchHandler {
     ///
     /// * `api_client` - The API client used for task operations.
     pub fn new(api_client: TaskApiClient) -> Self {
-        TaskBatchHandler { api_client }
+        TaskBatchHandler {
+            api_client,
+            keep_going: false,
+            conflict_strategy: ConflictStrategy::Fail,
+        }
+    }
+
+    /// Sets the failure policy of the handler.
+    ///
+    /// By default the first failed operation stops the run. With `keep_going`, failures are
+    /// recorded in the journal and processing goes on with the entries that do not depend on
+    /// the failed ones: the subtasks, checklist, links and comments of a task that could not be
+    /// created or updated, and the entries referring to it, are skipped, while every other entry is processed.
+    ///
+    /// # Arguments
+    ///
+    /// * `keep_going` - Whether to keep processing after a failure.
+    pub fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// Sets what to do with updates of issues changed by someone else during the run.
+    ///
+    /// The issues to update are read when the run starts, and every update is sent with the version
+    /// read then, or with the `version` declared on the entry. If the issue has changed since, the
+    /// update fails, unless the strategy is `Rebase` and the changes of the issue and of the update
+    /// do not overlap, in which case the update is sent against the current version.
+    ///
+    /// # Arguments
+    ///
+    /// * `conflict_strategy` - The strategy, `ConflictStrategy::Fail` by default.
+    pub fn with_conflict_strategy(mut self, conflict_strategy: ConflictStrategy) -> Self {
+        self.conflict_strategy = conflict_strategy;
+        self
     }
 
     /// Processes a batch of tasks, creating tasks and their subtasks in the Yandex Tracker.
     ///
+    /// Tasks are created in file order, each one after the task it depends on: the subtasks of a
+    /// task are created right after it, and a task whose `parent` refers to another entry as
+    /// `${id}` is created once that entry exists (see `TaskBatch::creation_order`). Updates run
+    /// once every task is created, so they may refer to created tasks as well.
+    /// Local attachments of all tasks are inspected before any request is sent, and uploaded
+    /// right before the task that references them is created or updated.
+    /// Checklist items declared on a task are added right after the task is created or updated.
+    /// Links and comments declared on the tasks are created last, once every task they refer to exists.
+    ///
+    /// The batch itself is left untouched: the outcome of every operation is appended to the
+    /// journal of the run, keyed by the JSON path of the entry in the input file. Operations
+    /// the journal records as completed are skipped, so a resumed run picks up where it stopped.
+    ///
+    /// Every created task is sent with a `unique`, generated from the entry if it has none.
+    /// If the tracker already holds a task with that `unique`, the existing task is used instead,
+    /// which makes running the same batch twice safe.
+    ///
+    /// Unless the handler keeps going (see `with_keep_going`), the first failure stops the run.
+    /// Updates of issues changed by someone else during the run are handled as set by
+    /// `with_conflict_strategy`.
+    ///
     /// # Arguments
     ///
     /// * `task_batch` - A batch of tasks to be processed.
+    /// * `journal` - The journal of the run.
     ///
     /// # Returns
     ///
     /// A `Result` indicating the success or failure of the operation.
-    pub async fn process_tasks(&self, task_batch: TaskBatch) -> Result<(), HandleResponseError> {
-        let mut task_batch_mut = task_batch.clone();
+    pub async fn process_tasks(
+        &self,
+        task_batch: &TaskBatch,
+        journal: &mut TaskJournal,
+    ) -> Result<(), HandleResponseError> {
+        LocalAttachment::inspect_all(task_batch.local_attachments())?;
+
         let duration = Duration::from_secs(1);
+        let mut progress = BatchProgress {
+            resolved: task_batch.resolved.clone(),
+            ids: task_batch.ids.clone(),
+            links: task_batch
+                .links
+                .iter()
+                .enumerate()
+                .map(|(index, link)| (format!("$.links[{}]", index), link.clone()))
+                .collect(),
+            comments: Vec::new(),
+            bases: self.read_bases(task_batch, journal).await,
+        };
 
-        task_batch_mut = self
-            .process_tasks_create_task(task_batch_mut, duration)
+        self.process_tasks_create_task(task_batch, &mut progress, journal, duration)
             .await?;
 
-        self.process_tasks_update_task(task_batch_mut, duration)
+        self.process_tasks_update_task(task_batch, &mut progress, journal, duration)
             .await?;
 
-        Ok(())
+        self.process_tasks_link_task(&progress, journal, duration)
+            .await?;
+
+        self.process_tasks_comment_task(&progress, journal, duration)
+            .await
     }
 
     /// Updates tasks in the batch using the Yandex Tracker API client.
     ///
+    /// An update whose `issue_id` or `parent` refers to a task that was not created is skipped.
+    ///
     /// # Arguments
     ///
-    /// * `task_batch_mut` - A mutable reference to the batch of tasks to be updated.
+    /// * `task_batch` - The batch of tasks to be updated.
+    /// * `progress` - The keys and deferred operations collected so far.
+    /// * `journal` - The journal of the run.
     /// * `duration` - The duration to wait between task updates.
     ///
     /// # Returns
@@ -57,31 +189,73 @@ impl TaskBatchHandler {
     /// A `Result` indicating the success or failure of the operation.
     async fn process_tasks_update_task(
         &self,
-        mut task_batch_mut: TaskBatch,
+        task_batch: &TaskBatch,
+        progress: &mut BatchProgress,
+        journal: &mut TaskJournal,
         duration: Duration,
-    ) -> Result<TaskBatch, HandleResponseError> {
-        for update_task_info in task_batch_mut.updated.clone() {
-            self.api_client
-                .update_task(
-                    &update_task_info.issue_id,
-                    UpdatedTask::from(update_task_info.clone()),
-                )
-                .await?;
+    ) -> Result<(), HandleResponseError> {
+        for (index, declared_task_info) in task_batch.updated.iter().enumerate() {
+            let path = format!("$.updated[{}]", index);
 
-            sleep(duration).await;
+            let issue_id = resolve_references(&declared_task_info.issue_id, &progress.ids);
+            let parent = declared_task_info
+                .parent
+                .as_deref()
+                .map(|parent| resolve_references(parent, &progress.ids))
+                .transpose();
+            let epic = declared_task_info
+                .epic
+                .as_deref()
+                .map(|epic| resolve_references(epic, &progress.ids))
+                .transpose();
+            let update_task_info = match (issue_id, parent, epic) {
+                (Ok(issue_id), Ok(parent), Ok(epic)) => UpdatedTaskInfo {
+                    issue_id,
+                    parent,
+                    epic,
+                    ..declared_task_info.clone()
+                },
+                // A task the update refers to was not created.
+                _ => continue,
+            };
+
+            let is_completed = journal.completed(&path, JournalAction::Update).is_some();
+            if update_task_info.has_field_changes() && !is_completed {
+                let base = progress.bases.get(&path);
+                let result = match self.update_task(&path, &update_task_info, base, journal).await {
+                    Ok(changes) => journal
+                        .record_update(&path, &update_task_info.issue_id, changes)
+                        .map(|()| update_task_info.issue_id.clone())
+                        .map_err(HandleResponseError::from),
+                    Err(err) => journaled(journal, &path, JournalAction::Update, Err(err)),
+                };
+                let updated = self.tolerate(result)?;
+
+                sleep(duration).await;
+                if updated.is_none() {
+                    continue;
+                }
+            }
 
-            task_batch_mut.updated.remove(&update_task_info);
+            let result = self
+                .add_checklist(&path, &update_task_info.issue_id, &update_task_info.checklist, true, journal)
+                .await;
+            self.tolerate(result)?;
 
-            self.save_task_batch(&task_batch_mut)?;
+            progress.defer(&path, &update_task_info.issue_id, &update_task_info.links, &update_task_info.comments);
         }
-        return Ok(task_batch_mut);
+        Ok(())
     }
 
     /// Creates tasks in the batch using the Yandex Tracker API client.
     ///
+    /// A task is skipped if the task it depends on was not created.
+    ///
     /// # Arguments
     ///
-    /// * `task_batch_mut` - A mutable reference to the batch of tasks to be created.
+    /// * `task_batch` - The batch of tasks to be created.
+    /// * `progress` - The keys and deferred operations collected so far.
+    /// * `journal` - The journal of the run.
     /// * `duration` - The duration to wait between task creations.
     ///
     /// # Returns
@@ -89,52 +263,516 @@ impl TaskBatchHandler {
     /// A `Result` indicating the success or failure of the operation.
     async fn process_tasks_create_task(
         &self,
-        mut task_batch_mut: TaskBatch,
+        task_batch: &TaskBatch,
+        progress: &mut BatchProgress,
+        journal: &mut TaskJournal,
         duration: Duration,
-    ) -> Result<TaskBatch, HandleResponseError> {
-        while !task_batch_mut.created.is_empty() {
-            let vec_created: Vec<CreatedTaskInfo> =
-                task_batch_mut.created.clone().into_iter().collect();
-
-            match vec_created.first() {
-                Some(task_from_created) => {
-                    let response = self
-                        .api_client
-                        .create_task(CreatedTaskBody::from(task_from_created.clone()))
-                        .await?;
-
-                    sleep(duration).await;
+    ) -> Result<(), HandleResponseError> {
+        // The key and the queue of every created task, by the JSON path of its entry.
+        let mut created: HashMap<String, (String, String)> = HashMap::new();
 
-                    let subtask = task_from_created.subtasks.clone();
+        for entry in task_batch.creation_order() {
+            let path = entry.path;
+            let task_from_created = match &entry.nesting {
+                Some(nesting) => match created.get(nesting) {
+                    Some((key, queue)) => entry.task.set(key.clone(), queue.clone()),
+                    // The task it is nested in was not created.
+                    None => continue,
+                },
+                None => match entry.task.parent.as_deref().map(|parent| resolve_references(parent, &progress.ids)) {
+                    Some(Ok(parent)) => CreatedTaskInfo {
+                        parent: Some(parent),
+                        ..entry.task.clone()
+                    },
+                    // The task its parent refers to was not created.
+                    Some(Err(_)) => continue,
+                    None => entry.task.clone(),
+                },
+            };
 
-                    task_batch_mut.created.remove(task_from_created);
+            let (key, existing) = match journal.completed(&path, JournalAction::Create) {
+                Some(key) => (key.to_string(), true),
+                None => {
+                    let task_to_create = match &task_from_created.unique {
+                        Some(_) => task_from_created.clone(),
+                        None => task_from_created.with_unique(&journal.unique(&path)?),
+                    };
+                    let result = self.create_task(&task_to_create).await;
+                    let existing = matches!(result, Ok((_, true)));
+                    let key = self.tolerate(journaled(journal, &path, JournalAction::Create, result))?;
 
-                    for task_from_subtask in subtask {
-                        task_batch_mut.created.insert(
-                            task_from_subtask
-                                .set(response.key.clone(), task_from_created.queue.clone()),
-                        );
+                    sleep(duration).await;
+                    match key {
+                        Some(key) => (key, existing),
+                        // The checklist, links, comments and dependent tasks of the task are skipped.
+                        None => continue,
                     }
-                    self.save_task_batch(&task_batch_mut)?;
                 }
-                None => return Ok(task_batch_mut),
+            };
+
+            let result = self
+                .add_checklist(&path, &key, &task_from_created.checklist, existing, journal)
+                .await;
+            self.tolerate(result)?;
+
+            if let Some(unique) = &task_from_created.unique {
+                progress.resolved.insert(unique.clone(), key.clone());
+            }
+            if let Some(id) = &task_from_created.id {
+                progress.ids.insert(id.clone(), key.clone());
             }
+            progress.defer(&path, &key, &task_from_created.links, &task_from_created.comments);
+            created.insert(path, (key, task_from_created.queue));
         }
-        return Ok(task_batch_mut);
+        Ok(())
     }
 
-    /// Saves the current state of the task batch to a JSON file.
+    /// Creates the links collected while creating and updating the tasks of the batch.
     ///
     /// # Arguments
     ///
-    /// * `task_batch` - The task batch to be saved.
+    /// * `progress` - The links to create, with the keys of the created tasks.
+    /// * `journal` - The journal of the run.
+    /// * `duration` - The duration to wait between link creations.
     ///
     /// # Returns
     ///
     /// A `Result` indicating the success or failure of the operation.
-    fn save_task_batch(&self, task_batch: &TaskBatch) -> Result<(), std::io::Error> {
-        let tasks_json = serde_json::to_string_pretty(task_batch)?;
-        fs::write("tasks.json", tasks_json)?;
+    async fn process_tasks_link_task(
+        &self,
+        progress: &BatchProgress,
+        journal: &mut TaskJournal,
+        duration: Duration,
+    ) -> Result<(), HandleResponseError> {
+        for (path, pending_link) in &progress.links {
+            if journal.completed(path, JournalAction::Link).is_some() {
+                continue;
+            }
+
+            let result = self.create_link(pending_link, progress).await;
+            self.tolerate(journaled(journal, path, JournalAction::Link, result))?;
+
+            sleep(duration).await;
+        }
         Ok(())
     }
+
+    /// Posts the comments collected while creating and updating the tasks of the batch.
+    ///
+    /// References in the text of a comment are replaced with the keys of the tasks they refer to.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - The comments to post, with the keys of the created tasks.
+    /// * `journal` - The journal of the run.
+    /// * `duration` - The duration to wait between comments.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating the success or failure of the operation.
+    async fn process_tasks_comment_task(
+        &self,
+        progress: &BatchProgress,
+        journal: &mut TaskJournal,
+        duration: Duration,
+    ) -> Result<(), HandleResponseError> {
+        for (path, issue, text) in &progress.comments {
+            if journal.completed(path, JournalAction::AddComment).is_some() {
+                continue;
+            }
+
+            let result = async {
+                let text = resolve_references(text, &progress.ids)?;
+                self.api_client.add_comment(issue, &text).await?;
+                Ok((issue.clone(), false))
+            }
+            .await;
+            self.tolerate(journaled(journal, path, JournalAction::AddComment, result))?;
+
+            sleep(duration).await;
+        }
+        Ok(())
+    }
+
+    /// Applies the failure policy to the outcome of an operation already recorded in the journal.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the value of the operation, or `None` if it failed and the handler
+    /// keeps going; the error of the operation otherwise.
+    fn tolerate<T>(&self, result: Result<T, HandleResponseError>) -> Result<Option<T>, HandleResponseError> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            // The journal could not be written, so the run cannot go on safely.
+            Err(HandleResponseError::IO(err)) => Err(HandleResponseError::IO(err)),
+            Err(_) if self.keep_going => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Uploads the local attachments of a task and creates it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the key of the issue, and whether a task with the same `unique`
+    /// already existed.
+    async fn create_task(
+        &self,
+        task_from_created: &CreatedTaskInfo,
+    ) -> Result<(String, bool), HandleResponseError> {
+        let mut task_body = CreatedTaskBody::from(task_from_created.clone());
+        task_body.attachment_ids.extend(
+            self.api_client
+                .upload_temporary_attachments(&task_from_created.attachments)
+                .await?,
+        );
+        task_body.description_attachment_ids.extend(
+            self.api_client
+                .upload_temporary_attachments(&task_from_created.description_attachments)
+                .await?,
+        );
+
+        match self.api_client.create_task(task_body).await {
+            Ok(response) => Ok((response.key, false)),
+            Err(err) if err.is_conflict() => {
+                let unique = task_from_created.unique.as_deref().unwrap_or_default();
+                match self.api_client.find_task_by_unique(unique).await? {
+                    Some(existing) => Ok((existing.key, true)),
+                    None => Err(err),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Reads the issues the batch updates, before the run changes anything.
+    ///
+    /// Issues referred to as `${id}` are created by the run and are not read, and neither are
+    /// issues that cannot be read: their update reports the error.
+    ///
+    /// # Returns
+    ///
+    /// The issues as returned by the API, by the local id of their entry.
+    async fn read_bases(&self, task_batch: &TaskBatch, journal: &TaskJournal) -> HashMap<String, Value> {
+        let mut bases = HashMap::new();
+        for (index, task) in task_batch.updated.iter().enumerate() {
+            let path = format!("$.updated[{}]", index);
+            let is_pending = task.has_field_changes() && journal.completed(&path, JournalAction::Update).is_none();
+            if !is_pending || !references(&task.issue_id).is_empty() {
+                continue;
+            }
+            if let Ok(base) = self.api_client.get_task_fields(&task.issue_id).await {
+                bases.insert(path, base);
+            }
+        }
+        bases
+    }
+
+    /// Uploads the local attachments of a task and updates it.
+    ///
+    /// The issue is read first and compared with the update: if no field would change and there
+    /// is nothing to attach, the request is not sent. If the issue has changed since the version the
+    /// update expects, the conflict strategy applies, see `with_conflict_strategy`. Otherwise the
+    /// fields the update overwrites are recorded in the journal as a snapshot, so that a rollback can
+    /// restore them, and the update is sent with the version just read.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The local id of the batch entry.
+    /// * `update_task_info` - The update.
+    /// * `base` - The issue as read when the run started (optional).
+    /// * `journal` - The journal of the run.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the changed fields, before and after the update, empty if it was not sent.
+    async fn update_task(
+        &self,
+        path: &str,
+        update_task_info: &UpdatedTaskInfo,
+        base: Option<&Value>,
+        journal: &mut TaskJournal,
+    ) -> Result<Vec<FieldChange>, HandleResponseError> {
+        let mut update_task = UpdatedTask::from(update_task_info.clone());
+
+        let current = self.api_client.get_task_fields(&update_task_info.issue_id).await?;
+        let body = serde_json::to_value(&update_task)?;
+        let has_files = !update_task_info.attachments.is_empty() || !update_task_info.description_attachments.is_empty();
+        if !has_files && diff_fields(&body, &current).is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let expected = update_task_info.version.or_else(|| base.and_then(issue_version));
+        if let Some(expected) = expected.filter(|expected| issue_version(&current) != Some(*expected)) {
+            // The fields both changed, known only if the issue was read at the expected version.
+            let fields = base
+                .filter(|base| issue_version(base) == Some(expected))
+                .map(|base| overlapping_fields(&body, base, &current));
+            match (self.conflict_strategy, fields) {
+                (ConflictStrategy::Rebase, Some(fields)) if fields.is_empty() => {}
+                (_, fields) => {
+                    return Err(HandleResponseError::VersionConflict {
+                        issue: update_task_info.issue_id.clone(),
+                        expected,
+                        fields: fields.unwrap_or_default(),
+                    })
+                }
+            }
+        }
+        update_task.version = issue_version(&current);
+        journal.record_snapshot(path, &update_task_info.issue_id, update_task.original_fields(&current))?;
+
+        update_task.attachment_ids.extend(
+            self.api_client
+                .upload_temporary_attachments(&update_task_info.attachments)
+                .await?,
+        );
+        update_task.description_attachment_ids.extend(
+            self.api_client
+                .upload_temporary_attachments(&update_task_info.description_attachments)
+                .await?,
+        );
+
+        let changes = diff_fields(&serde_json::to_value(&update_task)?, &current);
+        self.api_client
+            .update_task(&update_task_info.issue_id, update_task)
+            .await?;
+        Ok(changes)
+    }
+
+    /// Adds the checklist items declared on a task, recording the outcome in the journal.
+    ///
+    /// On an issue that existed before, items whose text is already in its checklist are skipped,
+    /// so that an interrupted run does not add them twice.
+    async fn add_checklist(
+        &self,
+        path: &str,
+        key: &str,
+        checklist: &[ChecklistItemInfo],
+        existing: bool,
+        journal: &mut TaskJournal,
+    ) -> Result<(), HandleResponseError> {
+        let path = format!("{}.checklist", path);
+        if checklist.is_empty() || journal.completed(&path, JournalAction::AddChecklist).is_some() {
+            return Ok(());
+        }
+
+        let result = async {
+            let mut items = checklist.to_vec();
+            if existing {
+                let present: HashSet<String> = self
+                    .api_client
+                    .get_checklist(key)
+                    .await?
+                    .into_iter()
+                    .map(|item| item.text)
+                    .collect();
+                items.retain(|item| !present.contains(&item.text));
+            }
+
+            self.api_client.add_checklist(key, &items).await?;
+            Ok((key.to_string(), false))
+        }
+        .await;
+        journaled(journal, &path, JournalAction::AddChecklist, result).map(|_| ())
+    }
+
+    /// Resolves the target of a link and creates it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the key of the issue the link was created on,
+    /// and whether the link already existed.
+    async fn create_link(
+        &self,
+        pending_link: &PendingLink,
+        progress: &BatchProgress,
+    ) -> Result<(String, bool), HandleResponseError> {
+        let target = pending_link.link.target(&progress.resolved).ok_or_else(|| {
+            HandleResponseError::UnresolvedLink(pending_link.link.task.clone().unwrap_or_default())
+        })?;
+        let target = resolve_references(&target, &progress.ids)?;
+        let issue = resolve_references(&pending_link.issue, &progress.ids)?;
+
+        let result = self
+            .api_client
+            .create_link(
+                &issue,
+                CreatedLinkBody {
+                    relationship: pending_link.link.relationship,
+                    issue: target,
+                },
+            )
+            .await;
+
+        match result {
+            Ok(_) => Ok((issue, false)),
+            Err(err) if err.is_conflict() => Ok((issue, true)),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// The keys resolved and the operations deferred while processing a batch.
+struct BatchProgress {
+    /// The keys of the created tasks by their `unique`.
+    resolved: HashMap<String, String>,
+
+    /// The keys of the created tasks by their local `id`.
+    ids: HashMap<String, String>,
+
+    /// The links to create once every task exists, with the local ids of the entries declaring them.
+    links: Vec<(String, PendingLink)>,
+
+    /// The comments to post once every task exists: the local id, the key of the issue and the text.
+    comments: Vec<(String, String, String)>,
+
+    /// The issues to update as read when the run started, by the local id of their entry.
+    bases: HashMap<String, Value>,
+}
+
+impl BatchProgress {
+    /// Defers the links and comments declared on a processed entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The local id of the batch entry.
+    /// * `key` - The key of the issue of the entry.
+    /// * `links` - The links declared on the entry.
+    /// * `comments` - The comments declared on the entry.
+    fn defer(&mut self, path: &str, key: &str, links: &[TaskLinkInfo], comments: &[String]) {
+        self.links.extend(links.iter().enumerate().map(|(index, link)| {
+            (
+                format!("{}.links[{}]", path, index),
+                PendingLink {
+                    issue: key.to_string(),
+                    link: link.clone(),
+                },
+            )
+        }));
+        self.comments.extend(
+            comments
+                .iter()
+                .enumerate()
+                .map(|(index, text)| (format!("{}.comments[{}]", path, index), key.to_string(), text.clone())),
+        );
+    }
+}
+
+/// Lists the fields an update sets that were changed on the issue since it was read.
+///
+/// # Arguments
+///
+/// * `body` - The body of the update.
+/// * `base` - The issue as read at the version the update expects.
+/// * `current` - The issue as it is now.
+fn overlapping_fields(body: &Value, base: &Value, current: &Value) -> Vec<String> {
+    body.as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(field, planned)| !is_empty_value(planned) && base.get(field.as_str()) != current.get(field.as_str()))
+        .map(|(field, _)| field.clone())
+        .collect()
+}
+
+/// Records the outcome of an operation in the journal.
+///
+/// # Arguments
+///
+/// * `journal` - The journal of the run.
+/// * `path` - The local id of the batch entry.
+/// * `action` - The operation performed.
+/// * `result` - The key the operation resulted in and whether it already existed, or the error.
+///
+/// # Returns
+///
+/// A `Result` containing the key, or the error of the operation.
+fn journaled(
+    journal: &mut TaskJournal,
+    path: &str,
+    action: JournalAction,
+    result: Result<(String, bool), HandleResponseError>,
+) -> Result<String, HandleResponseError> {
+    match &result {
+        Ok((key, true)) => journal.record_existing(path, action, key)?,
+        Ok((key, false)) => journal.record(path, action, Ok(key))?,
+        Err(err) => journal.record(path, action, Err(err))?,
+    }
+    result.map(|(key, _)| key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_overlapping_fields() {
+        let body = json!({ "summary": "Validate cards", "priority": "critical", "followers": [] });
+        let base = json!({ "version": 3, "summary": "Validate card numbers", "priority": { "key": "normal" } });
+        let current = json!({ "version": 4, "summary": "Validate card numbers", "priority": { "key": "minor" }, "followers": [{ "id": "7" }] });
+
+        assert_eq!(overlapping_fields(&body, &base, &current), vec!["priority".to_string()]);
+        assert!(overlapping_fields(&json!({ "summary": "Validate cards" }), &base, &current).is_empty());
+        assert_eq!("rebase".parse(), Ok(ConflictStrategy::Rebase));
+        assert!("merge".parse::<ConflictStrategy>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_reuses_existing_task() {
+        use super::super::task_journal::{JournalStatus, RUNS_DIR};
+        use std::sync::{Arc, Mutex};
+        use warp::http::StatusCode;
+        use warp::Filter;
+
+        // The server answers as if a crashed attempt of the run had already created the task.
+        let uniques = Arc::new(Mutex::new(Vec::new()));
+        let sent = uniques.clone();
+        let create = warp::post()
+            .and(warp::path("issues"))
+            .and(warp::path::end())
+            .and(warp::body::json())
+            .map(move |body: Value| {
+                sent.lock().unwrap().push(body["unique"].clone());
+                warp::reply::with_status(
+                    warp::reply::json(&json!({ "errorMessages": ["Issue already exists"], "statusCode": 409 })),
+                    StatusCode::CONFLICT,
+                )
+            });
+        let searched = uniques.clone();
+        let search = warp::post()
+            .and(warp::path!("issues" / "_search"))
+            .and(warp::body::json())
+            .map(move |body: Value| {
+                searched.lock().unwrap().push(body["filter"]["unique"].clone());
+                warp::reply::json(&json!([{
+                    "self": "", "id": "7", "key": "TEST-7", "version": 1, "summary": "Retro",
+                    "status": { "id": "1", "key": "open", "display": "Open" },
+                    "createdBy": { "self": "", "id": "1", "display": "Jane Doe" },
+                    "createdAt": "", "updatedAt": ""
+                }]))
+            });
+        let (address, server) = warp::serve(search.or(create)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let api_client = TaskApiClient::new(String::new(), String::new())
+            .with_base_url(&format!("http://{}", address));
+        let task_batch: TaskBatch =
+            serde_json::from_value(json!({ "created": [{ "queue": "TEST", "summary": "Retro", "subtasks": [] }], "updated": [] }))
+                .unwrap();
+        let runs_dir = std::env::temp_dir().join(format!("manager-test-{}", std::process::id())).join(RUNS_DIR);
+        let mut journal = TaskJournal::create(&runs_dir).unwrap();
+
+        let result = TaskBatchHandler::new(api_client).process_tasks(&task_batch, &mut journal).await;
+        let unique = journal.unique("$.created[0]").unwrap();
+        let entries = journal.entries().to_vec();
+        std::fs::remove_dir_all(runs_dir.parent().unwrap()).unwrap();
+
+        assert!(result.is_ok(), "{:?}", result);
+        assert!(unique.starts_with(&format!("batch-{}-", journal.run_id())));
+        assert_eq!(*uniques.lock().unwrap(), vec![json!(unique), json!(unique)]);
+        let create = entries.iter().find(|entry| entry.action == JournalAction::Create).unwrap();
+        assert_eq!(create.status, JournalStatus::Existing);
+        assert_eq!(create.key.as_deref(), Some("TEST-7"));
+    }
 }
@@ -0,0 +1,647 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::task_api_client::{HandleResponseError, TaskApiClient, API_BASE_URL};
+use super::task_batch::TaskBatch;
+use super::task_checklist::{ChecklistItemInfo, CreatedChecklistItemBody};
+use super::task_comment::CreatedCommentBody;
+use super::task_diff::{diff_fields, write_changes, FieldChange};
+use super::task_journal::run_unique;
+use super::task_link::{CreatedLinkBody, TaskLinkInfo};
+use super::task_reference::resolve_references;
+use super::{CreatedTaskBody, CreatedTaskInfo, UpdatedTask, UpdatedTaskInfo};
+use crate::modules::queue::metadata_cache::MetadataCache;
+
+/// Represents the kind of a planned HTTP operation.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    UploadAttachment,
+    CreateIssue,
+    UpdateIssue,
+    AddChecklistItem,
+    CreateLink,
+    AddComment,
+}
+
+/// Represents an HTTP operation that running the batch would perform.
+///
+/// # Fields
+///
+/// * `kind` - The kind of the operation.
+/// * `entry` - The JSON path of the batch entry the operation comes from.
+/// * `method` - The HTTP method.
+/// * `endpoint` - The endpoint path relative to `API_BASE_URL`.
+/// * `body` - The request body; keys of tasks that do not exist yet are placeholders such as `<$.created[0]>`.
+/// * `changes` - The fields of an existing issue changed by the operation.
+/// * `defaults` - The fields the tracker fills from the queue defaults when the issue is created.
+/// * `version` - The version of the issue an update expects, if declared on the entry.
+/// * `current_version` - The current version of the issue an update applies to.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PlannedOperation {
+    pub kind: OperationKind,
+    pub entry: String,
+    pub method: String,
+    pub endpoint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<FieldChange>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub defaults: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_version: Option<u32>,
+}
+
+impl PlannedOperation {
+    /// Creates a new `PlannedOperation` instance without changes, defaults or versions.
+    fn new<T: Serialize>(kind: OperationKind, entry: &str, method: Method, endpoint: String, body: Option<T>) -> Self {
+        PlannedOperation {
+            kind,
+            entry: entry.to_string(),
+            method: method.to_string(),
+            endpoint,
+            body: body.map(|body| serde_json::to_value(body).unwrap_or_default()),
+            changes: Vec::new(),
+            defaults: BTreeMap::new(),
+            version: None,
+            current_version: None,
+        }
+    }
+
+    /// Returns the key of the issue an `update_issue` operation applies to.
+    fn issue(&self) -> Option<&str> {
+        self.endpoint.strip_prefix("/issues/")
+    }
+}
+
+impl fmt::Display for PlannedOperation {
+    /// Formats the operation with its body, changes and defaults; the alternate flag, `{:#}`,
+    /// colours the changes, see `write_changes`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}  ({})", self.method, self.endpoint, self.entry)?;
+
+        if self.kind == OperationKind::UpdateIssue {
+            if self.changes.is_empty() {
+                write!(f, "\n     = no field changes, the update is skipped")?;
+            }
+            if let (Some(version), Some(current_version)) = (self.version, self.current_version) {
+                if version != current_version {
+                    write!(f, "\n     ! the issue changed since version {}, it is at version {}", version, current_version)?;
+                }
+            }
+            write_changes(f, &self.changes, "     ")?;
+        } else if let Some(Value::Object(body)) = &self.body {
+            for (field, value) in body {
+                write!(f, "\n     + {}: {}", field, value)?;
+            }
+        }
+
+        for (field, value) in &self.defaults {
+            write!(f, "\n     = {}: {} (queue default)", field, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents every HTTP operation that running a batch would perform, in execution order.
+///
+/// # Fields
+///
+/// * `api` - The base URL of the API the operations are sent to.
+/// * `operations` - The planned operations.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TaskBatchPlan {
+    pub api: String,
+    pub operations: Vec<PlannedOperation>,
+}
+
+impl fmt::Display for TaskBatchPlan {
+    /// Formats the plan with one numbered operation per paragraph, coloured with the alternate flag.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Plan: {} operation(s) against {}", self.operations.len(), self.api)?;
+        for (index, operation) in self.operations.iter().enumerate() {
+            if f.alternate() {
+                writeln!(f, "\n{:>3}. {:#}", index + 1, operation)?;
+            } else {
+                writeln!(f, "\n{:>3}. {}", index + 1, operation)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TaskBatchPlan {
+    /// Builds the plan of a batch without any request to the API.
+    ///
+    /// The operations follow the order of `TaskBatchHandler::process_tasks`: created tasks in creation
+    /// order, updated tasks, links, then comments. Keys that only exist once an earlier operation has run
+    /// are written as placeholders: `<$.created[0]>` for the key of a created task, including `${id}`
+    /// references to it, and `<attachment:path>` for the ID of an uploaded file.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_batch` - The batch to plan.
+    pub fn from_batch(task_batch: &TaskBatch) -> Self {
+        let entries = task_batch.creation_order();
+        let mut keys = task_batch.resolved.clone();
+        let mut ids = task_batch.ids.clone();
+        for entry in &entries {
+            if let Some(unique) = &entry.task.unique {
+                keys.insert(unique.clone(), placeholder(&entry.path));
+            }
+            if let Some(id) = &entry.task.id {
+                ids.insert(id.clone(), placeholder(&entry.path));
+            }
+        }
+
+        let mut operations = Vec::new();
+        let mut deferred = Deferred {
+            links: task_batch
+                .links
+                .iter()
+                .enumerate()
+                .map(|(index, pending)| (format!("$.links[{}]", index), pending.issue.clone(), pending.link.clone()))
+                .collect(),
+            comments: Vec::new(),
+        };
+
+        // The queue of every created task, by the JSON path of its entry.
+        let mut queues: HashMap<String, String> = HashMap::new();
+        for entry in &entries {
+            let parent = entry
+                .nesting
+                .as_ref()
+                .map(|nesting| (placeholder(nesting), queues.get(nesting).cloned().unwrap_or_default()));
+            let queue = plan_created(&mut operations, &mut deferred, &entry.path, entry.task, parent, &ids);
+            queues.insert(entry.path.clone(), queue);
+        }
+        for (index, task) in task_batch.updated.iter().enumerate() {
+            plan_updated(&mut operations, &mut deferred, &format!("$.updated[{}]", index), task, &ids);
+        }
+
+        for (entry, issue, link) in deferred.links {
+            let target = link
+                .issue
+                .clone()
+                .or_else(|| link.task.as_ref().and_then(|task| keys.get(task).cloned()))
+                .unwrap_or_default();
+            operations.push(PlannedOperation::new(
+                OperationKind::CreateLink,
+                &entry,
+                Method::POST,
+                format!("/issues/{}/links", with_placeholders(&issue, &ids)),
+                Some(CreatedLinkBody {
+                    relationship: link.relationship,
+                    issue: with_placeholders(&target, &ids),
+                }),
+            ));
+        }
+        for (entry, issue, text) in deferred.comments {
+            operations.push(PlannedOperation::new(
+                OperationKind::AddComment,
+                &entry,
+                Method::POST,
+                format!("/issues/{}/comments", issue),
+                Some(CreatedCommentBody {
+                    text: with_placeholders(&text, &ids),
+                }),
+            ));
+        }
+
+        TaskBatchPlan {
+            api: API_BASE_URL.to_string(),
+            operations,
+        }
+    }
+}
+
+/// Plans a batch against the live tracker without writing anything.
+///
+/// On top of `TaskBatchPlan::from_batch`, the planner reads the queues of the created tasks
+/// to report the fields filled from the queue defaults, and reads the updated issues to
+/// report the current value of every changed field.
+pub struct TaskBatchPlanner<'a> {
+    /// The metadata used to resolve the queue defaults.
+    cache: &'a MetadataCache,
+}
+
+impl<'a> TaskBatchPlanner<'a> {
+    /// Creates a new `TaskBatchPlanner` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The metadata used to resolve the queue defaults.
+    pub fn new(cache: &'a MetadataCache) -> Self {
+        TaskBatchPlanner { cache }
+    }
+
+    /// Plans every operation of a batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_batch` - The batch to plan.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the plan, or `HandleResponseError` if the tracker could not be read.
+    pub async fn plan(&self, task_batch: &TaskBatch) -> Result<TaskBatchPlan, HandleResponseError> {
+        let mut plan = TaskBatchPlan::from_batch(task_batch);
+
+        for operation in plan.operations.iter_mut() {
+            match operation.kind {
+                OperationKind::CreateIssue => self.resolve_defaults(operation).await?,
+                OperationKind::UpdateIssue => self.resolve_changes(operation).await?,
+                _ => {}
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Records the type and priority the tracker takes from the queue when the body does not set them.
+    async fn resolve_defaults(&self, operation: &mut PlannedOperation) -> Result<(), HandleResponseError> {
+        let body = match &operation.body {
+            Some(body) => body,
+            None => return Ok(()),
+        };
+        let queue = match body.get("queue").and_then(Value::as_str) {
+            Some(queue) => self.cache.queue(queue).await?,
+            None => None,
+        };
+
+        if let Some(queue) = queue {
+            let defaults = [("type", queue.default_type), ("priority", queue.default_priority)];
+            for (field, default) in defaults {
+                if let (None, Some(default)) = (body.get(field), default) {
+                    operation
+                        .defaults
+                        .insert(field.to_string(), default.key.unwrap_or(default.display));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares the body of an update with the current fields and version of the issue.
+    ///
+    /// An issue referred to by a key placeholder is created by the run and cannot be read yet:
+    /// every field of the body is a change.
+    async fn resolve_changes(&self, operation: &mut PlannedOperation) -> Result<(), HandleResponseError> {
+        let (issue, body) = match (operation.issue(), &operation.body) {
+            (Some(issue), Some(body)) => (issue, body),
+            _ => return Ok(()),
+        };
+        if is_placeholder(issue) {
+            operation.changes = diff_fields(body, &Value::Object(Map::new()));
+            return Ok(());
+        }
+        let current = self.cache.api_client().get_task_fields(issue).await?;
+        operation.changes = diff_fields(body, &current);
+        operation.current_version = issue_version(&current);
+        Ok(())
+    }
+}
+
+impl TaskApiClient {
+    /// Reads the raw fields of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the issue as a JSON object, or `HandleResponseError` if an error occurred.
+    pub async fn get_task_fields(&self, issue_id: &str) -> Result<Value, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/issues/{}", issue_id))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+}
+
+/// The links and comments planned once every task exists.
+struct Deferred {
+    /// The links: the local id, the key of the issue and the declared link.
+    links: Vec<(String, String, TaskLinkInfo)>,
+
+    /// The comments: the local id, the key of the issue and the text.
+    comments: Vec<(String, String, String)>,
+}
+
+impl Deferred {
+    /// Defers the links and comments declared on an entry.
+    fn extend(&mut self, path: &str, key: &str, links: &[TaskLinkInfo], comments: &[String]) {
+        self.links.extend(
+            links
+                .iter()
+                .enumerate()
+                .map(|(index, link)| (format!("{}.links[{}]", path, index), key.to_string(), link.clone())),
+        );
+        self.comments.extend(
+            comments
+                .iter()
+                .enumerate()
+                .map(|(index, text)| (format!("{}.comments[{}]", path, index), key.to_string(), text.clone())),
+        );
+    }
+}
+
+/// Plans the operations of a task to be created.
+///
+/// # Arguments
+///
+/// * `operations` - The operations planned so far.
+/// * `deferred` - The links and comments to plan once every task exists.
+/// * `path` - The JSON path of the task.
+/// * `task` - The task to plan.
+/// * `parent` - The key placeholder and the queue of the task it is nested in, for subtasks.
+/// * `ids` - The key placeholders of the created tasks, by their local `id`.
+///
+/// # Returns
+///
+/// The queue the task is created in.
+fn plan_created(
+    operations: &mut Vec<PlannedOperation>,
+    deferred: &mut Deferred,
+    path: &str,
+    task: &CreatedTaskInfo,
+    parent: Option<(String, String)>,
+    ids: &HashMap<String, String>,
+) -> String {
+    // The actual `unique` is generated from the run, which does not exist yet.
+    let task = task.with_unique(&run_unique("<run-id>", path));
+    let task = match parent {
+        Some((parent, queue)) => task.set(parent, queue),
+        None => CreatedTaskInfo {
+            parent: task.parent.as_deref().map(|parent| with_placeholders(parent, ids)),
+            ..task
+        },
+    };
+
+    let mut body = CreatedTaskBody::from(task.clone());
+    body.attachment_ids
+        .extend(plan_uploads(operations, path, &task.attachments));
+    body.description_attachment_ids
+        .extend(plan_uploads(operations, path, &task.description_attachments));
+    operations.push(PlannedOperation::new(
+        OperationKind::CreateIssue,
+        path,
+        Method::POST,
+        "/issues".to_string(),
+        Some(body),
+    ));
+
+    let key = placeholder(path);
+    plan_checklist(operations, path, &key, &task.checklist);
+    deferred.extend(path, &key, &task.links, &task.comments);
+    task.queue
+}
+
+/// Plans the operations of a task to be updated.
+fn plan_updated(
+    operations: &mut Vec<PlannedOperation>,
+    deferred: &mut Deferred,
+    path: &str,
+    task: &UpdatedTaskInfo,
+    ids: &HashMap<String, String>,
+) {
+    let task = UpdatedTaskInfo {
+        issue_id: with_placeholders(&task.issue_id, ids),
+        parent: task.parent.as_deref().map(|parent| with_placeholders(parent, ids)),
+        epic: task.epic.as_deref().map(|epic| with_placeholders(epic, ids)),
+        ..task.clone()
+    };
+
+    if task.has_field_changes() {
+        let mut body = UpdatedTask::from(task.clone());
+        body.attachment_ids
+            .extend(plan_uploads(operations, path, &task.attachments));
+        body.description_attachment_ids
+            .extend(plan_uploads(operations, path, &task.description_attachments));
+        let mut operation = PlannedOperation::new(
+            OperationKind::UpdateIssue,
+            path,
+            Method::PATCH,
+            format!("/issues/{}", task.issue_id),
+            Some(body),
+        );
+        operation.version = task.version;
+        operations.push(operation);
+    }
+
+    plan_checklist(operations, path, &task.issue_id, &task.checklist);
+    deferred.extend(path, &task.issue_id, &task.links, &task.comments);
+}
+
+/// Plans the upload of local files as temporary attachments.
+///
+/// # Returns
+///
+/// The placeholders of the IDs of the uploaded attachments.
+fn plan_uploads(operations: &mut Vec<PlannedOperation>, path: &str, files: &[impl AsRef<Path>]) -> Vec<String> {
+    files
+        .iter()
+        .map(|file| {
+            let file = file.as_ref().display().to_string();
+            operations.push(PlannedOperation::new(
+                OperationKind::UploadAttachment,
+                path,
+                Method::POST,
+                "/attachments".to_string(),
+                Some(serde_json::json!({ "file": file })),
+            ));
+            format!("<attachment:{}>", file)
+        })
+        .collect()
+}
+
+/// Plans the addition of the checklist items of a task.
+fn plan_checklist(operations: &mut Vec<PlannedOperation>, path: &str, issue: &str, checklist: &[ChecklistItemInfo]) {
+    for (index, item) in checklist.iter().enumerate() {
+        operations.push(PlannedOperation::new(
+            OperationKind::AddChecklistItem,
+            &format!("{}.checklist[{}]", path, index),
+            Method::POST,
+            format!("/issues/{}/checklistItems", issue),
+            Some(CreatedChecklistItemBody::from(item.clone())),
+        ));
+    }
+}
+
+/// Returns the placeholder of the key of the task created from a batch entry.
+fn placeholder(path: &str) -> String {
+    format!("<{}>", path)
+}
+
+/// Checks if a value is the key placeholder of a task created by the batch, see `placeholder`.
+fn is_placeholder(value: &str) -> bool {
+    value.starts_with('<') && value.ends_with('>')
+}
+
+/// Replaces the `${id}` references of a value with the key placeholders of the tasks they refer to.
+fn with_placeholders(value: &str, ids: &HashMap<String, String>) -> String {
+    resolve_references(value, ids).unwrap_or_else(|_| value.to_string())
+}
+
+/// Returns the version of an issue as returned by the API.
+pub(crate) fn issue_version(issue: &Value) -> Option<u32> {
+    issue.get("version").and_then(Value::as_u64).and_then(|version| u32::try_from(version).ok())
+}
+
+/// Reduces a field of an issue to the value used in request bodies.
+///
+/// References such as `{"key": "bug", "display": "Bug"}` are reduced to their key,
+/// or to their display name or ID if they have no key.
+pub(crate) fn plain_value(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => ["key", "display", "id"]
+            .iter()
+            .find_map(|field| object.get(*field).cloned())
+            .unwrap_or_else(|| value.clone()),
+        Value::Array(values) => Value::Array(values.iter().map(plain_value).collect()),
+        _ => value.clone(),
+    }
+}
+
+/// Checks if a body value leaves the field of the issue untouched.
+pub(crate) fn is_empty_value(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(values) => values.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_from_batch() {
+        let task_batch: TaskBatch = serde_json::from_str(
+            r#"{
+                "created": [
+                    {
+                        "queue": "TEST",
+                        "summary": "Epic",
+                        "unique": "epic",
+                        "checklist": [{ "text": "Review" }],
+                        "subtasks": [{ "queue": "OTHER", "summary": "Write tests", "subtasks": [] }]
+                    }
+                ],
+                "updated": [
+                    {
+                        "issue_id": "TEST-1",
+                        "summary": "Renamed",
+                        "followers": [],
+                        "links": [{ "relationship": "has epic", "task": "epic" }]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let plan = TaskBatchPlan::from_batch(&task_batch);
+        let operations: Vec<(OperationKind, &str, &str)> = plan
+            .operations
+            .iter()
+            .map(|operation| (operation.kind, operation.method.as_str(), operation.endpoint.as_str()))
+            .collect();
+
+        assert_eq!(
+            operations,
+            vec![
+                (OperationKind::CreateIssue, "POST", "/issues"),
+                (OperationKind::AddChecklistItem, "POST", "/issues/<$.created[0]>/checklistItems"),
+                (OperationKind::CreateIssue, "POST", "/issues"),
+                (OperationKind::UpdateIssue, "PATCH", "/issues/TEST-1"),
+                (OperationKind::CreateLink, "POST", "/issues/TEST-1/links"),
+            ]
+        );
+
+        let subtask = plan.operations[2].body.as_ref().unwrap();
+        assert_eq!(subtask["parent"], "<$.created[0]>");
+        assert_eq!(subtask["queue"], "TEST");
+        assert_eq!(plan.operations[4].body.as_ref().unwrap()["issue"], "<$.created[0]>");
+        assert_eq!(plan.operations[0].body.as_ref().unwrap()["unique"], "epic");
+        assert!(subtask["unique"].as_str().unwrap().starts_with("batch-"));
+    }
+
+    #[test]
+    fn test_plan_references() {
+        let task_batch: TaskBatch = serde_json::from_str(
+            r#"{
+                "created": [
+                    { "queue": "TEST", "summary": "Story", "parent": "${epic}", "subtasks": [] },
+                    { "queue": "TEST", "summary": "Epic", "id": "epic", "comments": ["Stories: ${story}"], "subtasks": [] },
+                    { "queue": "TEST", "summary": "Story", "id": "story", "subtasks": [] }
+                ],
+                "updated": [{ "issue_id": "${epic}", "summary": "Renamed", "followers": [] }]
+            }"#,
+        )
+        .unwrap();
+
+        let plan = TaskBatchPlan::from_batch(&task_batch);
+        let operations: Vec<(&str, &str)> = plan
+            .operations
+            .iter()
+            .map(|operation| (operation.entry.as_str(), operation.endpoint.as_str()))
+            .collect();
+
+        assert_eq!(
+            operations,
+            vec![
+                ("$.created[1]", "/issues"),
+                ("$.created[0]", "/issues"),
+                ("$.created[2]", "/issues"),
+                ("$.updated[0]", "/issues/<$.created[1]>"),
+                ("$.created[1].comments[0]", "/issues/<$.created[1]>/comments"),
+            ]
+        );
+        assert_eq!(plan.operations[1].body.as_ref().unwrap()["parent"], "<$.created[1]>");
+        assert_eq!(plan.operations[4].body.as_ref().unwrap()["text"], "Stories: <$.created[2]>");
+    }
+
+    #[tokio::test]
+    async fn test_planner_update_of_created_task() {
+        let task_batch: TaskBatch = serde_json::from_str(
+            r#"{
+                "created": [{ "queue": "TEST", "summary": "Epic", "id": "epic", "subtasks": [] }],
+                "updated": [{ "issue_id": "${epic}", "summary": "Renamed", "followers": [] }]
+            }"#,
+        )
+        .unwrap();
+        // The cache holds no queue, and any request to the tracker would fail.
+        let cache = MetadataCache::new(TaskApiClient::new(String::new(), String::new()).with_base_url("http://127.0.0.1:9"))
+            .with_queue("TEST", None, Vec::new(), Vec::new(), Vec::new());
+
+        let plan = TaskBatchPlanner::new(&cache).plan(&task_batch).await.unwrap();
+
+        let update = &plan.operations[1];
+        assert_eq!(update.endpoint, "/issues/<$.created[0]>");
+        assert_eq!(update.changes.len(), 1);
+        assert_eq!(update.changes[0].field, "summary");
+        assert_eq!(update.changes[0].current, Value::Null);
+    }
+
+    #[test]
+    fn test_plain_value() {
+        let current = serde_json::json!({
+            "type": { "id": "1", "key": "bug", "display": "Bug" },
+            "followers": [{ "id": "42", "display": "Jane Doe" }]
+        });
+
+        assert_eq!(plain_value(&current["type"]), "bug");
+        assert_eq!(plain_value(&current["followers"]), serde_json::json!(["Jane Doe"]));
+    }
+}
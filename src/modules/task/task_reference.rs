@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use super::task_api_client::HandleResponseError;
+
+/// Checks if a character may be part of the local `id` of a batch entry.
+fn is_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// Checks if a local `id` can be referred to: it is not empty and only holds letters, digits, `_`, `-` and `.`.
+pub fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(is_id_char)
+}
+
+/// Finds the references to created entries of a batch in a value, written `${id}`.
+///
+/// A value may be a reference on its own, e.g. a `parent` of `${epic}`, or contain references
+/// in its text, e.g. a comment `Follow-up of ${epic}`. Text that only looks like the start of
+/// a reference, such as `${` followed by spaces, is left alone.
+///
+/// # Arguments
+///
+/// * `value` - The value to search.
+///
+/// # Returns
+///
+/// The referenced ids, in order of appearance.
+pub fn references(value: &str) -> Vec<&str> {
+    let mut ids = Vec::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        rest = &rest[start + 2..];
+        match rest.find('}') {
+            Some(end) if is_valid_id(&rest[..end]) => {
+                ids.push(&rest[..end]);
+                rest = &rest[end + 1..];
+            }
+            _ => {}
+        }
+    }
+    ids
+}
+
+/// Replaces the references in a value with the keys of the issues they refer to.
+///
+/// # Arguments
+///
+/// * `value` - A key, or a text holding references.
+/// * `keys` - The keys of the created tasks, by their local `id`.
+///
+/// # Returns
+///
+/// A `Result` containing the resolved value, or `HandleResponseError::UnresolvedReference`
+/// with the first id that has no key yet.
+pub fn resolve_references(value: &str, keys: &HashMap<String, String>) -> Result<String, HandleResponseError> {
+    let mut resolved = value.to_string();
+    for id in references(value) {
+        let key = keys
+            .get(id)
+            .ok_or_else(|| HandleResponseError::UnresolvedReference(id.to_string()))?;
+        resolved = resolved.replacen(&format!("${{{}}}", id), key, 1);
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_references() {
+        assert_eq!(references("${epic}"), vec!["epic"]);
+        assert_eq!(references("Blocks ${api-v2} and ${db_1}."), vec!["api-v2", "db_1"]);
+        assert!(references("TEST-1").is_empty());
+        assert!(references("costs ${ 5 } or ${}").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_references() {
+        let keys = HashMap::from([("epic".to_string(), "TEST-7".to_string())]);
+
+        assert_eq!(resolve_references("${epic}", &keys).unwrap(), "TEST-7");
+        assert_eq!(resolve_references("See ${epic}", &keys).unwrap(), "See TEST-7");
+        assert_eq!(resolve_references("TEST-1", &keys).unwrap(), "TEST-1");
+        assert!(matches!(
+            resolve_references("${story}", &keys),
+            Err(HandleResponseError::UnresolvedReference(id)) if id == "story"
+        ));
+    }
+}
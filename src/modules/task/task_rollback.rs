@@ -0,0 +1,443 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tokio::time::{sleep, Duration};
+
+use crate::modules::queue::Reference;
+
+use super::task_api_client::{HandleResponseError, TaskApiClient};
+use super::task_journal::{recorded_unique, JournalAction, JournalEntry, JournalStatus, TaskJournal};
+use super::task_plan::is_empty_value;
+use super::UpdatedTask;
+
+/// The resolution set on the issues closed by a rollback.
+pub const ROLLBACK_RESOLUTION: &str = "wontFix";
+
+/// The fields of an update that cannot be restored by another update.
+const UNRESTORABLE_FIELDS: [&str; 2] = ["attachmentIds", "descriptionAttachmentIds"];
+
+/// Represents a transition of the workflow of an issue.
+///
+/// # Fields
+///
+/// * `id` - The identifier of the transition, e.g. `close`.
+/// * `to` - The status the transition leads to.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub id: String,
+    pub to: Reference,
+}
+
+/// Represents an operation of a run to undo.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RollbackStep {
+    /// Closes an issue created by the run, or moves it to the trash queue.
+    Discard { entry: String, key: String },
+
+    /// Restores the fields of an issue updated by the run to their values before the update.
+    Restore { entry: String, key: String, original: Value },
+}
+
+impl RollbackStep {
+    /// Returns the local id of the batch entry the step undoes.
+    pub fn entry(&self) -> &str {
+        match self {
+            RollbackStep::Discard { entry, .. } | RollbackStep::Restore { entry, .. } => entry,
+        }
+    }
+
+    /// Returns the key of the issue the step applies to.
+    pub fn key(&self) -> &str {
+        match self {
+            RollbackStep::Discard { key, .. } | RollbackStep::Restore { key, .. } => key,
+        }
+    }
+}
+
+impl fmt::Display for RollbackStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RollbackStep::Discard { entry, key } => write!(f, "discard {} ({})", key, entry),
+            RollbackStep::Restore { entry, key, original } => {
+                let fields: Vec<&str> = original
+                    .as_object()
+                    .map(|original| original.keys().map(String::as_str).collect())
+                    .unwrap_or_default();
+                write!(f, "restore {} {} ({})", key, fields.join(", "), entry)
+            }
+        }
+    }
+}
+
+/// Undoes the operations of a run recorded in its journal.
+pub struct TaskRollback {
+    /// The API client used for interacting with the Yandex Tracker.
+    api_client: TaskApiClient,
+
+    /// The queue the created issues are moved to instead of being closed (optional).
+    trash_queue: Option<String>,
+}
+
+impl TaskRollback {
+    /// Creates a new `TaskRollback` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_client` - The API client used for task operations.
+    /// * `trash_queue` - The queue the created issues are moved to, or `None` to close them.
+    pub fn new(api_client: TaskApiClient, trash_queue: Option<String>) -> Self {
+        TaskRollback { api_client, trash_queue }
+    }
+
+    /// Lists the operations of a run that remain to be undone, latest first.
+    ///
+    /// Issues the run created are discarded, and so are issues found already existing under a
+    /// `unique` the run generated: only the run itself, e.g. right before a crash, can have created
+    /// them. Issues found under a declared `unique` are left alone, since an earlier run may have
+    /// created them. Issues the run updated, or tried to update,
+    /// are restored from the first snapshot taken of them. Steps a previous rollback completed
+    /// are left out, so an interrupted rollback can be run again.
+    ///
+    /// # Arguments
+    ///
+    /// * `journal` - The entries of the journal of the run.
+    pub fn steps(journal: &[JournalEntry]) -> Vec<RollbackStep> {
+        let mut snapshots: HashSet<&str> = HashSet::new();
+        let mut rolled_back: HashMap<&str, bool> = HashMap::new();
+        let mut steps = Vec::new();
+
+        for journal_entry in journal {
+            let entry = journal_entry.entry.as_str();
+            match (journal_entry.action, journal_entry.status, &journal_entry.key) {
+                (JournalAction::Create, JournalStatus::Ok, Some(key)) => steps.push(RollbackStep::Discard {
+                    entry: entry.to_string(),
+                    key: key.clone(),
+                }),
+                (JournalAction::Create, JournalStatus::Existing, Some(key))
+                    if recorded_unique(journal, entry).is_some() =>
+                {
+                    steps.push(RollbackStep::Discard {
+                        entry: entry.to_string(),
+                        key: key.clone(),
+                    })
+                }
+                (JournalAction::Snapshot, _, Some(key)) if snapshots.insert(entry) => {
+                    steps.push(RollbackStep::Restore {
+                        entry: entry.to_string(),
+                        key: key.clone(),
+                        original: journal_entry.original.clone().unwrap_or_default(),
+                    })
+                }
+                (JournalAction::Rollback, status, _) => {
+                    rolled_back.insert(entry, status != JournalStatus::Failed);
+                }
+                _ => {}
+            }
+        }
+
+        steps.retain(|step| !rolled_back.get(step.entry()).copied().unwrap_or_default());
+        steps.reverse();
+        steps
+    }
+
+    /// Undoes the operations of a run, recording every step in its journal.
+    ///
+    /// Every step is attempted even if an earlier one fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `journal` - The journal of the run.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result`, or the error of the first failed step.
+    pub async fn rollback(&self, journal: &mut TaskJournal) -> Result<(), HandleResponseError> {
+        let duration = Duration::from_secs(1);
+        let mut first_error = None;
+
+        for step in TaskRollback::steps(journal.entries()) {
+            let result = match &step {
+                RollbackStep::Discard { key, .. } => self.discard(key).await,
+                RollbackStep::Restore { key, original, .. } => {
+                    self.api_client.patch_task_fields(key, original).await
+                }
+            };
+
+            match &result {
+                Ok(()) => journal.record(step.entry(), JournalAction::Rollback, Ok(step.key()))?,
+                Err(err) => journal.record(step.entry(), JournalAction::Rollback, Err(err))?,
+            }
+            if let Err(err) = result {
+                first_error.get_or_insert(err);
+            }
+
+            sleep(duration).await;
+        }
+
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Closes an issue created by the run, or moves it to the trash queue.
+    async fn discard(&self, key: &str) -> Result<(), HandleResponseError> {
+        if let Some(queue) = &self.trash_queue {
+            return self.api_client.move_task(key, queue).await;
+        }
+
+        let transition = self
+            .api_client
+            .get_transitions(key)
+            .await?
+            .into_iter()
+            .find(|transition| transition.to.key.as_deref() == Some("closed") || transition.id == "close")
+            .ok_or_else(|| HandleResponseError::NoCloseTransition(key.to_string()))?;
+        self.api_client
+            .execute_transition(key, &transition.id, Some(ROLLBACK_RESOLUTION))
+            .await
+    }
+}
+
+impl UpdatedTask {
+    /// Reads the values an update is about to overwrite.
+    ///
+    /// Attachments cannot be restored and are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The issue as returned by the API before the update.
+    ///
+    /// # Returns
+    ///
+    /// A JSON object mapping every field the update sets to its current value, in the form
+    /// accepted by an update: references are reduced to their key or ID, and missing fields are `null`.
+    pub fn original_fields(&self, current: &Value) -> Value {
+        let body = match serde_json::to_value(self) {
+            Ok(Value::Object(body)) => body,
+            _ => return Value::Object(Map::new()),
+        };
+
+        body.into_iter()
+            .filter(|(field, value)| !UNRESTORABLE_FIELDS.contains(&field.as_str()) && !is_empty_value(value))
+            .map(|(field, _)| {
+                let original = current.get(&field).map(restorable_value).unwrap_or(Value::Null);
+                (field, original)
+            })
+            .collect::<Map<String, Value>>()
+            .into()
+    }
+}
+
+impl TaskApiClient {
+    /// Sets fields of an issue to raw JSON values.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `fields` - The values by field name; `null` clears a field.
+    pub async fn patch_task_fields(&self, issue_id: &str, fields: &Value) -> Result<(), HandleResponseError> {
+        let response = self
+            .request(Method::PATCH, &format!("/issues/{}", issue_id))
+            .json(fields)
+            .send()
+            .await?;
+
+        TaskApiClient::handle_empty_response(response).await
+    }
+
+    /// Retrieves the transitions available from the current status of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    pub async fn get_transitions(&self, issue_id: &str) -> Result<Vec<Transition>, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/issues/{}/transitions", issue_id))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Moves an issue to another status.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `transition_id` - The identifier of the transition.
+    /// * `resolution` - The key of the resolution to set, e.g. `wontFix` (optional).
+    pub async fn execute_transition(
+        &self,
+        issue_id: &str,
+        transition_id: &str,
+        resolution: Option<&str>,
+    ) -> Result<(), HandleResponseError> {
+        let body = match resolution {
+            Some(resolution) => json!({ "resolution": resolution }),
+            None => json!({}),
+        };
+        let response = self
+            .request(
+                Method::POST,
+                &format!("/issues/{}/transitions/{}/_execute", issue_id, transition_id),
+            )
+            .json(&body)
+            .send()
+            .await?;
+
+        TaskApiClient::handle_empty_response(response).await
+    }
+
+    /// Moves an issue to another queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `queue` - The key of the target queue.
+    pub async fn move_task(&self, issue_id: &str, queue: &str) -> Result<(), HandleResponseError> {
+        let response = self
+            .request(Method::POST, &format!("/issues/{}/_move", issue_id))
+            .query(&[("queue", queue)])
+            .send()
+            .await?;
+
+        TaskApiClient::handle_empty_response(response).await
+    }
+}
+
+/// Reduces a field of an issue to a value an update accepts.
+///
+/// References are reduced to their key, or to their ID if they have no key,
+/// e.g. `{"id": "1234", "display": "Jane Doe"}` becomes `"1234"`.
+pub(crate) fn restorable_value(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => ["key", "id"]
+            .iter()
+            .find_map(|field| object.get(*field).cloned())
+            .unwrap_or_else(|| value.clone()),
+        Value::Array(values) => Value::Array(values.iter().map(restorable_value).collect()),
+        _ => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::task::task_fields::IssueFields;
+    use crate::modules::task::task_list_update::ListUpdate;
+    use chrono::Local;
+
+    fn journal_entry(entry: &str, action: JournalAction, key: &str, original: Option<Value>) -> JournalEntry {
+        JournalEntry {
+            entry: entry.to_string(),
+            action,
+            key: Some(key.to_string()),
+            status: JournalStatus::Ok,
+            error: None,
+            original,
+            changes: Vec::new(),
+            unique: None,
+            at: Local::now().fixed_offset(),
+        }
+    }
+
+    #[test]
+    fn test_rollback_steps() {
+        let journal = vec![
+            journal_entry("$.created[0]", JournalAction::Create, "TEST-2", None),
+            journal_entry("$.created[1]", JournalAction::Create, "TEST-3", None),
+            journal_entry("$.updated[0]", JournalAction::Snapshot, "TEST-1", Some(json!({ "summary": "Old" }))),
+            journal_entry("$.updated[0]", JournalAction::Snapshot, "TEST-1", Some(json!({ "summary": "New" }))),
+            journal_entry("$.updated[0]", JournalAction::Update, "TEST-1", None),
+            journal_entry("$.created[1]", JournalAction::Rollback, "TEST-3", None),
+        ];
+
+        let steps = TaskRollback::steps(&journal);
+
+        assert_eq!(
+            steps,
+            vec![
+                RollbackStep::Restore {
+                    entry: "$.updated[0]".to_string(),
+                    key: "TEST-1".to_string(),
+                    original: json!({ "summary": "Old" }),
+                },
+                RollbackStep::Discard {
+                    entry: "$.created[0]".to_string(),
+                    key: "TEST-2".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rollback_steps_of_existing_tasks() {
+        let journal = vec![
+            JournalEntry {
+                unique: Some("batch-run-1-epic".to_string()),
+                ..journal_entry("$.created[0]", JournalAction::Unique, "", None)
+            },
+            JournalEntry {
+                status: JournalStatus::Existing,
+                ..journal_entry("$.created[0]", JournalAction::Create, "TEST-2", None)
+            },
+            JournalEntry {
+                status: JournalStatus::Existing,
+                ..journal_entry("$.created[1]", JournalAction::Create, "TEST-3", None)
+            },
+        ];
+
+        assert_eq!(
+            TaskRollback::steps(&journal),
+            vec![RollbackStep::Discard {
+                entry: "$.created[0]".to_string(),
+                key: "TEST-2".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_original_fields() {
+        let update_task = UpdatedTask {
+            issue_id: "TEST-1".to_string(),
+            summary: Some("Renamed".to_string()),
+            parent: None,
+            epic: None,
+            description: Some("New description".to_string()),
+            sprint: ListUpdate::default(),
+            task_type: None,
+            priority: Some("critical".to_string()),
+            followers: ListUpdate {
+                add: vec!["jane".to_string()],
+                ..ListUpdate::default()
+            },
+            tags: ListUpdate::default(),
+            components: ListUpdate::default(),
+            affected_versions: ListUpdate::default(),
+            fix_versions: ListUpdate::default(),
+            attachment_ids: vec!["1".to_string()],
+            description_attachment_ids: Vec::new(),
+            version: None,
+            fields: IssueFields::default(),
+        };
+        let current = json!({
+            "summary": "Original",
+            "priority": { "key": "normal", "display": "Normal" },
+            "followers": [{ "id": "1234", "display": "John Doe" }]
+        });
+
+        assert_eq!(
+            update_task.original_fields(&current),
+            json!({
+                "summary": "Original",
+                "description": null,
+                "priority": "normal",
+                "followers": ["1234"]
+            })
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::task_batch::TaskBatch;
+use super::task_journal::{JournalAction, JournalEntry, JournalStatus, TaskJournal, BATCH_FILE, JOURNAL_FILE};
+use super::task_rollback::TaskRollback;
+use super::CreatedTaskInfo;
+
+/// The name of the report file of a run.
+pub const REPORT_FILE: &str = "report.json";
+
+/// Represents the outcome of a batch entry at the end of a run.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    /// Every operation of the entry succeeded.
+    Done,
+
+    /// The update of the entry would not change the issue and was not sent.
+    Unchanged,
+
+    /// An operation of the entry failed.
+    Failed,
+
+    /// The run stopped before the entry was processed.
+    Pending,
+}
+
+impl fmt::Display for ReportStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportStatus::Done => write!(f, "done"),
+            ReportStatus::Unchanged => write!(f, "unchanged"),
+            ReportStatus::Failed => write!(f, "failed"),
+            ReportStatus::Pending => write!(f, "pending"),
+        }
+    }
+}
+
+/// Represents a batch entry in the report of a run.
+///
+/// # Fields
+///
+/// * `entry` - The local id of the entry: its JSON path in the input file.
+/// * `action` - The main operation of the entry, `create` or `update`.
+/// * `summary` - The summary of a created task, or the summary set by an update (optional).
+/// * `key` - The key of the issue in Yandex Tracker (optional).
+/// * `status` - The outcome of the entry.
+/// * `error` - The first error of the entry (optional).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReportEntry {
+    pub entry: String,
+    pub action: JournalAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub status: ReportStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Represents the final report of a run, mapping every input entry to its Tracker key.
+///
+/// # Fields
+///
+/// * `run_id` - The identifier of the run.
+/// * `entries` - Every created and updated entry of the input, in file order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RunReport {
+    pub run_id: String,
+    pub entries: Vec<ReportEntry>,
+}
+
+impl RunReport {
+    /// Builds the report of a run from its input and its journal.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - The identifier of the run.
+    /// * `task_batch` - The input of the run.
+    /// * `journal` - The entries of the journal of the run.
+    pub fn new(run_id: &str, task_batch: &TaskBatch, journal: &[JournalEntry]) -> Self {
+        let mut entries = Vec::new();
+
+        let mut stack: Vec<(String, &CreatedTaskInfo)> = task_batch
+            .created
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, task)| (format!("$.created[{}]", index), task))
+            .collect();
+        while let Some((path, task)) = stack.pop() {
+            entries.push(report_entry(&path, JournalAction::Create, Some(&task.summary), None, journal));
+            stack.extend(
+                task.subtasks
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, subtask)| (format!("{}.subtasks[{}]", path, index), subtask)),
+            );
+        }
+
+        for (index, task) in task_batch.updated.iter().enumerate() {
+            entries.push(report_entry(
+                &format!("$.updated[{}]", index),
+                JournalAction::Update,
+                task.summary.as_deref(),
+                Some(&task.issue_id),
+                journal,
+            ));
+        }
+
+        RunReport {
+            run_id: run_id.to_string(),
+            entries,
+        }
+    }
+
+    /// Checks if every entry of the input was processed successfully.
+    pub fn is_done(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|entry| matches!(entry.status, ReportStatus::Done | ReportStatus::Unchanged))
+    }
+
+    /// Saves the report as `report.json` in the directory of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory of the run.
+    pub fn save(&self, dir: &Path) -> Result<(), io::Error> {
+        let report_json = serde_json::to_string_pretty(self)?;
+        fs::write(dir.join(REPORT_FILE), report_json)?;
+        Ok(())
+    }
+}
+
+/// Finds the latest run of a batch file that stopped before every entry was done.
+///
+/// Tasks without a declared `unique` get a new one in every run, so running such a batch again
+/// instead of resuming it would create the tasks of the unfinished run a second time. A run is
+/// unfinished when it has no report, e.g. it crashed, or its report lists failed or pending
+/// entries, unless it left nothing to undo, e.g. it failed right away or was rolled back.
+///
+/// # Arguments
+///
+/// * `runs_dir` - The directory holding the runs, usually `RUNS_DIR`.
+/// * `file` - The path of the batch file.
+///
+/// # Returns
+///
+/// A `Result` containing the identifier of the run, `None` if every run of the file finished, or `io::Error`.
+pub fn unfinished_run(runs_dir: &Path, file: &Path) -> Result<Option<String>, io::Error> {
+    if !runs_dir.is_dir() {
+        return Ok(None);
+    }
+    let file = fs::canonicalize(file)?;
+
+    let mut run_ids = Vec::new();
+    for dir_entry in fs::read_dir(runs_dir)? {
+        let dir = dir_entry?.path();
+        let is_same_batch = fs::read_to_string(dir.join(BATCH_FILE)).is_ok_and(|batch| Path::new(&batch) == file);
+        let journal_path = dir.join(JOURNAL_FILE);
+        if !is_same_batch || !journal_path.exists() {
+            continue;
+        }
+
+        let is_done = fs::read_to_string(dir.join(REPORT_FILE))
+            .ok()
+            .and_then(|report_json| serde_json::from_str::<RunReport>(&report_json).ok())
+            .is_some_and(|report| report.is_done());
+        if !is_done && !TaskRollback::steps(&TaskJournal::load(&journal_path)?).is_empty() {
+            run_ids.push(dir.file_name().unwrap_or_default().to_string_lossy().into_owned());
+        }
+    }
+    // Run identifiers start with their timestamp, so they sort chronologically.
+    Ok(run_ids.into_iter().max())
+}
+
+impl fmt::Display for RunReport {
+    /// Formats the report as a table with one row per entry, followed by the errors.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .entries
+            .iter()
+            .map(|entry| entry.entry.len())
+            .chain(std::iter::once("ENTRY".len()))
+            .max()
+            .unwrap_or_default();
+
+        writeln!(f, "Run {}", self.run_id)?;
+        writeln!(f, "{:<width$}  {:<7}  {:<12}  {:<7}  SUMMARY", "ENTRY", "ACTION", "KEY", "STATUS")?;
+        for entry in &self.entries {
+            let action = match entry.action {
+                JournalAction::Create => "create",
+                _ => "update",
+            };
+            writeln!(
+                f,
+                "{:<width$}  {:<7}  {:<12}  {:<7}  {}",
+                entry.entry,
+                action,
+                entry.key.as_deref().unwrap_or("-"),
+                entry.status,
+                entry.summary.as_deref().unwrap_or(""),
+            )?;
+        }
+
+        for entry in &self.entries {
+            if let Some(error) = &entry.error {
+                writeln!(f, "\n{}: {}", entry.entry, error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the report entry of a batch entry from the journal.
+///
+/// The operations of an entry are the journal entries whose local id is the entry itself,
+/// or one of its checklist items, links or comments. Only the latest outcome of every operation counts,
+/// so a failure fixed by a resumed run is not reported. Snapshots, rollbacks and generated uniques are not
+/// operations of the run.
+fn report_entry(
+    path: &str,
+    action: JournalAction,
+    summary: Option<&str>,
+    issue: Option<&str>,
+    journal: &[JournalEntry],
+) -> ReportEntry {
+    let mut operations: Vec<&JournalEntry> = Vec::new();
+    for journal_entry in journal {
+        let is_operation = !matches!(
+            journal_entry.action,
+            JournalAction::Snapshot | JournalAction::Rollback | JournalAction::Unique
+        );
+        let belongs = journal_entry.entry == path
+            || journal_entry
+                .entry
+                .strip_prefix(path)
+                .is_some_and(|rest| {
+                    rest.starts_with(".links[") || rest.starts_with(".checklist") || rest.starts_with(".comments[")
+                });
+        if is_operation && belongs {
+            operations.retain(|operation| {
+                operation.entry != journal_entry.entry || operation.action != journal_entry.action
+            });
+            operations.push(journal_entry);
+        }
+    }
+
+    let failed = operations
+        .iter()
+        .find(|operation| operation.status == JournalStatus::Failed);
+    let main = operations
+        .iter()
+        .find(|operation| operation.entry == path && operation.action == action);
+    let key = main
+        .and_then(|operation| operation.key.clone())
+        .or_else(|| issue.map(str::to_string));
+
+    ReportEntry {
+        entry: path.to_string(),
+        action,
+        summary: summary.map(str::to_string),
+        key,
+        status: match (failed, operations.is_empty()) {
+            (Some(_), _) => ReportStatus::Failed,
+            (None, true) => ReportStatus::Pending,
+            (None, false) if main.is_some_and(|operation| operation.status == JournalStatus::Unchanged) => {
+                ReportStatus::Unchanged
+            }
+            (None, false) => ReportStatus::Done,
+        },
+        error: failed.and_then(|operation| operation.error.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    fn journal_entry(entry: &str, action: JournalAction, key: Option<&str>, error: Option<&str>) -> JournalEntry {
+        JournalEntry {
+            entry: entry.to_string(),
+            action,
+            key: key.map(str::to_string),
+            status: if error.is_some() { JournalStatus::Failed } else { JournalStatus::Ok },
+            error: error.map(str::to_string),
+            original: None,
+            changes: Vec::new(),
+            unique: None,
+            at: Local::now().fixed_offset(),
+        }
+    }
+
+    #[test]
+    fn test_run_report() {
+        let task_batch: TaskBatch = serde_json::from_str(
+            r#"{
+                "created": [
+                    {
+                        "queue": "TEST",
+                        "summary": "Epic",
+                        "subtasks": [{ "queue": "TEST", "summary": "Write tests", "subtasks": [] }]
+                    },
+                    { "queue": "TEST", "summary": "Release", "subtasks": [] }
+                ],
+                "updated": [{ "issue_id": "TEST-1", "summary": "Renamed", "followers": [] }]
+            }"#,
+        )
+        .unwrap();
+        let journal = vec![
+            journal_entry("$.created[0]", JournalAction::Create, Some("TEST-2"), None),
+            journal_entry("$.created[0].subtasks[0]", JournalAction::Create, Some("TEST-3"), None),
+            journal_entry("$.created[1]", JournalAction::Create, Some("TEST-4"), None),
+            journal_entry("$.created[1].checklist[0]", JournalAction::AddChecklist, None, Some("API error")),
+        ];
+
+        let report = RunReport::new("run", &task_batch, &journal);
+        let rows: Vec<(&str, Option<&str>, ReportStatus)> = report
+            .entries
+            .iter()
+            .map(|entry| (entry.entry.as_str(), entry.key.as_deref(), entry.status))
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                ("$.created[0]", Some("TEST-2"), ReportStatus::Done),
+                ("$.created[0].subtasks[0]", Some("TEST-3"), ReportStatus::Done),
+                ("$.created[1]", Some("TEST-4"), ReportStatus::Failed),
+                ("$.updated[0]", Some("TEST-1"), ReportStatus::Pending),
+            ]
+        );
+        assert!(!report.is_done());
+    }
+
+    #[test]
+    fn test_unfinished_run() {
+        let dir = std::env::temp_dir().join(format!("report-test-{}", std::process::id()));
+        let runs_dir = dir.join("runs");
+        let file = dir.join("tasks.json");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&file, r#"{ "created": [{ "queue": "TEST", "summary": "Retro", "subtasks": [] }], "updated": [] }"#)
+            .unwrap();
+        let task_batch = TaskBatch::create_from_path(&file).unwrap();
+
+        let mut journal = TaskJournal::create(&runs_dir).unwrap();
+        journal.record_batch(&file).unwrap();
+        let before_create = unfinished_run(&runs_dir, &file).unwrap();
+        journal.record("$.created[0]", JournalAction::Create, Ok("TEST-2")).unwrap();
+        let crashed = unfinished_run(&runs_dir, &file).unwrap();
+        let other_file = unfinished_run(&runs_dir, &runs_dir).unwrap();
+        RunReport::new(journal.run_id(), &task_batch, journal.entries())
+            .save(journal.dir())
+            .unwrap();
+        let finished = unfinished_run(&runs_dir, &file).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(before_create, None);
+        assert_eq!(crashed.as_deref(), Some(journal.run_id()));
+        assert_eq!(other_file, None);
+        assert_eq!(finished, None);
+    }
+}
@@ -0,0 +1,741 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use super::task_api_client::{HandleResponseError, TaskApiClient};
+use super::task_batch_format::BatchFormat;
+use super::task_journal::{JournalAction, TaskJournal};
+use super::task_link::{CreatedLinkBody, IssueLink, TaskLinkInfo};
+use super::task_fields::IssueFields;
+use super::task_diff::{matches_current, write_changes, FieldChange};
+use super::task_plan::plain_value;
+use super::task_rollback::restorable_value;
+use super::{CreatedTaskBody, CreatedTaskInfo};
+
+/// Represents possible errors when reconciling a state file with the tracker.
+///
+/// # Variants
+///
+/// * `ReadError` - Indicates an error occurred while reading the state file.
+/// * `InvalidState` - Indicates that the state file is malformed or inconsistent.
+/// * `ApiError` - Indicates that the current state could not be read from the tracker.
+#[derive(Error, Debug)]
+pub enum SyncError {
+    /// Error reading the state file.
+    #[error("Error reading the state file: {0}")]
+    ReadError(#[from] std::io::Error),
+
+    /// The state file is malformed or inconsistent.
+    #[error("Invalid state file: {0}")]
+    InvalidState(String),
+
+    /// The current state could not be read.
+    #[error("Error reading the current state: {0}")]
+    ApiError(#[from] HandleResponseError),
+}
+
+/// Represents an issue as declared in a state file.
+///
+/// An issue is identified by its `key`, or by its `unique` if it has no key, in which case it is
+/// created when no issue has this `unique` yet. Fields left out are not managed: their current
+/// value is kept whatever it is. The queue of an existing issue is never changed.
+///
+/// # Fields
+///
+/// * `key` - The key of an existing issue (optional).
+/// * `unique` - The `unique` of the issue, created if it does not exist (optional).
+/// * `queue` - The queue the issue is created in, the default queue if `None`.
+/// * `summary` - A brief summary of the issue, required to create it (optional).
+/// * `description` - A detailed description of the issue (optional).
+/// * `parent` - The key of the parent issue, or the `unique` of another issue of the state (optional).
+/// * `task_type` - The type of the issue (optional).
+/// * `priority` - The priority of the issue (optional).
+/// * `assignee` - The user assigned to the issue (optional).
+/// * `followers` - The followers of the issue, an empty list removing all of them (optional).
+/// * `sprint` - The sprints of the issue (optional).
+/// * `status` - The status the issue is moved to, e.g. `inProgress` (optional).
+/// * `resolution` - The resolution set when the issue is moved to `status`, e.g. `fixed` (optional).
+/// * `links` - Links the issue must have; other links of the issue are left alone.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct DesiredIssue {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unique: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub task_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub followers: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sprint: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<TaskLinkInfo>,
+}
+
+impl DesiredIssue {
+    /// Returns the name the issue is referred to by in the state file and in plans: its key, or its `unique`.
+    pub fn label(&self) -> &str {
+        self.key.as_deref().or(self.unique.as_deref()).unwrap_or_default()
+    }
+
+    /// Returns the managed fields, by their name in request bodies.
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let texts = [
+            ("summary", &self.summary),
+            ("description", &self.description),
+            ("type", &self.task_type),
+            ("priority", &self.priority),
+            ("assignee", &self.assignee),
+        ];
+        let lists = [("followers", &self.followers), ("sprint", &self.sprint)];
+
+        texts
+            .into_iter()
+            .filter_map(|(field, value)| value.clone().map(|value| (field, Value::String(value))))
+            .chain(lists.into_iter().filter_map(|(field, values)| {
+                values
+                    .clone()
+                    .map(|values| (field, Value::Array(values.into_iter().map(Value::String).collect())))
+            }))
+            .collect()
+    }
+}
+
+/// Represents the desired state of a set of issues, read from a state file.
+///
+/// # Fields
+///
+/// * `issues` - The declared issues, parents before their subtasks.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SyncState {
+    pub issues: Vec<DesiredIssue>,
+}
+
+/// Represents an issue as it currently is in the tracker.
+///
+/// # Fields
+///
+/// * `key` - The key of the issue.
+/// * `fields` - The issue as returned by the API.
+/// * `links` - The links of the issue, read only if the state declares links on it.
+#[derive(Debug, Clone)]
+pub struct CurrentIssue {
+    pub key: String,
+    pub fields: Value,
+    pub links: Vec<IssueLink>,
+}
+
+/// Represents a difference between the state file and the tracker.
+///
+/// Issues are referred to by their label, see `DesiredIssue::label`, and so are parents and link
+/// targets that are issues of the state; labels are resolved to keys when the change is applied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncChange {
+    /// Creates an issue that does not exist yet.
+    Create { entry: String, task: Box<CreatedTaskInfo> },
+
+    /// Changes fields of an existing issue; `original` holds their current values, to roll back.
+    Update { entry: String, changes: Vec<FieldChange>, original: Value },
+
+    /// Moves an issue to another status; `from` is `None` for an issue that is created first.
+    Transition { entry: String, from: Option<String>, to: String, resolution: Option<String> },
+
+    /// Adds a missing link to an issue.
+    Link { entry: String, link: TaskLinkInfo },
+}
+
+impl fmt::Display for SyncChange {
+    /// Formats the change; the alternate flag, `{:#}`, colours field changes, see `write_changes`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncChange::Create { entry, task } => {
+                write!(f, "+ create {} in {}", entry, task.queue)?;
+                let body = serde_json::to_value(CreatedTaskBody::from(*task.clone())).unwrap_or_default();
+                for (field, value) in body.as_object().into_iter().flatten() {
+                    if !matches!(field.as_str(), "queue" | "unique") && !is_unset(value) {
+                        write!(f, "\n    + {}: {}", field, value)?;
+                    }
+                }
+                Ok(())
+            }
+            SyncChange::Update { entry, changes, .. } => {
+                write!(f, "~ update {}", entry)?;
+                write_changes(f, changes, "    ")
+            }
+            SyncChange::Transition { entry, from: Some(from), to, .. } => {
+                write!(f, "> transition {}: {} -> {}", entry, from, to)
+            }
+            SyncChange::Transition { entry, from: None, to, .. } => {
+                write!(f, "> transition {}: -> {} once created", entry, to)
+            }
+            SyncChange::Link { entry, link } => write!(
+                f,
+                "+ link {} {} {}",
+                entry,
+                serde_json::to_value(link.relationship).unwrap_or_default().as_str().unwrap_or_default(),
+                link.issue.as_deref().or(link.task.as_deref()).unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// Represents the changes that bring the tracker to the state of a state file.
+///
+/// # Fields
+///
+/// * `changes` - The changes in the order they are applied: creations, updates, transitions, then links.
+/// * `keys` - The keys of the issues of the state that already exist, by label.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SyncPlan {
+    pub changes: Vec<SyncChange>,
+    pub keys: HashMap<String, String>,
+}
+
+impl SyncPlan {
+    /// Checks if the tracker already matches the state file.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    /// Formats a summary line followed by one change per paragraph, coloured with the alternate flag.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes, the issues match the state file");
+        }
+
+        let count = |kind: fn(&SyncChange) -> bool| self.changes.iter().filter(|change| kind(change)).count();
+        writeln!(
+            f,
+            "Plan: {} to create, {} to update, {} to transition, {} link(s) to add",
+            count(|change| matches!(change, SyncChange::Create { .. })),
+            count(|change| matches!(change, SyncChange::Update { .. })),
+            count(|change| matches!(change, SyncChange::Transition { .. })),
+            count(|change| matches!(change, SyncChange::Link { .. })),
+        )?;
+        for change in &self.changes {
+            if f.alternate() {
+                writeln!(f, "\n  {:#}", change)?;
+            } else {
+                writeln!(f, "\n  {}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SyncState {
+    /// Loads a state file, written in any batch format but Markdown, e.g. **state.yaml**.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the state file.
+    /// * `format` - The format of the state file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the state, or `SyncError` if the file cannot be read or is invalid.
+    pub fn load(path: &Path, format: BatchFormat) -> Result<SyncState, SyncError> {
+        let source = fs::read_to_string(path)?;
+        let document = format.parse(&source).map_err(|err| match err.position {
+            Some((line, column)) => SyncError::InvalidState(format!("{} at line {} column {}", err.message, line, column)),
+            None => SyncError::InvalidState(err.message),
+        })?;
+        let state: SyncState = serde_path_to_error::deserialize(document)
+            .map_err(|err| SyncError::InvalidState(format!("{} at `{}`", err.inner(), err.path())))?;
+        state.validate()?;
+        Ok(state)
+    }
+
+    /// Checks that every issue has a label, that labels are unique and that links have a single target.
+    fn validate(&self) -> Result<(), SyncError> {
+        let mut labels = HashSet::new();
+        for (index, issue) in self.issues.iter().enumerate() {
+            if issue.label().is_empty() {
+                return Err(SyncError::InvalidState(format!("`issues[{}]` needs a `key` or a `unique`", index)));
+            }
+            if !labels.insert(issue.label()) {
+                return Err(SyncError::InvalidState(format!("`{}` is declared twice", issue.label())));
+            }
+            if issue.links.iter().any(|link| !link.has_single_target()) {
+                return Err(SyncError::InvalidState(format!(
+                    "every link of `{}` needs exactly one of `issue` and `task`",
+                    issue.label()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the changes that bring the current issues to the state.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The current issue of every declared issue, in the same order, `None` if it does not exist.
+    /// * `default_queue` - The queue of created issues that do not declare one.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the plan, or `SyncError::InvalidState` if an issue cannot be created.
+    pub fn diff(&self, current: &[Option<CurrentIssue>], default_queue: &str) -> Result<SyncPlan, SyncError> {
+        let keys: HashMap<String, String> = self
+            .issues
+            .iter()
+            .zip(current)
+            .filter_map(|(issue, current)| current.as_ref().map(|current| (issue.label().to_string(), current.key.clone())))
+            .collect();
+        let labels: HashSet<&str> = self.issues.iter().map(DesiredIssue::label).collect();
+
+        let mut creations = Vec::new();
+        let mut updates = Vec::new();
+        let mut transitions = Vec::new();
+        let mut links = Vec::new();
+        // The labels of the issues that exist once the creations so far are applied.
+        let mut known: HashSet<&str> = keys.keys().map(String::as_str).collect();
+
+        for (issue, current) in self.issues.iter().zip(current) {
+            let entry = issue.label().to_string();
+            if let Some(parent) = issue.parent.as_deref().filter(|parent| labels.contains(parent)) {
+                if !known.contains(parent) {
+                    return Err(SyncError::InvalidState(format!(
+                        "`{}` must be declared before its subtask `{}`",
+                        parent, entry
+                    )));
+                }
+            }
+
+            match current {
+                None => {
+                    let summary = issue.summary.clone().ok_or_else(|| {
+                        SyncError::InvalidState(format!("`{}` does not exist and needs a `summary` to be created", entry))
+                    })?;
+                    creations.push(SyncChange::Create {
+                        entry: entry.clone(),
+                        task: Box::new(CreatedTaskInfo {
+                            queue: issue.queue.clone().unwrap_or_else(|| default_queue.to_string()),
+                            summary,
+                            parent: issue.parent.clone(),
+                            epic: None,
+                            description: issue.description.clone(),
+                            sprint: issue.sprint.clone().unwrap_or_default(),
+                            task_type: issue.task_type.clone(),
+                            priority: issue.priority.clone(),
+                            followers: issue.followers.clone().unwrap_or_default(),
+                            tags: Vec::new(),
+                            components: Vec::new(),
+                            affected_versions: Vec::new(),
+                            fix_versions: Vec::new(),
+                            assignee: issue.assignee.clone(),
+                            author: None,
+                            unique: issue.unique.clone(),
+                            id: None,
+                            attachment_ids: Vec::new(),
+                            description_attachment_ids: Vec::new(),
+                            attachments: Vec::new(),
+                            description_attachments: Vec::new(),
+                            links: Vec::new(),
+                            checklist: Vec::new(),
+                            comments: Vec::new(),
+                            subtasks: Vec::new(),
+                            fields: IssueFields::default(),
+                            error: None,
+                        }),
+                    });
+                    known.insert(issue.label());
+                }
+                Some(current) => {
+                    let mut fields = issue.fields();
+                    if let Some(parent) = &issue.parent {
+                        fields.push(("parent", Value::String(parent.clone())));
+                    }
+                    let changes: Vec<FieldChange> = fields
+                        .into_iter()
+                        .filter(|(field, planned)| {
+                            let planned = match (*field, planned) {
+                                ("parent", Value::String(parent)) => {
+                                    Value::String(keys.get(parent).unwrap_or(parent).clone())
+                                }
+                                _ => planned.clone(),
+                            };
+                            !matches_current(&planned, current.fields.get(*field))
+                        })
+                        .map(|(field, planned)| FieldChange {
+                            field: field.to_string(),
+                            current: current.fields.get(field).map(plain_value).unwrap_or(Value::Null),
+                            planned,
+                        })
+                        .collect();
+                    if !changes.is_empty() {
+                        let original: Map<String, Value> = changes
+                            .iter()
+                            .map(|change| {
+                                let value = current.fields.get(change.field.as_str());
+                                (change.field.clone(), value.map(restorable_value).unwrap_or(Value::Null))
+                            })
+                            .collect();
+                        updates.push(SyncChange::Update {
+                            entry: entry.clone(),
+                            changes,
+                            original: Value::Object(original),
+                        });
+                    }
+                }
+            }
+
+            if let Some(status) = &issue.status {
+                let current_status = current.as_ref().map(|current| current.fields.get("status"));
+                if !current_status
+                    .flatten()
+                    .is_some_and(|current| matches_current(&Value::String(status.clone()), Some(current)))
+                {
+                    transitions.push(SyncChange::Transition {
+                        entry: entry.clone(),
+                        from: current_status.map(|current| {
+                            current
+                                .map(restorable_value)
+                                .and_then(|status| status.as_str().map(str::to_string))
+                                .unwrap_or_default()
+                        }),
+                        to: status.clone(),
+                        resolution: issue.resolution.clone(),
+                    });
+                }
+            }
+
+            for link in &issue.links {
+                let target = link.target(&keys);
+                let exists = current.as_ref().is_some_and(|current| {
+                    target
+                        .as_deref()
+                        .is_some_and(|target| current.links.iter().any(|existing| existing.matches(link.relationship, target)))
+                });
+                if !exists {
+                    links.push(SyncChange::Link {
+                        entry: entry.clone(),
+                        link: link.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(SyncPlan {
+            changes: creations.into_iter().chain(updates).chain(transitions).chain(links).collect(),
+            keys,
+        })
+    }
+}
+
+/// Reconciles the tracker with a state file.
+pub struct TaskSync {
+    api_client: TaskApiClient,
+}
+
+impl TaskSync {
+    /// Creates a new `TaskSync` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_client` - The API client used to read and change the issues.
+    pub fn new(api_client: TaskApiClient) -> Self {
+        TaskSync { api_client }
+    }
+
+    /// Reads the current state of the issues of a state file and computes the changes to apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The desired state.
+    /// * `default_queue` - The queue of created issues that do not declare one.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the plan, or `SyncError` if an issue cannot be read or created.
+    pub async fn plan(&self, state: &SyncState, default_queue: &str) -> Result<SyncPlan, SyncError> {
+        let mut current = Vec::new();
+        for issue in &state.issues {
+            current.push(self.read(issue).await?);
+        }
+        state.diff(&current, default_queue)
+    }
+
+    /// Reads an issue of the state, `None` if it has no key and no issue has its `unique` yet.
+    async fn read(&self, issue: &DesiredIssue) -> Result<Option<CurrentIssue>, SyncError> {
+        let fields = match (&issue.key, &issue.unique) {
+            (Some(key), _) => self.api_client.get_task_fields(key).await?,
+            (None, Some(unique)) => {
+                let found = self
+                    .api_client
+                    .search_task_fields(&serde_json::json!({ "filter": { "unique": unique } }))
+                    .await?;
+                match found.into_iter().next() {
+                    Some(fields) => fields,
+                    None => return Ok(None),
+                }
+            }
+            (None, None) => return Ok(None),
+        };
+
+        let key = fields.get("key").and_then(Value::as_str).unwrap_or(issue.label()).to_string();
+        let links = if issue.links.is_empty() {
+            Vec::new()
+        } else {
+            self.api_client.list_links(&key).await?
+        };
+        Ok(Some(CurrentIssue { key, fields, links }))
+    }
+
+    /// Applies the changes of a plan, recording every operation in the journal of the run.
+    ///
+    /// Applying stops at the first failure; planning again lists the changes that remain.
+    ///
+    /// # Arguments
+    ///
+    /// * `plan` - The changes to apply.
+    /// * `journal` - The journal of the run, which `rollback` undoes.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result`, or the error of the first failed change.
+    pub async fn apply(&self, plan: &SyncPlan, journal: &mut TaskJournal) -> Result<(), HandleResponseError> {
+        let mut keys = plan.keys.clone();
+        // The status of the issues created by this run, which may already be the desired one.
+        let mut statuses: HashMap<String, String> = HashMap::new();
+
+        for change in &plan.changes {
+            match change {
+                SyncChange::Create { entry, task } => {
+                    let task = CreatedTaskInfo {
+                        parent: task.parent.as_ref().map(|parent| keys.get(parent).unwrap_or(parent).clone()),
+                        ..*task.clone()
+                    };
+                    let result = self.api_client.create_task(CreatedTaskBody::from(task)).await;
+                    match &result {
+                        Ok(response) => journal.record(entry, JournalAction::Create, Ok(&response.key))?,
+                        Err(err) => journal.record(entry, JournalAction::Create, Err(err))?,
+                    }
+                    let response = result?;
+                    keys.insert(entry.clone(), response.key);
+                    statuses.insert(entry.clone(), response.status.key);
+                }
+                SyncChange::Update { entry, changes, original } => {
+                    let key = issue_key(&keys, entry);
+                    let fields: Map<String, Value> = changes
+                        .iter()
+                        .map(|change| match (change.field.as_str(), &change.planned) {
+                            ("parent", Value::String(parent)) => {
+                                (change.field.clone(), Value::String(issue_key(&keys, parent)))
+                            }
+                            _ => (change.field.clone(), change.planned.clone()),
+                        })
+                        .collect();
+
+                    journal.record_snapshot(entry, &key, original.clone())?;
+                    let result = self.api_client.patch_task_fields(&key, &Value::Object(fields)).await;
+                    journal.record(entry, JournalAction::Update, result.as_ref().map(|_| key.as_str()))?;
+                    result?;
+                }
+                SyncChange::Transition { entry, to, resolution, .. } => {
+                    if statuses.get(entry).is_some_and(|status| status.eq_ignore_ascii_case(to)) {
+                        continue;
+                    }
+                    let key = issue_key(&keys, entry);
+                    let result = self.transition(&key, to, resolution.as_deref()).await;
+                    journal.record(entry, JournalAction::Transition, result.as_ref().map(|_| key.as_str()))?;
+                    result?;
+                }
+                SyncChange::Link { entry, link } => {
+                    let key = issue_key(&keys, entry);
+                    let target = link
+                        .target(&keys)
+                        .ok_or_else(|| HandleResponseError::UnresolvedLink(link.task.clone().unwrap_or_default()))?;
+                    let body = CreatedLinkBody {
+                        relationship: link.relationship,
+                        issue: target,
+                    };
+                    let result = self.api_client.create_link(&key, body).await;
+                    journal.record(entry, JournalAction::Link, result.as_ref().map(|_| key.as_str()))?;
+                    result?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves an issue to a status through the transition of its workflow that leads there.
+    async fn transition(&self, key: &str, status: &str, resolution: Option<&str>) -> Result<(), HandleResponseError> {
+        let transition = self
+            .api_client
+            .get_transitions(key)
+            .await?
+            .into_iter()
+            .find(|transition| transition.to.matches(status) || transition.id == status)
+            .ok_or_else(|| HandleResponseError::NoTransition(key.to_string(), status.to_string()))?;
+        self.api_client
+            .execute_transition(key, &transition.id, resolution)
+            .await
+    }
+}
+
+/// Resolves the label of an issue of the state to its key, labels that are not known being keys.
+fn issue_key(keys: &HashMap<String, String>, label: &str) -> String {
+    keys.get(label).cloned().unwrap_or_else(|| label.to_string())
+}
+
+/// Checks if a field of a request body leaves nothing to send.
+fn is_unset(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(values) => values.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::task::task_link::LinkRelationship;
+    use serde_json::json;
+
+    fn state() -> SyncState {
+        serde_yaml::from_str(
+            r#"
+issues:
+  - unique: checkout-epic
+    queue: TEST
+    summary: Checkout redesign
+    type: epic
+    status: inProgress
+  - key: TEST-42
+    summary: Validate cards
+    parent: checkout-epic
+    followers: [jdoe, asmith]
+    status: open
+    links:
+      - relationship: relates
+        issue: TEST-7
+      - relationship: has epic
+        task: checkout-epic
+"#,
+        )
+        .unwrap()
+    }
+
+    fn current_issue() -> CurrentIssue {
+        CurrentIssue {
+            key: "TEST-42".to_string(),
+            fields: json!({
+                "key": "TEST-42",
+                "summary": "Validate card numbers",
+                "parent": { "key": "TEST-1", "display": "Payments" },
+                "followers": [{ "id": "11", "display": "asmith" }, { "id": "12", "display": "jdoe" }],
+                "status": { "key": "open", "display": "Open" }
+            }),
+            links: serde_json::from_value(json!([{
+                "self": "https://api.tracker.yandex.net/v2/issues/TEST-42/links/1",
+                "id": 1,
+                "type": { "id": "relates", "inward": "relates", "outward": "relates" },
+                "direction": "outward",
+                "object": { "self": "https://api.tracker.yandex.net/v2/issues/TEST-7", "id": "7", "key": "TEST-7", "display": "Cards" }
+            }]))
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_diff_state() {
+        let plan = state().diff(&[None, Some(current_issue())], "DEFAULT").unwrap();
+
+        assert_eq!(plan.changes.len(), 4);
+        match &plan.changes[0] {
+            SyncChange::Create { entry, task } => {
+                assert_eq!(entry, "checkout-epic");
+                assert_eq!(task.queue, "TEST");
+                assert_eq!(task.unique.as_deref(), Some("checkout-epic"));
+            }
+            change => panic!("unexpected change {:?}", change),
+        }
+        match &plan.changes[1] {
+            SyncChange::Update { entry, changes, original } => {
+                assert_eq!(entry, "TEST-42");
+                let fields: Vec<&str> = changes.iter().map(|change| change.field.as_str()).collect();
+                assert_eq!(fields, vec!["summary", "parent"]);
+                assert_eq!(original, &json!({ "summary": "Validate card numbers", "parent": "TEST-1" }));
+            }
+            change => panic!("unexpected change {:?}", change),
+        }
+        assert!(matches!(&plan.changes[2], SyncChange::Transition { from: None, to, .. } if to == "inProgress"));
+        assert!(matches!(&plan.changes[3], SyncChange::Link { link, .. } if link.task.as_deref() == Some("checkout-epic")));
+    }
+
+    #[test]
+    fn test_diff_state_without_changes() {
+        let mut epic = current_issue();
+        epic.key = "TEST-40".to_string();
+        epic.fields = json!({
+            "key": "TEST-40",
+            "summary": "Checkout redesign",
+            "type": { "key": "epic", "display": "Epic" },
+            "status": { "key": "inProgress", "display": "In progress" }
+        });
+        let mut issue = current_issue();
+        issue.fields["summary"] = json!("Validate cards");
+        issue.fields["parent"] = json!({ "key": "TEST-40", "display": "Checkout redesign" });
+        let relates = IssueLink {
+            object: serde_json::from_value(json!({ "self": "", "id": "40", "key": "TEST-40", "display": "" })).unwrap(),
+            ..issue.links[0].clone()
+        };
+        let mut related = issue.clone();
+        related.links.push(relates.clone());
+        issue.links.push(IssueLink {
+            link_type: serde_json::from_value(json!({ "id": "epic", "inward": "is epic of", "outward": "has epic" }))
+                .unwrap(),
+            ..relates
+        });
+
+        let plan = state().diff(&[Some(epic.clone()), Some(issue)], "DEFAULT").unwrap();
+
+        assert!(plan.is_empty(), "{}", plan);
+
+        let plan = state().diff(&[Some(epic), Some(related)], "DEFAULT").unwrap();
+
+        assert_eq!(plan.changes.len(), 1);
+        assert!(matches!(&plan.changes[0], SyncChange::Link { link, .. } if link.relationship == LinkRelationship::HasEpic));
+    }
+
+    #[test]
+    fn test_invalid_state() {
+        let mut state = state();
+        state.issues.swap(0, 1);
+        let err = state.diff(&[Some(current_issue()), None], "DEFAULT").unwrap_err();
+        assert!(err.to_string().contains("must be declared before its subtask"));
+
+        state.issues[1].summary = None;
+        state.issues.swap(0, 1);
+        let err = state.diff(&[None, Some(current_issue())], "DEFAULT").unwrap_err();
+        assert!(err.to_string().contains("needs a `summary`"));
+    }
+}
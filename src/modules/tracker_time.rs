@@ -0,0 +1,290 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+/// The date-time format used by the Yandex Tracker API, e.g. `2024-05-01T15:30:00.000+0300`.
+pub const TRACKER_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%z";
+
+/// Represents errors that can occur while parsing Tracker time values.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TrackerTimeError {
+    /// The duration is neither an ISO-8601 duration nor a duration like `1h30m`.
+    #[error("Invalid duration: {0}")]
+    InvalidDuration(String),
+
+    /// The date-time is neither in the Tracker format nor in RFC 3339.
+    #[error("Invalid date-time: {0}")]
+    InvalidDateTime(String),
+}
+
+/// Represents a duration of work tracked in Yandex Tracker.
+///
+/// Tracker counts durations in working time: a day is 8 hours and a week is 5 days.
+/// The duration is serialized as an ISO-8601 duration such as `PT1H30M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct TrackerDuration {
+    seconds: u64,
+}
+
+impl TrackerDuration {
+    /// The number of seconds in a working day.
+    pub const SECONDS_PER_DAY: u64 = 8 * 60 * 60;
+
+    /// The number of seconds in a working week.
+    pub const SECONDS_PER_WEEK: u64 = 5 * Self::SECONDS_PER_DAY;
+
+    /// Creates a duration from a number of seconds.
+    pub fn from_seconds(seconds: u64) -> Self {
+        TrackerDuration { seconds }
+    }
+
+    /// Returns the duration in seconds.
+    pub fn as_seconds(&self) -> u64 {
+        self.seconds
+    }
+
+    /// Checks if the duration is zero.
+    pub fn is_zero(&self) -> bool {
+        self.seconds == 0
+    }
+
+    /// Parses a duration.
+    ///
+    /// Both ISO-8601 durations (`PT1H30M`, `P1DT2H`) and compact durations with the
+    /// `w`, `d`, `h`, `m` and `s` units (`1h30m`, `2d 4h`) are accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The duration to parse.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<TrackerDuration, TrackerTimeError>` - The parsed duration or an error.
+    pub fn parse(input: &str) -> Result<Self, TrackerTimeError> {
+        let trimmed = input.trim();
+        let invalid = || TrackerTimeError::InvalidDuration(input.to_string());
+
+        match trimmed.strip_prefix('P') {
+            Some(iso) => Self::parse_iso8601(iso).ok_or_else(invalid),
+            None => Self::parse_compact(trimmed).ok_or_else(invalid),
+        }
+    }
+
+    /// Parses the part of an ISO-8601 duration that follows the leading `P`.
+    fn parse_iso8601(input: &str) -> Option<Self> {
+        let (date, time) = match input.split_once('T') {
+            Some((date, time)) if !time.is_empty() => (date, Some(time)),
+            Some(_) => return None,
+            None => (input, None),
+        };
+        if date.is_empty() && time.is_none() {
+            return None;
+        }
+
+        let date_seconds = Self::sum_units(date, |unit| match unit {
+            'W' => Some(Self::SECONDS_PER_WEEK),
+            'D' => Some(Self::SECONDS_PER_DAY),
+            _ => None,
+        })?;
+        let time_seconds = match time {
+            Some(time) => Self::sum_units(time, |unit| match unit {
+                'H' => Some(60 * 60),
+                'M' => Some(60),
+                'S' => Some(1),
+                _ => None,
+            })?,
+            None => 0,
+        };
+
+        Some(TrackerDuration::from_seconds(date_seconds + time_seconds))
+    }
+
+    /// Parses a compact duration such as `1h30m`.
+    fn parse_compact(input: &str) -> Option<Self> {
+        let compact: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+        if compact.is_empty() {
+            return None;
+        }
+
+        Self::sum_units(&compact.to_lowercase(), |unit| match unit {
+            'w' => Some(Self::SECONDS_PER_WEEK),
+            'd' => Some(Self::SECONDS_PER_DAY),
+            'h' => Some(60 * 60),
+            'm' => Some(60),
+            's' => Some(1),
+            _ => None,
+        })
+        .map(TrackerDuration::from_seconds)
+    }
+
+    /// Sums a sequence of `<number><unit>` pairs, each unit weighted by `unit_seconds`.
+    fn sum_units(input: &str, unit_seconds: impl Fn(char) -> Option<u64>) -> Option<u64> {
+        let mut total = 0u64;
+        let mut number = String::new();
+
+        for c in input.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+            } else {
+                let value: u64 = number.parse().ok()?;
+                total = total.checked_add(value.checked_mul(unit_seconds(c)?)?)?;
+                number.clear();
+            }
+        }
+
+        match number.is_empty() {
+            true => Some(total),
+            false => None,
+        }
+    }
+
+    /// Formats the duration as an ISO-8601 duration in hours, minutes and seconds.
+    ///
+    /// # Returns
+    ///
+    /// A `String` such as `PT1H30M`, or `PT0S` for a zero duration.
+    pub fn to_iso8601(&self) -> String {
+        if self.seconds == 0 {
+            return "PT0S".to_string();
+        }
+
+        let (hours, minutes, seconds) = self.split();
+        let mut iso = "PT".to_string();
+        if hours > 0 {
+            iso.push_str(&format!("{}H", hours));
+        }
+        if minutes > 0 {
+            iso.push_str(&format!("{}M", minutes));
+        }
+        if seconds > 0 {
+            iso.push_str(&format!("{}S", seconds));
+        }
+        iso
+    }
+
+    /// Splits the duration into hours, minutes and seconds.
+    fn split(&self) -> (u64, u64, u64) {
+        (self.seconds / 3600, self.seconds % 3600 / 60, self.seconds % 60)
+    }
+}
+
+impl fmt::Display for TrackerDuration {
+    /// Formats the duration in the compact form, e.g. `1h30m`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (hours, minutes, seconds) = self.split();
+        let mut parts = Vec::new();
+        if hours > 0 {
+            parts.push(format!("{}h", hours));
+        }
+        if minutes > 0 {
+            parts.push(format!("{}m", minutes));
+        }
+        if seconds > 0 || parts.is_empty() {
+            parts.push(format!("{}s", seconds));
+        }
+        write!(f, "{}", parts.concat())
+    }
+}
+
+impl FromStr for TrackerDuration {
+    type Err = TrackerTimeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TrackerDuration::parse(s)
+    }
+}
+
+impl Serialize for TrackerDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_iso8601())
+    }
+}
+
+impl<'de> Deserialize<'de> for TrackerDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        TrackerDuration::parse(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Parses a date-time in the Tracker format or in RFC 3339.
+///
+/// # Arguments
+///
+/// * `input` - The date-time to parse.
+///
+/// # Returns
+///
+/// * `Result<DateTime<FixedOffset>, TrackerTimeError>` - The parsed date-time or an error.
+pub fn parse_tracker_datetime(input: &str) -> Result<DateTime<FixedOffset>, TrackerTimeError> {
+    DateTime::parse_from_str(input, TRACKER_DATETIME_FORMAT)
+        .or_else(|_| DateTime::parse_from_rfc3339(input))
+        .map_err(|_| TrackerTimeError::InvalidDateTime(input.to_string()))
+}
+
+/// Serializes and deserializes `DateTime<FixedOffset>` in the Tracker format.
+///
+/// Use with `#[serde(with = "tracker_datetime")]`.
+pub mod tracker_datetime {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &DateTime<FixedOffset>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.format(TRACKER_DATETIME_FORMAT).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<FixedOffset>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        parse_tracker_datetime(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_compact_duration() {
+        assert_eq!(TrackerDuration::parse("1h30m").unwrap().as_seconds(), 5400);
+        assert_eq!(TrackerDuration::parse("2d 4h").unwrap().as_seconds(), 20 * 3600);
+        assert_eq!(TrackerDuration::parse("1w").unwrap().as_seconds(), 40 * 3600);
+        assert_eq!(TrackerDuration::parse("45M").unwrap().as_seconds(), 45 * 60);
+        assert!(TrackerDuration::parse("").is_err());
+        assert!(TrackerDuration::parse("90").is_err());
+        assert!(TrackerDuration::parse("1x").is_err());
+    }
+
+    #[test]
+    fn test_parse_iso8601_duration() {
+        assert_eq!(TrackerDuration::parse("PT1H30M").unwrap().as_seconds(), 5400);
+        assert_eq!(TrackerDuration::parse("P1DT2H").unwrap().as_seconds(), 10 * 3600);
+        assert_eq!(TrackerDuration::parse("P1W").unwrap().as_seconds(), 40 * 3600);
+        assert!(TrackerDuration::parse("P").is_err());
+        assert!(TrackerDuration::parse("PT").is_err());
+        assert!(TrackerDuration::parse("P1H").is_err());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        let duration = TrackerDuration::parse("1h30m").unwrap();
+        assert_eq!(duration.to_iso8601(), "PT1H30M");
+        assert_eq!(duration.to_string(), "1h30m");
+        assert_eq!(TrackerDuration::default().to_iso8601(), "PT0S");
+        assert_eq!(serde_json::to_string(&duration).unwrap(), "\"PT1H30M\"");
+    }
+
+    #[test]
+    fn test_parse_tracker_datetime() {
+        let tracker = parse_tracker_datetime("2024-05-01T15:30:00.000+0300").unwrap();
+        let rfc3339 = parse_tracker_datetime("2024-05-01T15:30:00+03:00").unwrap();
+
+        assert_eq!(tracker, rfc3339);
+        assert_eq!(
+            tracker.format(TRACKER_DATETIME_FORMAT).to_string(),
+            "2024-05-01T15:30:00.000+0300"
+        );
+        assert!(parse_tracker_datetime("yesterday").is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::queue::deserialize_id;
+use crate::modules::task::task_api_client::{HandleResponseError, TaskApiClient};
+
+/// Represents a user of the organization in Yandex Tracker.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrackerUser {
+    /// The unique identifier of the user.
+    #[serde(deserialize_with = "deserialize_id")]
+    pub uid: String,
+
+    /// The login of the user.
+    pub login: String,
+
+    /// The display name of the user.
+    #[serde(default)]
+    pub display: String,
+
+    /// The email of the user.
+    #[serde(default)]
+    pub email: Option<String>,
+
+    /// Whether the user is dismissed from the organization.
+    #[serde(default)]
+    pub dismissed: bool,
+}
+
+impl TaskApiClient {
+    /// Reads a user of the organization.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The login or the unique identifier of the user.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the user, or `HandleResponseError` if an error occurred.
+    pub async fn get_user(&self, user: &str) -> Result<TrackerUser, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/users/{}", user))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+}
@@ -0,0 +1,239 @@
+pub mod worklog_error;
+pub mod worklog_timer;
+
+use chrono::{DateTime, FixedOffset};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::task::success_response::User;
+use crate::modules::task::task_api_client::{HandleResponseError, TaskApiClient};
+use crate::modules::tracker_time::{tracker_datetime, TrackerDuration};
+
+/// Represents a worklog record of an issue returned by the Yandex Tracker API.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Worklog {
+    /// The URL of the worklog record.
+    ///
+    /// This field is serialized/deserialized as `self`.
+    #[serde(rename = "self")]
+    pub url: String,
+
+    /// The identifier of the worklog record.
+    pub id: u64,
+
+    /// The issue the work was logged on.
+    pub issue: WorklogIssue,
+
+    /// The comment of the worklog record.
+    pub comment: Option<String>,
+
+    /// Information about the user who logged the work.
+    ///
+    /// This field is serialized/deserialized as `createdBy`.
+    #[serde(rename = "createdBy")]
+    pub created_by: User,
+
+    /// The timestamp when the record was created.
+    ///
+    /// This field is serialized/deserialized as `createdAt`.
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+
+    /// The timestamp when the record was last updated.
+    ///
+    /// This field is serialized/deserialized as `updatedAt`.
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<String>,
+
+    /// The time the work was started.
+    #[serde(with = "tracker_datetime")]
+    pub start: DateTime<FixedOffset>,
+
+    /// The time spent.
+    pub duration: TrackerDuration,
+}
+
+/// Represents the issue of a worklog record.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorklogIssue {
+    /// The URL of the issue.
+    ///
+    /// This field is serialized/deserialized as `self`.
+    #[serde(rename = "self")]
+    pub url: String,
+
+    /// The unique identifier of the issue.
+    pub id: String,
+
+    /// The key of the issue.
+    pub key: String,
+
+    /// The display name of the issue.
+    pub display: String,
+}
+
+/// Represents the body of a worklog creation request.
+///
+/// # Fields
+///
+/// * `start` - The time the work was started.
+/// * `duration` - The time spent.
+/// * `comment` - A comment on the work (optional).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatedWorklogBody {
+    #[serde(with = "tracker_datetime")]
+    pub start: DateTime<FixedOffset>,
+    pub duration: TrackerDuration,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// Represents the body of a worklog update request.
+///
+/// # Fields
+///
+/// * `duration` - The new time spent (optional).
+/// * `comment` - The new comment (optional).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UpdatedWorklogBody {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<TrackerDuration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// Represents a worklog search by author and date range.
+///
+/// # Fields
+///
+/// * `created_by` - The login or ID of the user who logged the work (optional).
+/// * `created_at` - The range of record creation times (optional).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WorklogQuery {
+    #[serde(rename = "createdBy", default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(rename = "createdAt", default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTimeRange>,
+}
+
+/// Represents a range of date-times, bounded on either side.
+///
+/// # Fields
+///
+/// * `from` - The start of the range (optional).
+/// * `to` - The end of the range (optional).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DateTimeRange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+impl TaskApiClient {
+    /// Lists the worklog records of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the worklog records, or `HandleResponseError` if an error occurred.
+    pub async fn list_worklogs(&self, issue_id: &str) -> Result<Vec<Worklog>, HandleResponseError> {
+        let response = self
+            .request(Method::GET, &format!("/issues/{}/worklog", issue_id))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Logs work on an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `worklog` - The start time, duration and comment of the work.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the created worklog record, or `HandleResponseError` if an error occurred.
+    pub async fn add_worklog(
+        &self,
+        issue_id: &str,
+        worklog: CreatedWorklogBody,
+    ) -> Result<Worklog, HandleResponseError> {
+        let response = self
+            .request(Method::POST, &format!("/issues/{}/worklog", issue_id))
+            .json(&worklog)
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Updates a worklog record of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `worklog_id` - The identifier of the worklog record.
+    /// * `worklog` - The new duration and comment.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the updated worklog record, or `HandleResponseError` if an error occurred.
+    pub async fn update_worklog(
+        &self,
+        issue_id: &str,
+        worklog_id: u64,
+        worklog: UpdatedWorklogBody,
+    ) -> Result<Worklog, HandleResponseError> {
+        let response = self
+            .request(Method::PATCH, &format!("/issues/{}/worklog/{}", issue_id, worklog_id))
+            .json(&worklog)
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+
+    /// Deletes a worklog record of an issue.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue_id` - The key or ID of the issue.
+    /// * `worklog_id` - The identifier of the worklog record.
+    ///
+    /// # Returns
+    ///
+    /// An empty `Result`, or `HandleResponseError` if an error occurred.
+    pub async fn delete_worklog(&self, issue_id: &str, worklog_id: u64) -> Result<(), HandleResponseError> {
+        let response = self
+            .request(Method::DELETE, &format!("/issues/{}/worklog/{}", issue_id, worklog_id))
+            .send()
+            .await?;
+
+        TaskApiClient::handle_empty_response(response).await
+    }
+
+    /// Searches worklog records by author and date range.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The author and the range of record creation times.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the matching worklog records, or `HandleResponseError` if an error occurred.
+    pub async fn search_worklogs(&self, query: &WorklogQuery) -> Result<Vec<Worklog>, HandleResponseError> {
+        let response = self
+            .request(Method::POST, "/worklog/_search")
+            .json(query)
+            .send()
+            .await?;
+
+        TaskApiClient::handle_response(response).await
+    }
+}
@@ -0,0 +1,35 @@
+use thiserror::Error;
+
+use crate::modules::tracker_time::TrackerTimeError;
+
+/// Represents errors that can occur while tracking time locally.
+///
+/// # Variants
+///
+/// * `AlreadyRunning` - Indicates that a timer is already running for an issue.
+/// * `NotRunning` - Indicates that no timer is running.
+/// * `IOError` - Indicates an error reading or writing the timer file.
+/// * `ParseError` - Indicates an error parsing the timer file.
+/// * `TimeError` - Indicates an invalid duration or date-time.
+#[derive(Error, Debug)]
+pub enum WorklogError {
+    /// A timer is already running.
+    #[error("A timer is already running for {0}")]
+    AlreadyRunning(String),
+
+    /// No timer is running.
+    #[error("No timer is running")]
+    NotRunning,
+
+    /// Error reading or writing the timer file.
+    #[error("Timer file error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    /// Error parsing the timer file.
+    #[error("Timer file parse error: {0}")]
+    ParseError(#[from] serde_json::Error),
+
+    /// Invalid duration or date-time.
+    #[error("{0}")]
+    TimeError(#[from] TrackerTimeError),
+}
@@ -0,0 +1,115 @@
+use chrono::{DateTime, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::worklog_error::WorklogError;
+use super::CreatedWorklogBody;
+use crate::modules::tracker_time::{tracker_datetime, TrackerDuration};
+
+/// Represents a running timer persisted between invocations of the CLI.
+///
+/// # Fields
+///
+/// * `issue` - The key of the issue the time is tracked on.
+/// * `started_at` - The time the timer was started.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WorklogTimer {
+    pub issue: String,
+    #[serde(with = "tracker_datetime")]
+    pub started_at: DateTime<FixedOffset>,
+}
+
+impl WorklogTimer {
+    /// The filename to store the running timer.
+    pub const FILENAME: &'static str = "timer.json";
+
+    /// Checks if a timer is running.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - if the timer file exists.
+    /// * `false` - if the timer file does not exist.
+    pub fn is_running() -> bool {
+        Path::new(Self::FILENAME).exists()
+    }
+
+    /// Starts a timer for an issue and saves it to the timer file.
+    ///
+    /// # Arguments
+    ///
+    /// * `issue` - The key of the issue.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<WorklogTimer, WorklogError>` - The started timer, or `WorklogError::AlreadyRunning`
+    ///   if another timer is running.
+    pub fn start(issue: String) -> Result<WorklogTimer, WorklogError> {
+        if Self::is_running() {
+            return Err(WorklogError::AlreadyRunning(Self::load_from_file()?.issue));
+        }
+
+        let timer = WorklogTimer {
+            issue,
+            started_at: Local::now().fixed_offset(),
+        };
+        fs::write(Self::FILENAME, serde_json::to_string_pretty(&timer)?)?;
+        Ok(timer)
+    }
+
+    /// Loads the running timer from the timer file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<WorklogTimer, WorklogError>` - The running timer, or `WorklogError::NotRunning`.
+    pub fn load_from_file() -> Result<WorklogTimer, WorklogError> {
+        if !Self::is_running() {
+            return Err(WorklogError::NotRunning);
+        }
+
+        let json = fs::read_to_string(Self::FILENAME)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Removes the timer file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), WorklogError>` - An empty result, or an error if the file cannot be removed.
+    pub fn remove_file() -> Result<(), WorklogError> {
+        fs::remove_file(Self::FILENAME)?;
+        Ok(())
+    }
+
+    /// Returns the time elapsed since the timer was started, rounded down to whole minutes.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    ///
+    /// The elapsed `TrackerDuration`, at least one minute.
+    pub fn elapsed(&self, now: DateTime<FixedOffset>) -> TrackerDuration {
+        let minutes = (now - self.started_at).num_minutes().max(1) as u64;
+        TrackerDuration::from_seconds(minutes * 60)
+    }
+
+    /// Builds the worklog record for the time tracked by the timer.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The time the timer is stopped.
+    /// * `comment` - A comment on the work (optional).
+    ///
+    /// # Returns
+    ///
+    /// A `CreatedWorklogBody` starting when the timer was started.
+    pub fn to_worklog(&self, now: DateTime<FixedOffset>, comment: Option<String>) -> CreatedWorklogBody {
+        CreatedWorklogBody {
+            start: self.started_at,
+            duration: self.elapsed(now),
+            comment,
+        }
+    }
+}
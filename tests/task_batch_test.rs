@@ -1,30 +1,49 @@
 use yandex_tracker_api_rust::modules::task::{CreatedTaskInfo, UpdatedTaskInfo};
 use yandex_tracker_api_rust::modules::task::task_batch::TaskBatch;
-use std::collections::HashSet;
+use yandex_tracker_api_rust::modules::task::task_fields::IssueFields;
+use yandex_tracker_api_rust::modules::task::task_list_update::ListUpdate;
+use std::collections::HashMap;
 
 #[test]
 fn test_create_task() {
-    let mut created_tasks = HashSet::new();
+    let mut created_tasks = Vec::new();
     let task = CreatedTaskInfo {
         queue: String::from("QUEUE-1"),
         summary: String::from("Test task"),
         parent: None,
+        epic: None,
         description: Some(String::from("Test description")),
         sprint: vec![String::from("Sprint 1")],
         task_type: Some(String::from("Task")),
         priority: Some(String::from("High")),
         followers: vec![String::from("follower1")],
+        tags: vec![String::from("backend")],
+        components: Vec::new(),
+        affected_versions: Vec::new(),
+        fix_versions: Vec::new(),
         assignee: Some(String::from("assignee1")),
         author: Some(String::from("author1")),
         unique: Some(String::from("unique1")),
+        id: None,
         attachment_ids: vec![String::from("attachment1")],
-        subtasks: HashSet::new(),
+        description_attachment_ids: Vec::new(),
+        attachments: Vec::new(),
+        description_attachments: Vec::new(),
+        links: Vec::new(),
+        checklist: Vec::new(),
+        comments: Vec::new(),
+        subtasks: Vec::new(),
+        fields: IssueFields::default(),
+        error: None,
     };
-    created_tasks.insert(task);
+    created_tasks.push(task);
 
     let task_batch = TaskBatch {
         created: created_tasks,
-        updated: HashSet::new(),
+        updated: Vec::new(),
+        links: Vec::new(),
+        resolved: HashMap::new(),
+        ids: HashMap::new(),
     };
 
     assert!(task_batch.created.len() == 1);
@@ -33,24 +52,40 @@ fn test_create_task() {
 
 #[test]
 fn test_update_task() {
-    let mut updated_tasks = HashSet::new();
+    let mut updated_tasks = Vec::new();
     let updated_task = UpdatedTaskInfo {
         issue_id: String::from("TESTAPI-1"),
         summary: Some(String::from("Updated summary")),
         parent: None,
+        epic: None,
         description: Some(String::from("Updated description")),
-        sprint: Some(String::from("Sprint 2")),
+        sprint: ListUpdate::set(vec![String::from("Sprint 2")]),
         task_type: Some(String::from("Bug")),
         priority: Some(String::from("Low")),
-        followers: vec![String::from("follower2")],
+        followers: ListUpdate::set(vec![String::from("follower2")]),
+        tags: ListUpdate::default(),
+        components: ListUpdate::default(),
+        affected_versions: ListUpdate::default(),
+        fix_versions: ListUpdate::default(),
         attachment_ids: vec![String::from("attachment2")],
         description_attachment_ids: vec![String::from("desc_attachment1")],
+        attachments: Vec::new(),
+        description_attachments: Vec::new(),
+        links: Vec::new(),
+        checklist: Vec::new(),
+        comments: Vec::new(),
+        version: None,
+        fields: IssueFields::default(),
+        error: None,
     };
-    updated_tasks.insert(updated_task);
+    updated_tasks.push(updated_task);
 
     let task_batch = TaskBatch {
-        created: HashSet::new(),
+        created: Vec::new(),
         updated: updated_tasks,
+        links: Vec::new(),
+        resolved: HashMap::new(),
+        ids: HashMap::new(),
     };
 
     assert!(task_batch.updated.len() == 1);